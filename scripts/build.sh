@@ -104,6 +104,7 @@ fi
 
 if [ "$KIME_BUILD_CANDIDATE_WINDOW" -eq "1" ]; then
     KIME_RUST_PKGS+=("-pkime-candidate-window")
+    KIME_RUST_PKGS+=("-pkime-hanja")
 fi
 
 if [ "$KIME_BUILD_XIM" -eq "1" ]; then
@@ -123,6 +124,7 @@ cargo_build "${KIME_RUST_PKGS[@]}"
 cp $TARGET_DIR/libkime_engine.so $KIME_OUT || true
 cp $TARGET_DIR/kime-check $KIME_OUT || true
 cp $TARGET_DIR/kime-candidate-window $KIME_OUT || true
+cp $TARGET_DIR/kime-hanja $KIME_OUT || true
 cp $TARGET_DIR/kime-indicator $KIME_OUT || true
 cp $TARGET_DIR/kime-xim $KIME_OUT || true
 cp $TARGET_DIR/kime-wayland $KIME_OUT || true
@@ -6,6 +6,8 @@ bitflags::bitflags! {
         const HAS_PREEDIT = 0b100;
         const HAS_COMMIT = 0b1000;
         const NOT_READY = 0b10000;
+        const DELETE_SURROUNDING = 0b100000;
+        const BYPASS_KEYS = 0b1000000;
     }
 }
 
@@ -14,10 +14,19 @@ bitflags::bitflags! {
         const CONTROL = 0x2;
         const SUPER = 0x4;
         const ALT = 0x8;
+        const CAPSLOCK = 0x10;
+        const NUMLOCK = 0x20;
+        /// AltGr
+        const LEVEL3 = 0x40;
     }
 }
 
-// TODO: complete
+impl ModifierState {
+    /// Lock modifiers which are ignored by hotkeys
+    pub const LOCKS: Self = Self::from_bits_truncate(Self::CAPSLOCK.bits() | Self::NUMLOCK.bits());
+}
+
+/// Keys of evdev except for keys which aren't on keyboards e.g. buttons of gamepads
 #[repr(u32)]
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, EnumString, Display, Enum)]
 pub enum KeyCode {
@@ -122,21 +131,185 @@ pub enum KeyCode {
     F10,
     F11,
     F12,
+
+    // Lock keys
+    CapsLock,
+    NumLock,
+    ScrollLock,
+
+    // Keypad
+    Kp0,
+    Kp1,
+    Kp2,
+    Kp3,
+    Kp4,
+    Kp5,
+    Kp6,
+    Kp7,
+    Kp8,
+    Kp9,
+    KpPeriod,
+    KpComma,
+    KpJpComma,
+    KpPlus,
+    KpMinus,
+    KpMultiply,
+    KpDivide,
+    KpEqual,
+    KpPlusMinus,
+    KpEnter,
+    KpLeftParen,
+    KpRightParen,
+
+    // Other keys of main block
+    SuperL,
+    SuperR,
+    Menu,
+    Compose,
+    PrintScreen,
+    Pause,
+    LineFeed,
+    IntlBackslash,
+    IntlRo,
+    IntlYen,
+    ZenkakuHankaku,
+    Katakana,
+    Hiragana,
+    KatakanaHiragana,
+
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+
+    // Media and system keys
+    Macro,
+    Power,
+    Scale,
+    Stop,
+    Again,
+    Props,
+    Undo,
+    Front,
+    Copy,
+    Open,
+    Paste,
+    Find,
+    Cut,
+    Help,
+    Calc,
+    Setup,
+    Sleep,
+    WakeUp,
+    File,
+    SendFile,
+    DeleteFile,
+    Xfer,
+    Prog1,
+    Prog2,
+    Www,
+    MsDos,
+    ScreenLock,
+    RotateDisplay,
+    CycleWindows,
+    Mail,
+    Bookmarks,
+    Computer,
+    Back,
+    Forward,
+    CloseCd,
+    EjectCd,
+    EjectCloseCd,
+    NextSong,
+    PlayPause,
+    PreviousSong,
+    StopCd,
+    Record,
+    Rewind,
+    Phone,
+    Iso,
+    Config,
+    Homepage,
+    Refresh,
+    Exit,
+    Move,
+    Edit,
+    ScrollUp,
+    ScrollDown,
+    New,
+    Redo,
+    PlayCd,
+    PauseCd,
+    Prog3,
+    Prog4,
+    AllApplications,
+    Suspend,
+    Close,
+    Play,
+    FastForward,
+    BassBoost,
+    Print,
+    Hp,
+    Camera,
+    Sound,
+    Question,
+    Email,
+    Chat,
+    Search,
+    Connect,
+    Finance,
+    Sport,
+    Shop,
+    AltErase,
+    Cancel,
+    BrightnessDown,
+    BrightnessUp,
+    Media,
+    SwitchVideoMode,
+    KbdIllumToggle,
+    KbdIllumDown,
+    KbdIllumUp,
+    Send,
+    Reply,
+    ForwardMail,
+    Save,
+    Documents,
+    Battery,
+    Bluetooth,
+    Wlan,
+    Uwb,
+    Unknown,
+    VideoNext,
+    VideoPrev,
+    BrightnessCycle,
+    BrightnessAuto,
+    DisplayOff,
+    Wwan,
+    RfKill,
+    MicMute,
 }
 
 impl KeyCode {
     pub const fn from_hardward_code(code: u16) -> Option<Self> {
         match code {
-            10 | 87 => Some(Self::One),
-            11 | 88 => Some(Self::Two),
-            12 | 89 => Some(Self::Three),
-            13 | 83 => Some(Self::Four),
-            14 | 84 => Some(Self::Five),
-            15 | 85 => Some(Self::Six),
-            16 | 79 => Some(Self::Seven),
-            17 | 80 => Some(Self::Eight),
-            18 | 81 => Some(Self::Nine),
-            19 | 90 => Some(Self::Zero),
+            10 => Some(Self::One),
+            11 => Some(Self::Two),
+            12 => Some(Self::Three),
+            13 => Some(Self::Four),
+            14 => Some(Self::Five),
+            15 => Some(Self::Six),
+            16 => Some(Self::Seven),
+            17 => Some(Self::Eight),
+            18 => Some(Self::Nine),
+            19 => Some(Self::Zero),
             20 => Some(Self::Minus),
             21 => Some(Self::Equal),
             34 => Some(Self::OpenBracket),
@@ -198,6 +371,7 @@ impl KeyCode {
             102 => Some(Self::Muhenkan),
             64 => Some(Self::AltL),
             108 => Some(Self::AltR),
+            // Mute, VolumeDown and VolumeUp are used for Korean keys on some keyboards
             122 | 130 => Some(Self::Hangul),
             121 | 123 | 131 => Some(Self::HangulHanja),
 
@@ -216,12 +390,255 @@ impl KeyCode {
             74 => Some(Self::F8),
             75 => Some(Self::F9),
             76 => Some(Self::F10),
-            77 => Some(Self::F11),
-            78 => Some(Self::F12),
+            95 => Some(Self::F11),
+            96 => Some(Self::F12),
+
+            66 => Some(Self::CapsLock),
+            77 => Some(Self::NumLock),
+            78 => Some(Self::ScrollLock),
+
+            90 => Some(Self::Kp0),
+            87 => Some(Self::Kp1),
+            88 => Some(Self::Kp2),
+            89 => Some(Self::Kp3),
+            83 => Some(Self::Kp4),
+            84 => Some(Self::Kp5),
+            85 => Some(Self::Kp6),
+            79 => Some(Self::Kp7),
+            80 => Some(Self::Kp8),
+            81 => Some(Self::Kp9),
+            91 => Some(Self::KpPeriod),
+            129 => Some(Self::KpComma),
+            103 => Some(Self::KpJpComma),
+            86 => Some(Self::KpPlus),
+            82 => Some(Self::KpMinus),
+            63 => Some(Self::KpMultiply),
+            106 => Some(Self::KpDivide),
+            125 => Some(Self::KpEqual),
+            126 => Some(Self::KpPlusMinus),
+            104 => Some(Self::KpEnter),
+            187 => Some(Self::KpLeftParen),
+            188 => Some(Self::KpRightParen),
+
+            133 => Some(Self::SuperL),
+            134 => Some(Self::SuperR),
+            147 => Some(Self::Menu),
+            135 => Some(Self::Compose),
+            107 => Some(Self::PrintScreen),
+            127 => Some(Self::Pause),
+            109 => Some(Self::LineFeed),
+            94 => Some(Self::IntlBackslash),
+            97 => Some(Self::IntlRo),
+            132 => Some(Self::IntlYen),
+            93 => Some(Self::ZenkakuHankaku),
+            98 => Some(Self::Katakana),
+            99 => Some(Self::Hiragana),
+            101 => Some(Self::KatakanaHiragana),
+
+            191 => Some(Self::F13),
+            192 => Some(Self::F14),
+            193 => Some(Self::F15),
+            194 => Some(Self::F16),
+            195 => Some(Self::F17),
+            196 => Some(Self::F18),
+            197 => Some(Self::F19),
+            198 => Some(Self::F20),
+            199 => Some(Self::F21),
+            200 => Some(Self::F22),
+            201 => Some(Self::F23),
+            202 => Some(Self::F24),
+
+            120 => Some(Self::Macro),
+            124 => Some(Self::Power),
+            128 => Some(Self::Scale),
+            136 => Some(Self::Stop),
+            137 => Some(Self::Again),
+            138 => Some(Self::Props),
+            139 => Some(Self::Undo),
+            140 => Some(Self::Front),
+            141 => Some(Self::Copy),
+            142 => Some(Self::Open),
+            143 => Some(Self::Paste),
+            144 => Some(Self::Find),
+            145 => Some(Self::Cut),
+            146 => Some(Self::Help),
+            148 => Some(Self::Calc),
+            149 => Some(Self::Setup),
+            150 => Some(Self::Sleep),
+            151 => Some(Self::WakeUp),
+            152 => Some(Self::File),
+            153 => Some(Self::SendFile),
+            154 => Some(Self::DeleteFile),
+            155 => Some(Self::Xfer),
+            156 => Some(Self::Prog1),
+            157 => Some(Self::Prog2),
+            158 => Some(Self::Www),
+            159 => Some(Self::MsDos),
+            160 => Some(Self::ScreenLock),
+            161 => Some(Self::RotateDisplay),
+            162 => Some(Self::CycleWindows),
+            163 => Some(Self::Mail),
+            164 => Some(Self::Bookmarks),
+            165 => Some(Self::Computer),
+            166 => Some(Self::Back),
+            167 => Some(Self::Forward),
+            168 => Some(Self::CloseCd),
+            169 => Some(Self::EjectCd),
+            170 => Some(Self::EjectCloseCd),
+            171 => Some(Self::NextSong),
+            172 => Some(Self::PlayPause),
+            173 => Some(Self::PreviousSong),
+            174 => Some(Self::StopCd),
+            175 => Some(Self::Record),
+            176 => Some(Self::Rewind),
+            177 => Some(Self::Phone),
+            178 => Some(Self::Iso),
+            179 => Some(Self::Config),
+            180 => Some(Self::Homepage),
+            181 => Some(Self::Refresh),
+            182 => Some(Self::Exit),
+            183 => Some(Self::Move),
+            184 => Some(Self::Edit),
+            185 => Some(Self::ScrollUp),
+            186 => Some(Self::ScrollDown),
+            189 => Some(Self::New),
+            190 => Some(Self::Redo),
+            208 => Some(Self::PlayCd),
+            209 => Some(Self::PauseCd),
+            210 => Some(Self::Prog3),
+            211 => Some(Self::Prog4),
+            212 => Some(Self::AllApplications),
+            213 => Some(Self::Suspend),
+            214 => Some(Self::Close),
+            215 => Some(Self::Play),
+            216 => Some(Self::FastForward),
+            217 => Some(Self::BassBoost),
+            218 => Some(Self::Print),
+            219 => Some(Self::Hp),
+            220 => Some(Self::Camera),
+            221 => Some(Self::Sound),
+            222 => Some(Self::Question),
+            223 => Some(Self::Email),
+            224 => Some(Self::Chat),
+            225 => Some(Self::Search),
+            226 => Some(Self::Connect),
+            227 => Some(Self::Finance),
+            228 => Some(Self::Sport),
+            229 => Some(Self::Shop),
+            230 => Some(Self::AltErase),
+            231 => Some(Self::Cancel),
+            232 => Some(Self::BrightnessDown),
+            233 => Some(Self::BrightnessUp),
+            234 => Some(Self::Media),
+            235 => Some(Self::SwitchVideoMode),
+            236 => Some(Self::KbdIllumToggle),
+            237 => Some(Self::KbdIllumDown),
+            238 => Some(Self::KbdIllumUp),
+            239 => Some(Self::Send),
+            240 => Some(Self::Reply),
+            241 => Some(Self::ForwardMail),
+            242 => Some(Self::Save),
+            243 => Some(Self::Documents),
+            244 => Some(Self::Battery),
+            245 => Some(Self::Bluetooth),
+            246 => Some(Self::Wlan),
+            247 => Some(Self::Uwb),
+            248 => Some(Self::Unknown),
+            249 => Some(Self::VideoNext),
+            250 => Some(Self::VideoPrev),
+            251 => Some(Self::BrightnessCycle),
+            252 => Some(Self::BrightnessAuto),
+            253 => Some(Self::DisplayOff),
+            254 => Some(Self::Wwan),
+            255 => Some(Self::RfKill),
+            256 => Some(Self::MicMute),
 
             _ => None,
         }
     }
+
+    /// Reverse of [`KeyCode::from_hardward_code`]
+    pub fn to_hardware_code(self) -> Option<u16> {
+        match self {
+            // Not the media keys which are shared with them
+            Self::Hangul => Some(130),
+            Self::HangulHanja => Some(131),
+            _ => (0..=256).find(|code| Self::from_hardward_code(*code) == Some(self)),
+        }
+    }
+
+    pub const fn is_keypad(self) -> bool {
+        self as u32 >= Self::Kp0 as u32 && self as u32 <= Self::KpRightParen as u32
+    }
+
+    /// Keypad keys which type characters only while NumLock is on
+    pub const fn is_numlock_key(self) -> bool {
+        matches!(
+            self,
+            Self::Kp0
+                | Self::Kp1
+                | Self::Kp2
+                | Self::Kp3
+                | Self::Kp4
+                | Self::Kp5
+                | Self::Kp6
+                | Self::Kp7
+                | Self::Kp8
+                | Self::Kp9
+                | Self::KpPeriod
+        )
+    }
+
+    /// Keys which neither type text nor move cursor so preedit can survive them
+    pub const fn is_inert(self) -> bool {
+        matches!(
+            self,
+            Self::Shift
+                | Self::CapsLock
+                | Self::NumLock
+                | Self::ScrollLock
+                | Self::SuperL
+                | Self::SuperR
+                | Self::Menu
+                | Self::Compose
+                | Self::PrintScreen
+                | Self::Pause
+                | Self::ZenkakuHankaku
+                | Self::Katakana
+                | Self::Hiragana
+                | Self::KatakanaHiragana
+        ) || (self as u32 >= Self::F13 as u32
+            && self as u32 <= Self::MicMute as u32
+            && !self.is_editing())
+    }
+
+    /// Media keys which edit text or navigate like shortcuts
+    const fn is_editing(self) -> bool {
+        matches!(
+            self,
+            Self::Again
+                | Self::Undo
+                | Self::Redo
+                | Self::Copy
+                | Self::Cut
+                | Self::Paste
+                | Self::AltErase
+                | Self::Find
+                | Self::Open
+                | Self::New
+                | Self::Save
+                | Self::Close
+                | Self::Exit
+                | Self::Cancel
+                | Self::Edit
+                | Self::Move
+                | Self::Back
+                | Self::Forward
+                | Self::Refresh
+                | Self::ScrollUp
+                | Self::ScrollDown
+        )
+    }
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
@@ -254,6 +671,15 @@ impl Key {
     pub const fn super_(code: KeyCode) -> Self {
         Self::new(code, ModifierState::SUPER)
     }
+
+    pub const fn level3(code: KeyCode) -> Self {
+        Self::new(code, ModifierState::LEVEL3)
+    }
+
+    /// Same key without CapsLock and NumLock
+    pub fn without_locks(self) -> Self {
+        Self::new(self.code, self.state - ModifierState::LOCKS)
+    }
 }
 
 impl fmt::Display for Key {
@@ -270,6 +696,10 @@ impl fmt::Display for Key {
             f.write_str("C-")?;
         }
 
+        if self.state.contains(ModifierState::LEVEL3) {
+            f.write_str("G-")?;
+        }
+
         if self.state.contains(ModifierState::SHIFT) {
             f.write_str("S-")?;
         }
@@ -309,6 +739,12 @@ impl FromStr for Key {
                 continue;
             }
 
+            if let Some(n) = s.strip_prefix("G-") {
+                s = n;
+                state |= ModifierState::LEVEL3;
+                continue;
+            }
+
             break;
         }
 
@@ -346,4 +782,27 @@ fn key_parse() {
     assert_eq!("S-4".parse::<Key>().unwrap(), Key::shift(KeyCode::Four));
     assert_eq!("C-Space".parse::<Key>().unwrap(), Key::ctrl(KeyCode::Space));
     assert_eq!("M-X".parse::<Key>().unwrap(), Key::alt(KeyCode::X));
+    assert_eq!("G-A".parse::<Key>().unwrap(), Key::level3(KeyCode::A));
+    assert_eq!(
+        "G-S-A".parse::<Key>().unwrap(),
+        Key::new(KeyCode::A, ModifierState::LEVEL3 | ModifierState::SHIFT)
+    );
+    assert_eq!(
+        Key::new(KeyCode::A, ModifierState::LEVEL3 | ModifierState::SHIFT).to_string(),
+        "G-S-A"
+    );
+    assert_eq!("Kp1".parse::<Key>().unwrap(), Key::normal(KeyCode::Kp1));
+    assert_eq!("C-F13".parse::<Key>().unwrap(), Key::ctrl(KeyCode::F13));
+}
+
+#[test]
+fn hardware_code() {
+    assert_eq!(KeyCode::from_hardward_code(10), Some(KeyCode::One));
+    assert_eq!(KeyCode::from_hardward_code(87), Some(KeyCode::Kp1));
+    assert_eq!(KeyCode::from_hardward_code(95), Some(KeyCode::F11));
+    assert_eq!(KeyCode::from_hardward_code(147), Some(KeyCode::Menu));
+    assert_eq!(KeyCode::from_hardward_code(130), Some(KeyCode::Hangul));
+    assert_eq!(KeyCode::from_hardward_code(300), None);
+    assert_eq!(KeyCode::Space.to_hardware_code(), Some(65));
+    assert_eq!(KeyCode::Hangul.to_hardware_code(), Some(130));
 }
@@ -10,9 +10,10 @@ use std::{
     marker::PhantomData,
 };
 
+/// Value of each key with four levels: plain, Shift, AltGr and AltGr + Shift
 #[derive(Clone, Debug, PartialEq, Eq)]
 pub struct KeyMap<V> {
-    arr: EnumMap<KeyCode, [Option<V>; 2]>,
+    arr: EnumMap<KeyCode, [Option<V>; 4]>,
 }
 
 impl<V: Copy> Default for KeyMap<V> {
@@ -21,6 +22,27 @@ impl<V: Copy> Default for KeyMap<V> {
     }
 }
 
+/// Index of level, lock modifiers are ignored
+fn level(state: ModifierState) -> Option<usize> {
+    let state = state - ModifierState::LOCKS;
+
+    if state.intersects(!(ModifierState::SHIFT | ModifierState::LEVEL3)) {
+        None
+    } else {
+        Some(
+            state.contains(ModifierState::SHIFT) as usize
+                | (state.contains(ModifierState::LEVEL3) as usize) << 1,
+        )
+    }
+}
+
+fn level_state(level: usize) -> ModifierState {
+    let mut state = ModifierState::empty();
+    state.set(ModifierState::SHIFT, level & 0x1 != 0);
+    state.set(ModifierState::LEVEL3, level & 0x2 != 0);
+    state
+}
+
 impl<V: Copy> KeyMap<V> {
     pub fn new() -> Self {
         Self {
@@ -29,17 +51,49 @@ impl<V: Copy> KeyMap<V> {
     }
 
     pub fn get(&self, key: Key) -> Option<V> {
-        if key.state.intersects(!ModifierState::SHIFT) {
-            None
-        } else {
-            // SAFETY: key.state <= 0x1
-            unsafe { *self.arr[key.code].get_unchecked(key.state.bits() as usize) }
-        }
+        self.arr[key.code][level(key.state)?]
     }
 
-    /// Key must don't have shift modifier
+    /// Key which has other modifier than Shift and AltGr is ignored
     pub fn insert(&mut self, key: Key, value: V) {
-        self.arr[key.code][key.state.bits() as usize] = Some(value);
+        if let Some(level) = level(key.state) {
+            self.arr[key.code][level] = Some(value);
+        }
+    }
+
+    /// Iterate all entries, lower level comes first for each key code
+    pub fn iter(&self) -> impl Iterator<Item = (Key, V)> + '_ {
+        self.arr.iter().flat_map(|(code, values)| {
+            values.iter().enumerate().filter_map(move |(level, value)| {
+                Some((Key::new(code, level_state(level)), (*value)?))
+            })
+        })
+    }
+
+    /// Find key which has `value` prefer key with lower level, keypad is ignored
+    pub fn find_key(&self, mut f: impl FnMut(V) -> bool) -> Option<Key> {
+        let mut found: Option<(usize, Key)> = None;
+
+        for (key, value) in self.iter() {
+            // Keypad only duplicates keys of main block
+            if key.code.is_keypad() {
+                continue;
+            }
+
+            if f(value) {
+                let level = level(key.state).unwrap_or_default();
+
+                if level == 0 {
+                    return Some(key);
+                } else if matches!(found, Some((prev, _)) if prev <= level) {
+                    continue;
+                }
+
+                found = Some((level, key));
+            }
+        }
+
+        found.map(|(_, key)| key)
     }
 }
 
@@ -93,7 +147,7 @@ where
 
 #[cfg(test)]
 mod tests {
-    use super::{Key, KeyCode, KeyMap};
+    use super::{Key, KeyCode, KeyMap, ModifierState};
 
     #[test]
     fn insert() {
@@ -101,4 +155,46 @@ mod tests {
         map.insert(Key::normal(KeyCode::Backspace), 123);
         assert_eq!(map.get(Key::normal(KeyCode::Backspace)), Some(123));
     }
+
+    #[test]
+    fn find_key() {
+        let mut map = KeyMap::new();
+        map.insert(Key::shift(KeyCode::A), 'A');
+        map.insert(Key::normal(KeyCode::B), 'A');
+        assert_eq!(map.find_key(|v| v == 'A'), Some(Key::normal(KeyCode::B)));
+        assert_eq!(map.find_key(|v| v == 'B'), None);
+        map.insert(Key::normal(KeyCode::KpPlus), '+');
+        map.insert(Key::shift(KeyCode::Equal), '+');
+        assert_eq!(map.find_key(|v| v == '+'), Some(Key::shift(KeyCode::Equal)));
+    }
+
+    #[test]
+    fn levels() {
+        let mut map = KeyMap::new();
+        map.insert(Key::normal(KeyCode::A), 'a');
+        map.insert(Key::level3(KeyCode::A), 'æ');
+        map.insert(
+            Key::new(KeyCode::A, ModifierState::LEVEL3 | ModifierState::SHIFT),
+            'Æ',
+        );
+        map.insert(Key::level3(KeyCode::B), 'a');
+        assert_eq!(map.get(Key::level3(KeyCode::A)), Some('æ'));
+        assert_eq!(
+            map.get(Key::new(
+                KeyCode::A,
+                ModifierState::LEVEL3 | ModifierState::CAPSLOCK
+            )),
+            Some('æ')
+        );
+        assert_eq!(map.get(Key::shift(KeyCode::A)), None);
+        assert_eq!(map.get(Key::ctrl(KeyCode::A)), None);
+        assert_eq!(
+            map.find_key(|v| v == 'Æ'),
+            Some(Key::new(
+                KeyCode::A,
+                ModifierState::LEVEL3 | ModifierState::SHIFT
+            ))
+        );
+        assert_eq!(map.find_key(|v| v == 'a'), Some(Key::normal(KeyCode::A)));
+    }
 }
@@ -0,0 +1,258 @@
+use crate::{Key, KeyCode, ModifierState};
+
+/// Key of US-QWERTY which types `ch` and whether it needs Shift
+const fn ascii_key(ch: u8) -> Option<(KeyCode, bool)> {
+    let key = match ch {
+        b'a' | b'A' => KeyCode::A,
+        b'b' | b'B' => KeyCode::B,
+        b'c' | b'C' => KeyCode::C,
+        b'd' | b'D' => KeyCode::D,
+        b'e' | b'E' => KeyCode::E,
+        b'f' | b'F' => KeyCode::F,
+        b'g' | b'G' => KeyCode::G,
+        b'h' | b'H' => KeyCode::H,
+        b'i' | b'I' => KeyCode::I,
+        b'j' | b'J' => KeyCode::J,
+        b'k' | b'K' => KeyCode::K,
+        b'l' | b'L' => KeyCode::L,
+        b'm' | b'M' => KeyCode::M,
+        b'n' | b'N' => KeyCode::N,
+        b'o' | b'O' => KeyCode::O,
+        b'p' | b'P' => KeyCode::P,
+        b'q' | b'Q' => KeyCode::Q,
+        b'r' | b'R' => KeyCode::R,
+        b's' | b'S' => KeyCode::S,
+        b't' | b'T' => KeyCode::T,
+        b'u' | b'U' => KeyCode::U,
+        b'v' | b'V' => KeyCode::V,
+        b'w' | b'W' => KeyCode::W,
+        b'x' | b'X' => KeyCode::X,
+        b'y' | b'Y' => KeyCode::Y,
+        b'z' | b'Z' => KeyCode::Z,
+
+        b'1' | b'!' => KeyCode::One,
+        b'2' | b'@' => KeyCode::Two,
+        b'3' | b'#' => KeyCode::Three,
+        b'4' | b'$' => KeyCode::Four,
+        b'5' | b'%' => KeyCode::Five,
+        b'6' | b'^' => KeyCode::Six,
+        b'7' | b'&' => KeyCode::Seven,
+        b'8' | b'*' => KeyCode::Eight,
+        b'9' | b'(' => KeyCode::Nine,
+        b'0' | b')' => KeyCode::Zero,
+
+        b'-' | b'_' => KeyCode::Minus,
+        b'=' | b'+' => KeyCode::Equal,
+        b'\\' | b'|' => KeyCode::Backslash,
+        b'`' | b'~' => KeyCode::Grave,
+        b'[' | b'{' => KeyCode::OpenBracket,
+        b']' | b'}' => KeyCode::CloseBracket,
+        b';' | b':' => KeyCode::SemiColon,
+        b'\'' | b'"' => KeyCode::Quote,
+        b',' | b'<' => KeyCode::Comma,
+        b'.' | b'>' => KeyCode::Period,
+        b'/' | b'?' => KeyCode::Slash,
+        b' ' => KeyCode::Space,
+        _ => return None,
+    };
+
+    let shift = matches!(
+        ch,
+        b'A'..=b'Z'
+            | b'!'
+            | b'@'
+            | b'#'
+            | b'$'
+            | b'%'
+            | b'^'
+            | b'&'
+            | b'*'
+            | b'('
+            | b')'
+            | b'_'
+            | b'+'
+            | b'|'
+            | b'~'
+            | b'{'
+            | b'}'
+            | b':'
+            | b'"'
+            | b'<'
+            | b'>'
+            | b'?'
+    );
+
+    Some((key, shift))
+}
+
+/// Key code of keysym which doesn't type a character
+const fn function_key(keysym: u32) -> Option<KeyCode> {
+    let code = match keysym {
+        0xff08 => KeyCode::Backspace,
+        0xff09 | 0xfe20 => KeyCode::Tab,
+        0xff0d => KeyCode::Enter,
+        0xff0a => KeyCode::LineFeed,
+        0xff13 => KeyCode::Pause,
+        0xff14 => KeyCode::ScrollLock,
+        0xff1b => KeyCode::Esc,
+        0xff20 => KeyCode::Compose,
+        0xff22 => KeyCode::Muhenkan,
+        0xff23 => KeyCode::Henkan,
+        0xff25 => KeyCode::Hiragana,
+        0xff26 => KeyCode::Katakana,
+        0xff27 => KeyCode::KatakanaHiragana,
+        0xff2a => KeyCode::ZenkakuHankaku,
+        0xff31 => KeyCode::Hangul,
+        0xff34 => KeyCode::HangulHanja,
+        0xff50 => KeyCode::Home,
+        0xff51 => KeyCode::Left,
+        0xff52 => KeyCode::Up,
+        0xff53 => KeyCode::Right,
+        0xff54 => KeyCode::Down,
+        0xff55 => KeyCode::PageUp,
+        0xff56 => KeyCode::PageDown,
+        0xff57 => KeyCode::End,
+        0xff61 => KeyCode::PrintScreen,
+        0xff63 => KeyCode::Insert,
+        0xff67 => KeyCode::Menu,
+        0xff7f => KeyCode::NumLock,
+        0xffff => KeyCode::Delete,
+
+        0xff8d => KeyCode::KpEnter,
+        0xffaa => KeyCode::KpMultiply,
+        0xffab => KeyCode::KpPlus,
+        0xffac => KeyCode::KpComma,
+        0xffad => KeyCode::KpMinus,
+        0xffae | 0xff9f => KeyCode::KpPeriod,
+        0xffaf => KeyCode::KpDivide,
+        0xffbd => KeyCode::KpEqual,
+        0xffb0 | 0xff9e => KeyCode::Kp0,
+        0xffb1 | 0xff9c => KeyCode::Kp1,
+        0xffb2 | 0xff99 => KeyCode::Kp2,
+        0xffb3 | 0xff9b => KeyCode::Kp3,
+        0xffb4 | 0xff96 => KeyCode::Kp4,
+        0xffb5 | 0xff9d => KeyCode::Kp5,
+        0xffb6 | 0xff98 => KeyCode::Kp6,
+        0xffb7 | 0xff95 => KeyCode::Kp7,
+        0xffb8 | 0xff97 => KeyCode::Kp8,
+        0xffb9 | 0xff9a => KeyCode::Kp9,
+
+        0xffbe => KeyCode::F1,
+        0xffbf => KeyCode::F2,
+        0xffc0 => KeyCode::F3,
+        0xffc1 => KeyCode::F4,
+        0xffc2 => KeyCode::F5,
+        0xffc3 => KeyCode::F6,
+        0xffc4 => KeyCode::F7,
+        0xffc5 => KeyCode::F8,
+        0xffc6 => KeyCode::F9,
+        0xffc7 => KeyCode::F10,
+        0xffc8 => KeyCode::F11,
+        0xffc9 => KeyCode::F12,
+        0xffca => KeyCode::F13,
+        0xffcb => KeyCode::F14,
+        0xffcc => KeyCode::F15,
+        0xffcd => KeyCode::F16,
+        0xffce => KeyCode::F17,
+        0xffcf => KeyCode::F18,
+        0xffd0 => KeyCode::F19,
+        0xffd1 => KeyCode::F20,
+        0xffd2 => KeyCode::F21,
+        0xffd3 => KeyCode::F22,
+        0xffd4 => KeyCode::F23,
+        0xffd5 => KeyCode::F24,
+
+        0xffe1 | 0xffe2 => KeyCode::Shift,
+        0xffe3 => KeyCode::ControlL,
+        0xffe4 => KeyCode::ControlR,
+        0xffe5 => KeyCode::CapsLock,
+        0xffe9 => KeyCode::AltL,
+        // ISO_Level3_Shift is AltGr which is placed at right alt
+        0xffea | 0xfe03 => KeyCode::AltR,
+        0xffeb => KeyCode::SuperL,
+        0xffec => KeyCode::SuperR,
+        _ => return None,
+    };
+
+    Some(code)
+}
+
+impl Key {
+    /// Key of US-QWERTY which types same symbol with XKB `keysym`
+    ///
+    /// Keysym is already affected by `state`, so Shift and AltGr are replaced with what US-QWERTY needs
+    /// except that Shift of letters is kept since it is inverted by CapsLock
+    pub fn from_keysym(keysym: u32, state: ModifierState) -> Option<Self> {
+        if keysym < 0x80 {
+            let (code, shift) = ascii_key(keysym as u8)?;
+            let mut state = state - ModifierState::LEVEL3;
+
+            if !(keysym as u8).is_ascii_alphabetic() {
+                state.set(ModifierState::SHIFT, shift);
+            }
+
+            Some(Self::new(code, state))
+        } else {
+            function_key(keysym).map(|code| Self::new(code, state))
+        }
+    }
+
+    /// `keysym` types character or starts composing it even if it has no key e.g. `€` and dead keys
+    pub fn keysym_types_char(keysym: u32) -> bool {
+        matches!(
+            keysym,
+            // Latin-1 and legacy charsets
+            0x20..=0x7e | 0xa0..=0xfdff
+            // Dead keys
+            | 0xfe50..=0xfe93
+            // Unicode
+            | 0x100_0100..=0x110_ffff
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Key, KeyCode, ModifierState};
+
+    #[test]
+    fn from_keysym() {
+        // AZERTY: unshifted '&' is on position of US '1'
+        assert_eq!(
+            Key::from_keysym(b'&' as u32, ModifierState::empty()),
+            Some(Key::shift(KeyCode::Seven))
+        );
+        assert_eq!(
+            Key::from_keysym(b'1' as u32, ModifierState::SHIFT),
+            Some(Key::normal(KeyCode::One))
+        );
+        assert_eq!(
+            Key::from_keysym(b'A' as u32, ModifierState::CAPSLOCK),
+            Some(Key::new(KeyCode::A, ModifierState::CAPSLOCK))
+        );
+        assert_eq!(
+            Key::from_keysym(b'@' as u32, ModifierState::LEVEL3),
+            Some(Key::shift(KeyCode::Two))
+        );
+        assert_eq!(
+            Key::from_keysym(0xff0d, ModifierState::CONTROL),
+            Some(Key::ctrl(KeyCode::Enter))
+        );
+        assert_eq!(
+            Key::from_keysym(0xffb1, ModifierState::NUMLOCK),
+            Some(Key::new(KeyCode::Kp1, ModifierState::NUMLOCK))
+        );
+        // Euro sign
+        assert_eq!(Key::from_keysym(0x20ac, ModifierState::LEVEL3), None);
+
+        assert!(Key::keysym_types_char(0x20ac));
+        assert!(Key::keysym_types_char(0xe9));
+        // dead_acute
+        assert!(Key::keysym_types_char(0xfe51));
+        // U+1F600
+        assert!(Key::keysym_types_char(0x101_f600));
+        // XF86AudioMute
+        assert!(!Key::keysym_types_char(0x1008_ff12));
+        assert!(!Key::keysym_types_char(0xffe1));
+    }
+}
@@ -1,12 +1,17 @@
 mod input_result;
 mod keycode;
 mod keymap;
+mod keysym;
+mod preedit;
 
 pub use keycode::{Key, KeyCode, ModifierState};
 pub use keymap::KeyMap;
+pub use preedit::{Preedit, PreeditAttribute, PreeditSegment};
 
 pub use input_result::InputResult;
 
+use std::time::Instant;
+
 pub trait InputEngineBackend {
     type ConfigData;
 
@@ -14,14 +19,83 @@ pub trait InputEngineBackend {
     /// # Return
     /// `true` means key has handled
     fn press_key(&mut self, config: &Self::ConfigData, key: Key, commit_buf: &mut String) -> bool;
+    /// Press key which is pressed at `time`, backends which depend on timing of keys should override it
+    /// # Return
+    /// `true` means key has handled
+    #[allow(unused_variables)]
+    fn press_key_at(
+        &mut self,
+        config: &Self::ConfigData,
+        key: Key,
+        time: Instant,
+        commit_buf: &mut String,
+    ) -> bool {
+        self.press_key(config, key, commit_buf)
+    }
+    /// Release key which is released at `time`
+    /// # Return
+    /// `true` means preedit or commit string has changed
+    #[allow(unused_variables)]
+    fn release_key(
+        &mut self,
+        config: &Self::ConfigData,
+        key: Key,
+        time: Instant,
+        commit_buf: &mut String,
+    ) -> bool {
+        false
+    }
     /// Clear current preedit string this function may change commit string
     fn clear_preedit(&mut self, commit_buf: &mut String);
     /// Clear engine state
     fn reset(&mut self);
     /// Get preedit string
     fn preedit_str(&self, buf: &mut String);
+    /// Get preedit string with segments and cursor
+    fn preedit(&self, preedit: &mut Preedit) {
+        preedit.push_with(PreeditAttribute::Composing, |buf| self.preedit_str(buf));
+    }
     /// Is have preedit
     fn has_preedit(&self) -> bool;
+    /// Take committed text before cursor back into preedit so it can be edited again
+    /// # Return
+    /// count of chars taken from end of `text` these must be deleted from surrounding text
+    #[allow(unused_variables)]
+    fn reopen_surrounding(&mut self, text: &str) -> usize {
+        0
+    }
+    /// Serialize state of engine e.g. preedit so that it can be restored later
+    /// # Return
+    /// `None` when there is nothing to save
+    fn save_state(&self) -> Option<String> {
+        None
+    }
+    /// Restore state which is made by `save_state`
+    /// # Return
+    /// `false` when `state` is invalid
+    #[allow(unused_variables)]
+    fn restore_state(&mut self, state: &str) -> bool {
+        false
+    }
+    /// Name of active layout
+    /// # Return
+    /// `None` when backend doesn't have layouts
+    fn layout(&self) -> Option<&str> {
+        None
+    }
+    /// Select layout by `name` or next layout when it's `None`, preedit should be cleared before
+    /// # Return
+    /// `false` when there is no such layout
+    #[allow(unused_variables)]
+    fn select_layout(&mut self, config: &Self::ConfigData, name: Option<&str>) -> bool {
+        false
+    }
+    /// Other texts which preedit can be e.g. ambiguous romaja, preedit is the first one
+    /// # Return
+    /// empty when preedit isn't ambiguous
+    fn alternatives(&self) -> &[String] {
+        &[]
+    }
 }
 
 pub enum InputEngineModeResult<T> {
@@ -48,6 +122,10 @@ pub trait InputEngineMode {
     fn reset(&mut self) -> InputEngineModeResult<()>;
     /// Get preedit string
     fn preedit_str(&self, buf: &mut String);
+    /// Get preedit string with segments and cursor
+    fn preedit(&self, preedit: &mut Preedit) {
+        preedit.push_with(PreeditAttribute::Composing, |buf| self.preedit_str(buf));
+    }
     /// Is have preedit
     fn has_preedit(&self) -> bool;
     /// Is now ready
@@ -0,0 +1,125 @@
+/// Attribute of preedit segment
+#[repr(u32)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum PreeditAttribute {
+    /// Text which is being composed
+    Composing,
+    /// Focused part of preedit e.g. current syllable of word
+    Selected,
+    /// Text which is not typed yet but suggested
+    CandidateHint,
+    /// Text which can't be committed as it is
+    Error,
+}
+
+/// Range of preedit string which has same attribute
+#[repr(C)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PreeditSegment {
+    /// Start byte offset
+    pub start: usize,
+    /// End byte offset
+    pub end: usize,
+    pub attribute: PreeditAttribute,
+}
+
+/// Preedit string with segments and cursor
+#[derive(Clone, Debug, Default)]
+pub struct Preedit {
+    text: String,
+    segments: Vec<PreeditSegment>,
+    cursor: Option<usize>,
+}
+
+impl Preedit {
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.segments.clear();
+        self.cursor = None;
+    }
+
+    #[inline]
+    pub fn text(&self) -> &str {
+        &self.text
+    }
+
+    #[inline]
+    pub fn segments(&self) -> &[PreeditSegment] {
+        &self.segments
+    }
+
+    /// Byte offset of cursor, end of text when it isn't set
+    #[inline]
+    pub fn cursor(&self) -> usize {
+        self.cursor.unwrap_or(self.text.len())
+    }
+
+    /// Place cursor at current end of text
+    #[inline]
+    pub fn set_cursor(&mut self) {
+        self.cursor = Some(self.text.len());
+    }
+
+    pub fn push_str(&mut self, s: &str, attribute: PreeditAttribute) {
+        self.push_with(attribute, |buf| buf.push_str(s));
+    }
+
+    pub fn push(&mut self, ch: char, attribute: PreeditAttribute) {
+        self.push_with(attribute, |buf| buf.push(ch));
+    }
+
+    /// Append text which is written by `f`
+    pub fn push_with(&mut self, attribute: PreeditAttribute, f: impl FnOnce(&mut String)) {
+        let start = self.text.len();
+        f(&mut self.text);
+        let end = self.text.len();
+
+        if start == end {
+            return;
+        }
+
+        match self.segments.last_mut() {
+            Some(last) if last.attribute == attribute && last.end == start => {
+                last.end = end;
+            }
+            _ => self.segments.push(PreeditSegment {
+                start,
+                end,
+                attribute,
+            }),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Preedit, PreeditAttribute, PreeditSegment};
+
+    #[test]
+    fn merge_segments() {
+        let mut preedit = Preedit::default();
+        preedit.push_str("가", PreeditAttribute::Composing);
+        preedit.push_str("", PreeditAttribute::Error);
+        preedit.push('나', PreeditAttribute::Composing);
+        preedit.set_cursor();
+        preedit.push_str("다", PreeditAttribute::Selected);
+
+        assert_eq!(preedit.text(), "가나다");
+        assert_eq!(preedit.cursor(), 6);
+        assert_eq!(
+            preedit.segments(),
+            &[
+                PreeditSegment {
+                    start: 0,
+                    end: 6,
+                    attribute: PreeditAttribute::Composing,
+                },
+                PreeditSegment {
+                    start: 6,
+                    end: 9,
+                    attribute: PreeditAttribute::Selected,
+                },
+            ]
+        );
+    }
+}
@@ -1,7 +1,7 @@
 use kime_engine_backend::{
     InputEngineMode,
     InputEngineModeResult::{self, Continue, Exit, ExitHandled},
-    Key, KeyCode,
+    Key, KeyCode, Preedit, PreeditAttribute,
 };
 use kime_engine_backend_latin::LatinData;
 
@@ -70,6 +70,30 @@ impl InputEngineMode for EmojiMode {
         }
     }
 
+    /// Suggestions are shown after cursor
+    fn preedit(&self, preedit: &mut Preedit) {
+        let mut annotations = kime_engine_dict::search_unicode_annotations(&self.buf)
+            .take(5)
+            .peekable();
+
+        let attribute = if self.buf.is_empty() || annotations.peek().is_some() {
+            PreeditAttribute::Composing
+        } else {
+            PreeditAttribute::Error
+        };
+        preedit.push_str(&self.buf, attribute);
+        preedit.set_cursor();
+
+        for anno in annotations {
+            preedit.push_with(PreeditAttribute::CandidateHint, |buf| {
+                buf.push_str(anno.codepoint);
+                buf.push('(');
+                buf.push_str(anno.tts);
+                buf.push(')');
+            });
+        }
+    }
+
     fn has_preedit(&self) -> bool {
         true
     }
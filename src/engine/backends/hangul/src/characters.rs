@@ -1,10 +1,9 @@
-use enumset::EnumSet;
 use num_derive::FromPrimitive;
 use num_traits::FromPrimitive;
 use serde::{Deserialize, Serialize};
 use std::{convert::TryFrom, str::FromStr};
 
-use crate::Addon;
+use crate::compose::ComposeRules;
 
 macro_rules! impl_jamo {
     ($ty:ty, [$(($item:ident, $ch:expr),)+]) => {
@@ -112,6 +111,38 @@ pub enum Choseong {
     Tieut,
     Pieup,
     Hieuh,
+
+    // Old hangul, discriminant is offset from U+1100
+    SsangNieun = 0x14,
+    NieunDigeut = 0x15,
+    KapyeounMieum = 0x1D,
+    BieupGiyeok = 0x1E,
+    BieupDigeut = 0x20,
+    BieupJieut = 0x27,
+    BieupTieut = 0x29,
+    KapyeounBieup = 0x2B,
+    SiotGiyeok = 0x2D,
+    SiotNieun = 0x2E,
+    SiotDigeut = 0x2F,
+    SiotBieup = 0x32,
+    SiotJieut = 0x36,
+    Pansios = 0x40,
+    SsangIeung = 0x47,
+    Yesieung = 0x4C,
+    KapyeounPieup = 0x57,
+    SsangHieuh = 0x58,
+    Yeorinhieuh = 0x59,
+
+    // Hangul Jamo Extended-A
+    RieulGiyeok = 0xA964 - 0x1100,
+    RieulDigeut = 0xA966 - 0x1100,
+    RieulMieum = 0xA968 - 0x1100,
+    RieulBieup = 0xA969 - 0x1100,
+    RieulSiot = 0xA96C - 0x1100,
+    MieumGiyeok = 0xA96F - 0x1100,
+    MieumDigeut = 0xA970 - 0x1100,
+    MieumSiot = 0xA971 - 0x1100,
+    SsangYeorinhieuh = 0xA97C - 0x1100,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
@@ -144,6 +175,27 @@ pub enum Jongseong {
     Tieut,
     Pieup,
     Hieuh,
+
+    // Old hangul, discriminant is offset from U+11A8
+    NieunSiot = 0x11C7 - 0x11A8,
+    NieunPansios = 0x11C8 - 0x11A8,
+    RieulGiyeokSiot = 0x11CC - 0x11A8,
+    RieulPansios = 0x11D7 - 0x11A8,
+    RieulYeorinhieuh = 0x11D9 - 0x11A8,
+    MieumSiot = 0x11DD - 0x11A8,
+    MieumPansios = 0x11DF - 0x11A8,
+    KapyeounMieum = 0x11E2 - 0x11A8,
+    KapyeounBieup = 0x11E6 - 0x11A8,
+    Pansios = 0x11EB - 0x11A8,
+    Yesieung = 0x11F0 - 0x11A8,
+    Yeorinhieuh = 0x11F9 - 0x11A8,
+    SsangNieun = 0x11FF - 0x11A8,
+
+    // Hangul Jamo Extended-B
+    SsangDigeut = 0xD7CD - 0x11A8,
+    RieulYesieung = 0xD7DB - 0x11A8,
+    SsangMieum = 0xD7E0 - 0x11A8,
+    SsangBieup = 0xD7E6 - 0x11A8,
 }
 
 #[derive(Clone, Copy, Debug, PartialEq, Eq, FromPrimitive)]
@@ -170,6 +222,23 @@ pub enum Jungseong {
     EU,
     YI,
     I,
+
+    // Old hangul, discriminant is offset from U+1161
+    YoYa = 0x1184 - 0x1161,
+    YoYae = 0x1185 - 0x1161,
+    YoI = 0x1188 - 0x1161,
+    YuYeo = 0x1191 - 0x1161,
+    YuYe = 0x1192 - 0x1161,
+    YuI = 0x1194 - 0x1161,
+    Araea = 0x119E - 0x1161,
+    AraeaI = 0x11A1 - 0x1161,
+    SsangAraea = 0x11A2 - 0x1161,
+
+    // Hangul Jamo Extended-B
+    OYeo = 0xD7B0 - 0x1161,
+    UYeo = 0xD7B5 - 0x1161,
+    AraeaA = 0xD7C5 - 0x1161,
+    AraeaE = 0xD7C6 - 0x1161,
 }
 
 impl_traits!(Choseong, 'ᄀ');
@@ -198,6 +267,35 @@ impl_jamo!(
         (Tieut, 'ㅌ'),
         (Pieup, 'ㅍ'),
         (Hieuh, 'ㅎ'),
+        (SsangNieun, 'ㅥ'),
+        (NieunDigeut, 'ㅦ'),
+        (KapyeounMieum, 'ㅱ'),
+        (BieupGiyeok, 'ㅲ'),
+        (BieupDigeut, 'ㅳ'),
+        (BieupJieut, 'ㅶ'),
+        (BieupTieut, 'ㅷ'),
+        (KapyeounBieup, 'ㅸ'),
+        (SiotGiyeok, 'ㅺ'),
+        (SiotNieun, 'ㅻ'),
+        (SiotDigeut, 'ㅼ'),
+        (SiotBieup, 'ㅽ'),
+        (SiotJieut, 'ㅾ'),
+        (Pansios, 'ㅿ'),
+        (SsangIeung, 'ㆀ'),
+        (Yesieung, 'ㆁ'),
+        (KapyeounPieup, 'ㆄ'),
+        (SsangHieuh, 'ㆅ'),
+        (Yeorinhieuh, 'ㆆ'),
+        // These don't have compatibility jamo
+        (RieulGiyeok, 'ꥤ'),
+        (RieulDigeut, 'ꥦ'),
+        (RieulMieum, 'ꥨ'),
+        (RieulBieup, 'ꥩ'),
+        (RieulSiot, 'ꥬ'),
+        (MieumGiyeok, 'ꥯ'),
+        (MieumDigeut, 'ꥰ'),
+        (MieumSiot, 'ㅯ'),
+        (SsangYeorinhieuh, 'ꥼ'),
     ]
 );
 impl_jamo!(
@@ -224,6 +322,20 @@ impl_jamo!(
         (EU, 'ㅡ'),
         (YI, 'ㅢ'),
         (I, 'ㅣ'),
+        (YoYa, 'ㆇ'),
+        (YoYae, 'ㆈ'),
+        (YoI, 'ㆉ'),
+        (YuYeo, 'ㆊ'),
+        (YuYe, 'ㆋ'),
+        (YuI, 'ㆌ'),
+        (Araea, 'ㆍ'),
+        (AraeaI, 'ㆎ'),
+        // These don't have compatibility jamo
+        (SsangAraea, 'ᆢ'),
+        (OYeo, 'ힰ'),
+        (UYeo, 'ힵ'),
+        (AraeaA, 'ퟅ'),
+        (AraeaE, 'ퟆ'),
     ]
 );
 impl_jamo!(
@@ -256,12 +368,35 @@ impl_jamo!(
         (Tieut, 'ㅌ'),
         (Pieup, 'ㅍ'),
         (Hieuh, 'ㅎ'),
+        (NieunSiot, 'ㅧ'),
+        (NieunPansios, 'ㅨ'),
+        (RieulGiyeokSiot, 'ㅩ'),
+        (RieulPansios, 'ㅬ'),
+        (RieulYeorinhieuh, 'ㅭ'),
+        (MieumSiot, 'ㅯ'),
+        (MieumPansios, 'ㅰ'),
+        (KapyeounMieum, 'ㅱ'),
+        (KapyeounBieup, 'ㅸ'),
+        (Pansios, 'ㅿ'),
+        (Yesieung, 'ㆁ'),
+        (Yeorinhieuh, 'ㆆ'),
+        (SsangNieun, 'ㅥ'),
+        (SsangDigeut, 'ㄸ'),
+        (SsangBieup, 'ㅃ'),
+        // These don't have compatibility jamo
+        (RieulYesieung, 'ퟛ'),
+        (SsangMieum, 'ퟠ'),
     ]
 );
 
 impl Choseong {
     pub const FILLER: char = '\u{115F}';
 
+    /// Modern jamo can be composed into precomposed syllable
+    pub const fn is_modern(self) -> bool {
+        self as u32 <= Self::Hieuh as u32
+    }
+
     pub fn compose(self, jung: Jungseong, jong: Option<Jongseong>) -> char {
         unsafe {
             std::char::from_u32_unchecked(
@@ -286,163 +421,58 @@ impl Choseong {
         Some((cho, jung, jong))
     }
 
-    pub fn try_add(self, other: Self, addons: EnumSet<Addon>) -> Option<Self> {
-        let compose_choseong_ssang = addons.contains(Addon::ComposeChoseongSsang);
-        match (self, other) {
-            (Self::Giyeok, Self::Giyeok) if compose_choseong_ssang => Some(Self::SsangGiyeok),
-            (Self::Bieup, Self::Bieup) if compose_choseong_ssang => Some(Self::SsangBieup),
-            (Self::Siot, Self::Siot) if compose_choseong_ssang => Some(Self::SsangSiot),
-            (Self::Jieut, Self::Jieut) if compose_choseong_ssang => Some(Self::SsangJieut),
-            (Self::Digeut, Self::Digeut) if compose_choseong_ssang => Some(Self::SsangDigeut),
-            _ => None,
-        }
+    pub fn try_add(self, other: Self, rules: &ComposeRules) -> Option<Self> {
+        rules.choseong.compose(self, other)
     }
 
-    pub fn backspace(self, addons: EnumSet<Addon>) -> Option<Self> {
-        let decompose_choseong_ssang = addons.contains(Addon::DecomposeChoseongSsang);
-        match self {
-            Self::SsangGiyeok if decompose_choseong_ssang => Some(Self::Giyeok),
-            Self::SsangBieup if decompose_choseong_ssang => Some(Self::Bieup),
-            Self::SsangSiot if decompose_choseong_ssang => Some(Self::Siot),
-            Self::SsangJieut if decompose_choseong_ssang => Some(Self::SsangJieut),
-            Self::SsangDigeut if decompose_choseong_ssang => Some(Self::Digeut),
-            _ => None,
-        }
+    /// Left jamo of composition, `None` when backspace removes whole jamo
+    pub fn backspace(self, rules: &ComposeRules) -> Option<Self> {
+        rules.choseong.decompose(self).map(|(left, _)| left)
     }
 }
 
 impl Jungseong {
     pub const FILLER: char = '\u{1160}';
 
-    pub fn try_add(self, other: Self, addons: EnumSet<Addon>) -> Option<Self> {
-        let compose_jungseong_ssang = addons.contains(Addon::ComposeJungseongSsang);
-        match (self, other) {
-            // ㅑ ㅣ = ㅒ
-            (Self::YA, Self::I) if compose_jungseong_ssang => Some(Self::YAE),
-            // ㅕ ㅣ = ㅖ
-            (Self::YEO, Self::I) if compose_jungseong_ssang => Some(Self::YE),
-            // ㅗ ㅏ = ㅘ
-            (Self::O, Self::A) => Some(Self::WA),
-            // ㅗ ㅣ = ㅚ
-            (Self::O, Self::I) => Some(Self::OE),
-            // ㅗ ㅐ = ㅙ
-            (Self::O, Self::AE) => Some(Self::WAE),
-            // ㅜ ㅓ = ㅝ
-            (Self::U, Self::EO) => Some(Self::WEO),
-            // ㅜ ㅔ = ㅞ
-            (Self::U, Self::E) => Some(Self::WE),
-            // ㅜ ㅣ = ㅟ
-            (Self::U, Self::I) => Some(Self::WI),
-            // ㅡ ㅣ = ㅢ
-            (Self::EU, Self::I) => Some(Self::YI),
-            _ => None,
-        }
+    /// Modern jamo can be composed into precomposed syllable
+    pub const fn is_modern(self) -> bool {
+        self as u32 <= Self::I as u32
     }
 
-    pub fn backspace(self, addons: EnumSet<Addon>) -> Option<Self> {
-        let decompose_jungseong_ssang = addons.contains(Addon::DecomposeJungseongSsang);
+    pub fn try_add(self, other: Self, rules: &ComposeRules) -> Option<Self> {
+        rules.jungseong.compose(self, other)
+    }
 
-        match self {
-            // ㅖ -> ㅕ
-            Self::YE if decompose_jungseong_ssang => Some(Self::YEO),
-            // ㅒ -> ㅑ
-            Self::YAE if decompose_jungseong_ssang => Some(Self::YA),
-            // ㅘ -> ㅗ
-            Self::WA => Some(Self::O),
-            // ㅚ -> ㅗ
-            Self::OE => Some(Self::O),
-            // ㅙ -> ㅗ
-            Self::WAE => Some(Self::O),
-            // ㅝ -> ㅜ
-            Self::WEO => Some(Self::U),
-            // ㅞ -> ㅜ
-            Self::WE => Some(Self::U),
-            // ㅟ -> ㅜ
-            Self::WI => Some(Self::U),
-            // ㅢ -> ㅡ
-            Self::YI => Some(Self::EU),
-            _ => None,
-        }
+    /// Left jamo of composition, `None` when backspace removes whole jamo
+    pub fn backspace(self, rules: &ComposeRules) -> Option<Self> {
+        rules.jungseong.decompose(self).map(|(left, _)| left)
     }
 }
 
 impl Jongseong {
-    pub fn try_add(self, other: Self, addons: EnumSet<Addon>) -> Option<Self> {
-        let compose_jongseong_ssang = addons.contains(Addon::ComposeJongseongSsang);
-
-        match (self, other) {
-            (Self::Giyeok, Self::Giyeok) if compose_jongseong_ssang => Some(Self::SsangGiyeok),
-            (Self::Siot, Self::Siot) if compose_jongseong_ssang => Some(Self::SsangSiot),
-
-            (Self::Giyeok, Self::Siot) => Some(Self::GiyeokSiot),
-            (Self::Nieun, Self::Hieuh) => Some(Self::NieunHieuh),
-            (Self::Nieun, Self::Jieut) => Some(Self::NieunJieut),
-            (Self::Rieul, Self::Giyeok) => Some(Self::RieulGiyeok),
-            (Self::Rieul, Self::Mieum) => Some(Self::RieulMieum),
-            (Self::Rieul, Self::Bieup) => Some(Self::RieulBieup),
-            (Self::Rieul, Self::Siot) => Some(Self::RieulSiot),
-            (Self::Rieul, Self::Tieut) => Some(Self::RieulTieut),
-            (Self::Rieul, Self::Pieup) => Some(Self::RieulPieup),
-            (Self::Rieul, Self::Hieuh) => Some(Self::RieulHieuh),
-            (Self::Bieup, Self::Siot) => Some(Self::BieupSiot),
-            _ => None,
-        }
+    /// Modern jamo can be composed into precomposed syllable
+    pub const fn is_modern(self) -> bool {
+        self as u32 <= Self::Hieuh as u32
     }
 
-    pub fn backspace(self, addons: EnumSet<Addon>) -> Option<Self> {
-        let decompose_jongseong_ssang = addons.contains(Addon::DecomposeJongseongSsang);
-
-        match self {
-            Self::SsangGiyeok if decompose_jongseong_ssang => Some(Self::Giyeok),
-            Self::SsangSiot if decompose_jongseong_ssang => Some(Self::Siot),
-            Self::GiyeokSiot => Some(Self::Giyeok),
-            Self::NieunHieuh | Self::NieunJieut => Some(Self::Nieun),
-            Self::RieulMieum
-            | Self::RieulBieup
-            | Self::RieulSiot
-            | Self::RieulTieut
-            | Self::RieulHieuh => Some(Self::Rieul),
-            Self::BieupSiot => Some(Self::Bieup),
-            _ => None,
-        }
+    pub fn try_add(self, other: Self, rules: &ComposeRules) -> Option<Self> {
+        rules.jongseong.compose(self, other)
     }
 
-    pub fn to_cho(self, addons: EnumSet<Addon>) -> JongToCho {
-        let decompose_jongseong_ssang = addons.contains(Addon::DecomposeJongseongSsang);
+    /// Left jamo of composition, `None` when backspace removes whole jamo
+    pub fn backspace(self, rules: &ComposeRules) -> Option<Self> {
+        rules.jongseong.decompose(self).map(|(left, _)| left)
+    }
 
-        use JongToCho::{Compose, Direct};
-        match self {
-            Self::Giyeok => Direct(Choseong::Giyeok),
-            Self::SsangGiyeok if decompose_jongseong_ssang => {
-                Compose(Self::Giyeok, Choseong::Giyeok)
+    /// Move jongseong to choseong of next syllable, composed jongseong leaves its left jamo
+    ///
+    /// Return `None` when there is no such choseong
+    pub fn to_cho(self, rules: &ComposeRules) -> Option<JongToCho> {
+        match rules.jongseong.decompose(self) {
+            Some((left, right)) => {
+                Some(JongToCho::Compose(left, Choseong::from_jamo(right.jamo())?))
             }
-            Self::SsangGiyeok => Direct(Choseong::SsangGiyeok),
-            Self::GiyeokSiot => Compose(Self::Giyeok, Choseong::Siot),
-            Self::Nieun => Direct(Choseong::Nieun),
-            Self::NieunJieut => Compose(Self::Nieun, Choseong::Jieut),
-            Self::NieunHieuh => Compose(Self::Nieun, Choseong::Hieuh),
-            Self::Digeut => Direct(Choseong::Digeut),
-            Self::Rieul => Direct(Choseong::Rieul),
-            Self::RieulGiyeok => Compose(Self::Rieul, Choseong::Giyeok),
-            Self::RieulMieum => Compose(Self::Rieul, Choseong::Mieum),
-            Self::RieulBieup => Compose(Self::Rieul, Choseong::Bieup),
-            Self::RieulSiot => Compose(Self::Rieul, Choseong::Siot),
-            Self::RieulTieut => Compose(Self::Rieul, Choseong::Tieut),
-            Self::RieulPieup => Compose(Self::Rieul, Choseong::Pieup),
-            Self::RieulHieuh => Compose(Self::Rieul, Choseong::Hieuh),
-            Self::Mieum => Direct(Choseong::Mieum),
-            Self::Bieup => Direct(Choseong::Bieup),
-            Self::BieupSiot => Compose(Self::Bieup, Choseong::Siot),
-            Self::Siot => Direct(Choseong::Siot),
-            Self::SsangSiot if decompose_jongseong_ssang => Compose(Self::Siot, Choseong::Siot),
-            Self::SsangSiot => Direct(Choseong::SsangSiot),
-            Self::Ieung => Direct(Choseong::Ieung),
-            Self::Jieut => Direct(Choseong::Jieut),
-            Self::Chieut => Direct(Choseong::Chieut),
-            Self::Kieuk => Direct(Choseong::Kiyeok),
-            Self::Tieut => Direct(Choseong::Tieut),
-            Self::Pieup => Direct(Choseong::Pieup),
-            Self::Hieuh => Direct(Choseong::Hieuh),
+            None => Choseong::from_jamo(self.jamo()).map(JongToCho::Direct),
         }
     }
 }
@@ -489,6 +519,24 @@ pub enum KeyValue {
     Pass(char),
 }
 
+impl KeyValue {
+    /// Keys which are pressed together are typed in this order, choseong comes first
+    pub const fn chord_order(self) -> u8 {
+        match self {
+            Self::Choseong { .. }
+            | Self::ChoJong { first: true, .. }
+            | Self::ChoJung { first: true, .. } => 0,
+            Self::Jungseong { .. }
+            | Self::ChoJung { first: false, .. }
+            | Self::JungJong { first: true, .. } => 1,
+            Self::Jongseong { .. }
+            | Self::ChoJong { first: false, .. }
+            | Self::JungJong { first: false, .. } => 2,
+            Self::Pass(_) => 3,
+        }
+    }
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 enum KeyValuePart {
     /// Choseong
@@ -607,6 +655,19 @@ fn decompose() {
     assert_eq!('앙', cho.compose(jung, jong));
 }
 
+#[test]
+fn old_hangul() {
+    assert_eq!('ᅀ', char::from(Choseong::Pansios));
+    assert_eq!('ꥼ', char::from(Choseong::SsangYeorinhieuh));
+    assert_eq!('ퟅ', char::from(Jungseong::AraeaA));
+    assert_eq!('ퟦ', char::from(Jongseong::SsangBieup));
+    assert_eq!(Ok(Jungseong::Araea), Jungseong::try_from('ᆞ'));
+    assert!(Choseong::Hieuh.is_modern());
+    assert!(!Choseong::SsangNieun.is_modern());
+    assert!(!Jungseong::YoYa.is_modern());
+    assert!(!Jongseong::NieunSiot.is_modern());
+}
+
 #[test]
 fn parse_keyvalue() {
     assert_eq!(
@@ -0,0 +1,375 @@
+//! Jamo composition rules of layout
+
+use std::{collections::BTreeMap, convert::TryFrom};
+
+use enumset::EnumSet;
+use serde::Deserialize;
+
+use crate::{
+    characters::{Choseong, Jongseong, Jungseong},
+    Addon,
+};
+
+/// Builtin rule `(left, right, result, compose, decompose)`
+///
+/// `compose` addon enables the rule and `decompose` addon enables backspace of result,
+/// `None` addon is always enabled
+type BuiltinRule<T> = (T, T, T, Option<Addon>, Option<Addon>);
+
+const ALWAYS: Option<Addon> = None;
+const OLD: Option<Addon> = Some(Addon::OldHangul);
+
+const BUILTIN_CHOSEONG: &[BuiltinRule<Choseong>] = {
+    use Choseong::*;
+
+    const COMPOSE: Option<Addon> = Some(Addon::ComposeChoseongSsang);
+    const DECOMPOSE: Option<Addon> = Some(Addon::DecomposeChoseongSsang);
+
+    &[
+        (Giyeok, Giyeok, SsangGiyeok, COMPOSE, DECOMPOSE),
+        (Bieup, Bieup, SsangBieup, COMPOSE, DECOMPOSE),
+        (Siot, Siot, SsangSiot, COMPOSE, DECOMPOSE),
+        (Jieut, Jieut, SsangJieut, COMPOSE, DECOMPOSE),
+        (Digeut, Digeut, SsangDigeut, COMPOSE, DECOMPOSE),
+        (Nieun, Nieun, SsangNieun, OLD, ALWAYS),
+        (Nieun, Digeut, NieunDigeut, OLD, ALWAYS),
+        (Rieul, Giyeok, RieulGiyeok, OLD, ALWAYS),
+        (Rieul, Digeut, RieulDigeut, OLD, ALWAYS),
+        (Rieul, Mieum, RieulMieum, OLD, ALWAYS),
+        (Rieul, Bieup, RieulBieup, OLD, ALWAYS),
+        (Rieul, Siot, RieulSiot, OLD, ALWAYS),
+        (Mieum, Giyeok, MieumGiyeok, OLD, ALWAYS),
+        (Mieum, Digeut, MieumDigeut, OLD, ALWAYS),
+        (Mieum, Siot, MieumSiot, OLD, ALWAYS),
+        (Mieum, Ieung, KapyeounMieum, OLD, ALWAYS),
+        (Bieup, Giyeok, BieupGiyeok, OLD, ALWAYS),
+        (Bieup, Digeut, BieupDigeut, OLD, ALWAYS),
+        (Bieup, Jieut, BieupJieut, OLD, ALWAYS),
+        (Bieup, Tieut, BieupTieut, OLD, ALWAYS),
+        (Bieup, Ieung, KapyeounBieup, OLD, ALWAYS),
+        (Siot, Giyeok, SiotGiyeok, OLD, ALWAYS),
+        (Siot, Nieun, SiotNieun, OLD, ALWAYS),
+        (Siot, Digeut, SiotDigeut, OLD, ALWAYS),
+        (Siot, Bieup, SiotBieup, OLD, ALWAYS),
+        (Siot, Jieut, SiotJieut, OLD, ALWAYS),
+        (Ieung, Ieung, SsangIeung, OLD, ALWAYS),
+        (Pieup, Ieung, KapyeounPieup, OLD, ALWAYS),
+        (Hieuh, Hieuh, SsangHieuh, OLD, ALWAYS),
+        (Yeorinhieuh, Yeorinhieuh, SsangYeorinhieuh, OLD, ALWAYS),
+    ]
+};
+
+const BUILTIN_JUNGSEONG: &[BuiltinRule<Jungseong>] = {
+    use Jungseong::*;
+
+    const COMPOSE: Option<Addon> = Some(Addon::ComposeJungseongSsang);
+    const DECOMPOSE: Option<Addon> = Some(Addon::DecomposeJungseongSsang);
+
+    &[
+        (YA, I, YAE, COMPOSE, DECOMPOSE),
+        (YEO, I, YE, COMPOSE, DECOMPOSE),
+        (O, A, WA, ALWAYS, ALWAYS),
+        (O, I, OE, ALWAYS, ALWAYS),
+        (O, AE, WAE, ALWAYS, ALWAYS),
+        (U, EO, WEO, ALWAYS, ALWAYS),
+        (U, E, WE, ALWAYS, ALWAYS),
+        (U, I, WI, ALWAYS, ALWAYS),
+        (EU, I, YI, ALWAYS, ALWAYS),
+        (Araea, I, AraeaI, OLD, ALWAYS),
+        (Araea, Araea, SsangAraea, OLD, ALWAYS),
+        (Araea, A, AraeaA, OLD, ALWAYS),
+        (Araea, E, AraeaE, OLD, ALWAYS),
+        (YO, YA, YoYa, OLD, ALWAYS),
+        (YO, YAE, YoYae, OLD, ALWAYS),
+        (YO, I, YoI, OLD, ALWAYS),
+        (YU, YEO, YuYeo, OLD, ALWAYS),
+        (YU, YE, YuYe, OLD, ALWAYS),
+        (YU, I, YuI, OLD, ALWAYS),
+        (O, YEO, OYeo, OLD, ALWAYS),
+        (U, YEO, UYeo, OLD, ALWAYS),
+    ]
+};
+
+const BUILTIN_JONGSEONG: &[BuiltinRule<Jongseong>] = {
+    use Jongseong::*;
+
+    const COMPOSE: Option<Addon> = Some(Addon::ComposeJongseongSsang);
+    const DECOMPOSE: Option<Addon> = Some(Addon::DecomposeJongseongSsang);
+
+    &[
+        (Giyeok, Giyeok, SsangGiyeok, COMPOSE, DECOMPOSE),
+        (Siot, Siot, SsangSiot, COMPOSE, DECOMPOSE),
+        (Giyeok, Siot, GiyeokSiot, ALWAYS, ALWAYS),
+        (Nieun, Hieuh, NieunHieuh, ALWAYS, ALWAYS),
+        (Nieun, Jieut, NieunJieut, ALWAYS, ALWAYS),
+        (Rieul, Giyeok, RieulGiyeok, ALWAYS, ALWAYS),
+        (Rieul, Mieum, RieulMieum, ALWAYS, ALWAYS),
+        (Rieul, Bieup, RieulBieup, ALWAYS, ALWAYS),
+        (Rieul, Siot, RieulSiot, ALWAYS, ALWAYS),
+        (Rieul, Tieut, RieulTieut, ALWAYS, ALWAYS),
+        (Rieul, Pieup, RieulPieup, ALWAYS, ALWAYS),
+        (Rieul, Hieuh, RieulHieuh, ALWAYS, ALWAYS),
+        (Bieup, Siot, BieupSiot, ALWAYS, ALWAYS),
+        (Nieun, Nieun, SsangNieun, OLD, ALWAYS),
+        (Nieun, Siot, NieunSiot, OLD, ALWAYS),
+        (Nieun, Pansios, NieunPansios, OLD, ALWAYS),
+        (Digeut, Digeut, SsangDigeut, OLD, ALWAYS),
+        (RieulGiyeok, Siot, RieulGiyeokSiot, OLD, ALWAYS),
+        (Rieul, Pansios, RieulPansios, OLD, ALWAYS),
+        (Rieul, Yeorinhieuh, RieulYeorinhieuh, OLD, ALWAYS),
+        (Rieul, Yesieung, RieulYesieung, OLD, ALWAYS),
+        (Mieum, Mieum, SsangMieum, OLD, ALWAYS),
+        (Mieum, Siot, MieumSiot, OLD, ALWAYS),
+        (Mieum, Pansios, MieumPansios, OLD, ALWAYS),
+        (Mieum, Ieung, KapyeounMieum, OLD, ALWAYS),
+        (Bieup, Bieup, SsangBieup, OLD, ALWAYS),
+        (Bieup, Ieung, KapyeounBieup, OLD, ALWAYS),
+    ]
+};
+
+/// Compositions of one jamo kind
+#[derive(Clone, Debug, PartialEq)]
+pub struct JamoTable<T> {
+    /// `(left, right, result)`
+    compose: Vec<(T, T, T)>,
+    /// `(result, left, right)` which is decomposed by backspace
+    decompose: Vec<(T, T, T)>,
+}
+
+impl<T> Default for JamoTable<T> {
+    fn default() -> Self {
+        Self {
+            compose: Vec::new(),
+            decompose: Vec::new(),
+        }
+    }
+}
+
+impl<T: Copy + PartialEq> JamoTable<T> {
+    /// Enabled rules of `builtin` with `overrides` of layout
+    fn new(
+        builtin: &[BuiltinRule<T>],
+        overrides: &[(T, T, Option<T>)],
+        addons: EnumSet<Addon>,
+    ) -> Self {
+        let enabled = |addon: Option<Addon>| addon.is_none_or(|addon| addons.contains(addon));
+        let mut table = Self::default();
+
+        for (left, right, result, compose, decompose) in builtin.iter().copied() {
+            if enabled(compose) {
+                table.compose.push((left, right, result));
+            }
+
+            if enabled(decompose) {
+                table.decompose.push((result, left, right));
+            }
+        }
+
+        for (left, right, result) in overrides.iter().copied() {
+            table.compose.retain(|(l, r, _)| (*l, *r) != (left, right));
+
+            if let Some(result) = result {
+                table.compose.push((left, right, result));
+                table.decompose.retain(|(res, _, _)| *res != result);
+                table.decompose.push((result, left, right));
+            }
+        }
+
+        table
+    }
+
+    /// Return `None` when there is no rule for `left` and `right`
+    pub fn compose(&self, left: T, right: T) -> Option<T> {
+        self.compose
+            .iter()
+            .find(|(l, r, _)| *l == left && *r == right)
+            .map(|(_, _, result)| *result)
+    }
+
+    /// Left and right jamo which are composed into `jamo`, `None` when backspace removes it
+    pub fn decompose(&self, jamo: T) -> Option<(T, T)> {
+        self.decompose
+            .iter()
+            .find(|(result, _, _)| *result == jamo)
+            .map(|(_, left, right)| (*left, *right))
+    }
+}
+
+/// `compose` section of layout file, rules in here take precedence over builtin rules
+///
+/// `None` result disallows the composition
+#[derive(Clone, Debug, Default, PartialEq, Deserialize)]
+#[serde(try_from = "RawComposeTable")]
+pub struct ComposeTable {
+    choseong: Vec<(Choseong, Choseong, Option<Choseong>)>,
+    jungseong: Vec<(Jungseong, Jungseong, Option<Jungseong>)>,
+    jongseong: Vec<(Jongseong, Jongseong, Option<Jongseong>)>,
+}
+
+#[derive(Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+struct RawComposeTable {
+    choseong: BTreeMap<String, Option<String>>,
+    jungseong: BTreeMap<String, Option<String>>,
+    jongseong: BTreeMap<String, Option<String>>,
+}
+
+/// Parse `pair: result` items, error has the invalid item
+fn parse_items<T>(
+    items: BTreeMap<String, Option<String>>,
+    from_jamo: impl Fn(char) -> Option<T>,
+) -> Result<Vec<(T, T, Option<T>)>, String> {
+    items
+        .into_iter()
+        .map(|(pair, result)| {
+            let invalid = || format!("Invalid composition {}: {:?}", pair, result);
+
+            let mut chars = pair.chars();
+            let (left, right) = match (chars.next(), chars.next(), chars.next()) {
+                (Some(left), Some(right), None) => (
+                    from_jamo(left).ok_or_else(invalid)?,
+                    from_jamo(right).ok_or_else(invalid)?,
+                ),
+                _ => return Err(invalid()),
+            };
+
+            let result = match result.as_deref().map(|s| {
+                let mut chars = s.chars();
+                (chars.next(), chars.next())
+            }) {
+                Some((Some(ch), None)) => Some(from_jamo(ch).ok_or_else(invalid)?),
+                Some(_) => return Err(invalid()),
+                None => None,
+            };
+
+            Ok((left, right, result))
+        })
+        .collect()
+}
+
+impl TryFrom<RawComposeTable> for ComposeTable {
+    type Error = String;
+
+    fn try_from(raw: RawComposeTable) -> Result<Self, Self::Error> {
+        Ok(Self {
+            choseong: parse_items(raw.choseong, Choseong::from_jamo)?,
+            jungseong: parse_items(raw.jungseong, Jungseong::from_jamo)?,
+            jongseong: parse_items(raw.jongseong, Jongseong::from_jamo)?,
+        })
+    }
+}
+
+/// Addons and compositions of layout
+#[derive(Clone, Debug, PartialEq)]
+pub struct ComposeRules {
+    pub addons: EnumSet<Addon>,
+    pub choseong: JamoTable<Choseong>,
+    pub jungseong: JamoTable<Jungseong>,
+    pub jongseong: JamoTable<Jongseong>,
+}
+
+impl Default for ComposeRules {
+    fn default() -> Self {
+        Self::new(EnumSet::empty(), &ComposeTable::default())
+    }
+}
+
+impl ComposeRules {
+    /// Builtin rules which are enabled by `addons` with `table` of layout
+    pub fn new(addons: EnumSet<Addon>, table: &ComposeTable) -> Self {
+        Self {
+            addons,
+            choseong: JamoTable::new(BUILTIN_CHOSEONG, &table.choseong, addons),
+            jungseong: JamoTable::new(BUILTIN_JUNGSEONG, &table.jungseong, addons),
+            jongseong: JamoTable::new(BUILTIN_JONGSEONG, &table.jongseong, addons),
+        }
+    }
+
+    #[inline]
+    pub fn has_addon(&self, addon: Addon) -> bool {
+        self.addons.contains(addon)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn table(yaml: &str) -> Result<ComposeTable, serde_yaml::Error> {
+        serde_yaml::from_str(yaml)
+    }
+
+    #[test]
+    fn builtin() {
+        let rules = ComposeRules::default();
+        assert_eq!(
+            rules.jungseong.compose(Jungseong::O, Jungseong::A),
+            Some(Jungseong::WA)
+        );
+        assert_eq!(
+            rules.choseong.compose(Choseong::Giyeok, Choseong::Giyeok),
+            None
+        );
+        assert_eq!(rules.jongseong.decompose(Jongseong::SsangGiyeok), None);
+
+        let rules = ComposeRules::new(
+            Addon::ComposeChoseongSsang | Addon::OldHangul,
+            &ComposeTable::default(),
+        );
+        assert_eq!(
+            rules.choseong.compose(Choseong::Giyeok, Choseong::Giyeok),
+            Some(Choseong::SsangGiyeok)
+        );
+        // Backspace of ssang needs its own addon
+        assert_eq!(rules.choseong.decompose(Choseong::SsangGiyeok), None);
+        assert_eq!(
+            rules.jongseong.compose(Jongseong::Mieum, Jongseong::Ieung),
+            Some(Jongseong::KapyeounMieum)
+        );
+    }
+
+    #[test]
+    fn override_builtin() {
+        let table = table(
+            "
+jungseong:
+  ㅗㅏ: ㅛ
+jongseong:
+  ㄱㅅ: ~
+",
+        )
+        .unwrap();
+        let rules = ComposeRules::new(EnumSet::empty(), &table);
+
+        assert_eq!(
+            rules.jungseong.compose(Jungseong::O, Jungseong::A),
+            Some(Jungseong::YO)
+        );
+        assert_eq!(
+            rules.jungseong.decompose(Jungseong::YO),
+            Some((Jungseong::O, Jungseong::A))
+        );
+        // Other builtin rules are kept
+        assert_eq!(
+            rules.jungseong.compose(Jungseong::O, Jungseong::I),
+            Some(Jungseong::OE)
+        );
+        assert_eq!(
+            rules.jongseong.compose(Jongseong::Giyeok, Jongseong::Siot),
+            None
+        );
+        // Directly typed cluster can be still decomposed
+        assert_eq!(
+            rules.jongseong.decompose(Jongseong::GiyeokSiot),
+            Some((Jongseong::Giyeok, Jongseong::Siot))
+        );
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(table("choseong:\n  ㄱㄱ: ㅏ\n").is_err());
+        assert!(table("choseong:\n  ㄱ: ㄲ\n").is_err());
+        assert!(table("jungseong:\n  ㅗㅏ: ㅘㅘ\n").is_err());
+        assert!(table("moeum:\n  ㅗㅏ: ㅘ\n").is_err());
+    }
+}
@@ -1,11 +1,17 @@
 use crate::characters::KeyValue;
+use crate::compose::ComposeTable;
 use crate::Key;
 use kime_engine_backend::KeyMap;
+use serde_yaml::{Mapping, Value};
 use std::collections::HashMap;
 
-#[derive(Clone, Default)]
+/// Key of `compose` section in layout file
+const COMPOSE: &str = "compose";
+
+#[derive(Clone, Default, PartialEq)]
 pub struct Layout {
     keymap: KeyMap<KeyValue>,
+    compose: ComposeTable,
 }
 
 impl Layout {
@@ -21,15 +27,48 @@ impl Layout {
             keymap.insert(key, value);
         }
 
-        Self { keymap }
+        Self {
+            keymap,
+            compose: ComposeTable::default(),
+        }
     }
 
     pub fn load_from(content: &str) -> Result<Self, serde_yaml::Error> {
-        Ok(Self::from_items(serde_yaml::from_str(content)?))
+        let mut items: Mapping = serde_yaml::from_str(content)?;
+        let compose = match items.remove(&Value::from(COMPOSE)) {
+            Some(compose) => serde_yaml::from_value(compose)?,
+            None => ComposeTable::default(),
+        };
+
+        // Number is also key or symbol e.g. `1: 1`
+        let to_str = |value| match value {
+            Value::Number(value) => Value::String(value.to_string()),
+            value => value,
+        };
+        // `~` leaves the key unbound
+        let items = items
+            .into_iter()
+            .filter(|(_, value)| !value.is_null())
+            .map(|(key, value)| (to_str(key), to_str(value)))
+            .collect();
+
+        let mut layout = Self::from_items(serde_yaml::from_value(Value::Mapping(items))?);
+        layout.compose = compose;
+        Ok(layout)
+    }
+
+    #[inline]
+    pub fn compose_table(&self) -> &ComposeTable {
+        &self.compose
     }
 
     #[inline]
     pub fn lookup_kv(&self, key: Key) -> Option<KeyValue> {
         self.keymap.get(key)
     }
+
+    #[inline]
+    pub fn find_key(&self, f: impl FnMut(KeyValue) -> bool) -> Option<Key> {
+        self.keymap.find_key(f)
+    }
 }
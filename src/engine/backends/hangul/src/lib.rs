@@ -1,14 +1,26 @@
 mod characters;
+mod compose;
 mod layout;
+mod normalize;
+mod reverse;
+mod romaja;
 mod state;
 
+use compose::ComposeRules;
 use layout::Layout;
-use std::{borrow::Cow, collections::BTreeMap};
+use std::{
+    borrow::Cow,
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
 
+use characters::KeyValue;
 use enumset::{EnumSet, EnumSetType};
-use kime_engine_backend::{InputEngineBackend, Key, KeyCode};
+use kime_engine_backend::{InputEngineBackend, Key, KeyCode, Preedit};
 use serde::{Deserialize, Serialize};
 
+pub use normalize::CommitNormalization;
+pub use romaja::ROMAJA_LAYOUT;
 pub use state::HangulEngine;
 
 #[derive(Hash, Serialize, Deserialize, Debug, EnumSetType)]
@@ -29,6 +41,10 @@ pub enum Addon {
     TreatJongseongAsChoseong,
     /// 읅 + ㄱ = 을ㄲ
     TreatJongseongAsChoseongCompose,
+
+    /// ㅅ + ㄱ = ㅺ
+    /// ㆍ + ㅣ = ㆎ
+    OldHangul,
 }
 
 #[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
@@ -47,24 +63,58 @@ impl Default for PreeditJohabLevel {
     }
 }
 
+#[derive(Clone, Copy, Debug, Serialize, Deserialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct MoachigiConfig {
+    /// Type keys which are pressed together as one syllable regardless of order
+    pub enable: bool,
+    /// Keys pressed within this milliseconds from first key are pressed together even after release
+    pub window: u64,
+}
+
+impl Default for MoachigiConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            window: 50,
+        }
+    }
+}
+
 #[derive(Serialize, Deserialize)]
 #[serde(default)]
 pub struct HangulConfig {
     pub layout: String,
+    /// Layouts which can be selected by hotkeys in addition to `layout`
+    pub layouts: Vec<String>,
     pub word_commit: bool,
     pub preedit_johab: PreeditJohabLevel,
+    /// Unicode form of committed hangul
+    pub commit_normalization: CommitNormalization,
     pub addons: BTreeMap<String, EnumSet<Addon>>,
+    /// Interpret layout by keysym of system keyboard layout instead of key position
+    pub keysym_layout: bool,
+    pub moachigi: MoachigiConfig,
 }
 
 impl Default for HangulConfig {
     fn default() -> Self {
         Self {
             layout: "dubeolsik".into(),
+            layouts: Vec::new(),
             word_commit: false,
             preedit_johab: PreeditJohabLevel::default(),
+            commit_normalization: CommitNormalization::default(),
+            keysym_layout: false,
+            moachigi: MoachigiConfig::default(),
             addons: vec![
                 ("all".into(), Addon::ComposeChoseongSsang.into()),
                 ("dubeolsik".into(), Addon::TreatJongseongAsChoseong.into()),
+                (
+                    "dubeolsik-old".into(),
+                    Addon::TreatJongseongAsChoseong | Addon::OldHangul,
+                ),
+                ("sebeolsik-old".into(), Addon::OldHangul.into()),
             ]
             .into_iter()
             .collect(),
@@ -72,6 +122,18 @@ impl Default for HangulConfig {
     }
 }
 
+impl HangulConfig {
+    /// Names of selectable layouts, `layout` comes first since it's used at start
+    pub fn active_layouts(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.layout.as_str()).chain(
+            self.layouts
+                .iter()
+                .map(String::as_str)
+                .filter(move |name| *name != self.layout),
+        )
+    }
+}
+
 pub const BUILTIN_LAYOUTS: &'static [(&'static str, &'static str)] = &[
     ("dubeolsik", include_str!("../data/dubeolsik.yaml")),
     (
@@ -90,13 +152,27 @@ pub const BUILTIN_LAYOUTS: &'static [(&'static str, &'static str)] = &[
         "sebeolsik-3sin-p2",
         include_str!("../data/sebeolsik-3sin-p2.yaml"),
     ),
+    ("dubeolsik-old", include_str!("../data/dubeolsik-old.yaml")),
+    ("sebeolsik-old", include_str!("../data/sebeolsik-old.yaml")),
 ];
 
-pub struct HangulData {
+#[derive(Clone, PartialEq)]
+struct LayoutData {
+    name: String,
     layout: Layout,
-    addons: EnumSet<Addon>,
+    rules: ComposeRules,
+    /// Layout types romaja instead of jamo
+    romaja: bool,
+}
+
+#[derive(Clone, PartialEq)]
+pub struct HangulData {
+    /// Never empty
+    layouts: Vec<LayoutData>,
     preedit_johab: PreeditJohabLevel,
     word_commit: bool,
+    /// Window of moachigi, `None` when moachigi is disabled
+    moachigi: Option<Duration>,
 }
 
 impl Default for HangulData {
@@ -124,30 +200,80 @@ impl HangulData {
 
     pub fn new(
         config: &HangulConfig,
-        mut layouts: impl Iterator<Item = (Cow<'static, str>, Layout)>,
+        layouts: impl Iterator<Item = (Cow<'static, str>, Layout)>,
     ) -> Self {
-        Self {
-            layout: layouts
-                .find_map(|(name, layout)| {
-                    if name == config.layout {
-                        Some(layout)
-                    } else {
-                        None
-                    }
+        let mut names: Vec<&str> = Vec::new();
+        for name in config.active_layouts() {
+            if !names.contains(&name) {
+                names.push(name);
+            }
+        }
+
+        // Earlier layout wins so custom layout can override builtin layout
+        let mut found: Vec<Option<Layout>> = names.iter().map(|_| None).collect();
+        for (name, layout) in layouts {
+            if let Some(idx) = names.iter().position(|n| *n == name) {
+                found[idx].get_or_insert(layout);
+            }
+
+            if found.iter().all(Option::is_some) {
+                break;
+            }
+        }
+
+        let all_addons = config.addons.get("all").copied().unwrap_or_default();
+        let addons =
+            |name: &str| all_addons.union(config.addons.get(name).copied().unwrap_or_default());
+
+        let mut active: Vec<LayoutData> = names
+            .into_iter()
+            .zip(found)
+            .filter_map(|(name, layout)| {
+                // Custom layout can override romaja
+                let (layout, romaja) = match layout {
+                    Some(layout) => (layout, false),
+                    None if name == ROMAJA_LAYOUT => (Layout::default(), true),
+                    None => return None,
+                };
+                Some(LayoutData {
+                    name: name.into(),
+                    rules: ComposeRules::new(addons(name), layout.compose_table()),
+                    layout,
+                    romaja,
                 })
-                .unwrap_or_default(),
-            addons: config.addons.get("all").copied().unwrap_or_default().union(
-                config
-                    .addons
-                    .get(&config.layout)
-                    .copied()
-                    .unwrap_or_default(),
-            ),
+            })
+            .collect();
+
+        if active.is_empty() {
+            active.push(LayoutData {
+                name: config.layout.clone(),
+                layout: Layout::default(),
+                rules: ComposeRules::new(addons(&config.layout), &Default::default()),
+                romaja: false,
+            });
+        }
+
+        Self {
+            layouts: active,
             preedit_johab: config.preedit_johab,
             word_commit: config.word_commit,
+            moachigi: if config.moachigi.enable {
+                Some(Duration::from_millis(config.moachigi.window))
+            } else {
+                None
+            },
         }
     }
 
+    fn layout_index(&self, name: &str) -> Option<usize> {
+        self.layouts.iter().position(|l| l.name == name)
+    }
+
+    /// Layout of `index`, first layout is used when it's out of range
+    fn layout_at(&self, index: usize) -> &LayoutData {
+        self.layouts.get(index).unwrap_or(&self.layouts[0])
+    }
+
     pub const fn preedit_johab(&self) -> PreeditJohabLevel {
         self.preedit_johab
     }
@@ -155,21 +281,95 @@ impl HangulData {
     pub const fn word_commit(&self) -> bool {
         self.word_commit
     }
+
+    /// Engine which starts with first layout
+    pub fn new_engine(&self) -> HangulEngine {
+        let mut engine = HangulEngine::new(self.word_commit, self.preedit_johab);
+        engine.set_layout(0, &self.layouts[0].name, self.layouts[0].romaja);
+        engine
+    }
+
+    /// Text which is typed by `keys` with `layout`, first layout is used when there is no such layout
+    ///
+    /// Return `None` when there is key which is not handled by layout
+    pub fn type_keys(&self, layout: &str, keys: &[Key]) -> Option<String> {
+        let mut engine = HangulEngine::new(false, self.preedit_johab);
+        let idx = self.layout_index(layout).unwrap_or_default();
+        engine.set_layout(idx, &self.layouts[idx].name, self.layouts[idx].romaja);
+        let mut out = String::with_capacity(keys.len() * 3);
+        for key in keys.iter().copied() {
+            if !engine.press_key(self, key, &mut out) {
+                return None;
+            }
+        }
+        engine.clear_preedit(&mut out);
+        Some(out)
+    }
+
+    /// Find keys which type `text` with `layout`
+    pub fn keys_of(&self, layout: &str, text: &str) -> Option<Vec<Key>> {
+        let data = self.layout_at(self.layout_index(layout).unwrap_or_default());
+        let keys = reverse::keys_of(&data.layout, &data.rules, text)?;
+
+        // Check keys are really type same text
+        if self.type_keys(layout, &keys)? == text {
+            Some(keys)
+        } else {
+            None
+        }
+    }
 }
 
 impl InputEngineBackend for HangulEngine {
     type ConfigData = HangulData;
 
     fn press_key(&mut self, config: &HangulData, key: Key, commit_buf: &mut String) -> bool {
-        if key.code == KeyCode::Backspace {
-            self.backspace(config.addons, commit_buf)
-        } else if let Some(kv) = config.layout.lookup_kv(key) {
-            self.key(kv, config.addons, commit_buf)
+        self.press_key_at(config, key, Instant::now(), commit_buf)
+    }
+
+    fn press_key_at(
+        &mut self,
+        config: &HangulData,
+        key: Key,
+        time: Instant,
+        commit_buf: &mut String,
+    ) -> bool {
+        let data = config.layout_at(self.layout());
+
+        if data.romaja {
+            // Restored preedit is edited after romaja
+            self.romaja_key(key) || key.code == KeyCode::Backspace && self.backspace(&data.rules)
+        } else if key.code == KeyCode::Backspace {
+            self.end_chord(commit_buf);
+            self.backspace(&data.rules)
+        } else if let Some(kv) = data.layout.lookup_kv(key) {
+            match config.moachigi {
+                Some(window) if !matches!(kv, KeyValue::Pass(_)) => {
+                    self.chord_key(key, kv, &data.rules, time, window, commit_buf)
+                }
+                _ => {
+                    self.end_chord(commit_buf);
+                    self.key(kv, &data.rules, commit_buf)
+                }
+            }
         } else {
             false
         }
     }
 
+    fn release_key(
+        &mut self,
+        config: &HangulData,
+        key: Key,
+        time: Instant,
+        commit_buf: &mut String,
+    ) -> bool {
+        match config.moachigi {
+            Some(window) => self.release_chord_key(key, time, window, commit_buf),
+            None => false,
+        }
+    }
+
     #[inline]
     fn clear_preedit(&mut self, commit_buf: &mut String) {
         self.clear_preedit(commit_buf);
@@ -188,6 +388,46 @@ impl InputEngineBackend for HangulEngine {
     fn preedit_str(&self, buf: &mut String) {
         self.preedit_str(buf);
     }
+
+    fn preedit(&self, preedit: &mut Preedit) {
+        self.preedit(preedit);
+    }
+
+    #[inline]
+    fn reopen_surrounding(&mut self, text: &str) -> usize {
+        self.reopen(text)
+    }
+
+    #[inline]
+    fn save_state(&self) -> Option<String> {
+        self.save_state()
+    }
+
+    #[inline]
+    fn restore_state(&mut self, state: &str) -> bool {
+        self.restore_state(state)
+    }
+
+    fn layout(&self) -> Option<&str> {
+        Some(self.layout_name())
+    }
+
+    fn select_layout(&mut self, config: &HangulData, name: Option<&str>) -> bool {
+        let idx = match name {
+            Some(name) => match config.layout_index(name) {
+                Some(idx) => idx,
+                None => return false,
+            },
+            None => (self.layout() + 1) % config.layouts.len(),
+        };
+
+        self.set_layout(idx, &config.layouts[idx].name, config.layouts[idx].romaja);
+        true
+    }
+
+    fn alternatives(&self) -> &[String] {
+        self.romaja_candidates()
+    }
 }
 
 pub fn builtin_layouts() -> impl Iterator<Item = (Cow<'static, str>, Layout)> {
@@ -0,0 +1,111 @@
+use serde::{Deserialize, Serialize};
+
+use crate::characters::{Choseong, Jongseong, Jungseong};
+
+/// Unicode form of committed hangul
+#[derive(Clone, Copy, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub enum CommitNormalization {
+    /// Precomposed syllables, incomplete syllables are committed as compatibility jamo
+    #[default]
+    Nfc,
+    /// Syllables are decomposed into conjoining jamo, incomplete syllables are committed as compatibility jamo
+    Nfd,
+    /// Every hangul is committed as conjoining jamo, missing jamo of incomplete syllable is filled with filler
+    Conjoining,
+}
+
+impl CommitNormalization {
+    /// Push `text` into `out` with this form, characters which aren't hangul are pushed as it is
+    pub fn normalize(self, text: &str, out: &mut String) {
+        for ch in text.chars() {
+            self.push_char(ch, out);
+        }
+    }
+
+    /// Normalize text of `buf` after `start` in place
+    pub fn normalize_from(self, buf: &mut String, start: usize) {
+        if self == CommitNormalization::Nfc
+            || !buf[start..].chars().any(|ch| self.need_normalize(ch))
+        {
+            return;
+        }
+
+        let text = buf.split_off(start);
+        self.normalize(&text, buf);
+    }
+
+    fn need_normalize(self, ch: char) -> bool {
+        match self {
+            CommitNormalization::Nfc => false,
+            CommitNormalization::Nfd => Choseong::decompose(ch).is_some(),
+            CommitNormalization::Conjoining => {
+                Choseong::decompose(ch).is_some() || is_compat_jamo(ch)
+            }
+        }
+    }
+
+    fn push_char(self, ch: char, out: &mut String) {
+        if self == CommitNormalization::Nfc {
+            out.push(ch);
+        } else if let Some((cho, jung, jong)) = Choseong::decompose(ch) {
+            out.push(cho.into());
+            out.push(jung.into());
+            if let Some(jong) = jong {
+                out.push(jong.into());
+            }
+        } else if self == CommitNormalization::Nfd || !is_compat_jamo(ch) {
+            // Conjoining jamo e.g. old hangul is already committed as it is
+            out.push(ch);
+        } else if let Some(cho) = Choseong::from_jamo(ch) {
+            out.push(cho.into());
+            out.push(Jungseong::FILLER);
+        } else if let Some(jung) = Jungseong::from_jamo(ch) {
+            out.push(Choseong::FILLER);
+            out.push(jung.into());
+        } else if let Some(jong) = Jongseong::from_jamo(ch) {
+            out.push(Choseong::FILLER);
+            out.push(Jungseong::FILLER);
+            out.push(jong.into());
+        } else {
+            out.push(ch);
+        }
+    }
+}
+
+/// Hangul compatibility jamo block, conjoining jamo are outside of it
+fn is_compat_jamo(ch: char) -> bool {
+    ('\u{3131}'..='\u{318E}').contains(&ch)
+}
+
+#[test]
+fn normalize_commit() {
+    let normalize = |normalization: CommitNormalization, text: &str| {
+        let mut out = String::new();
+        normalization.normalize(text, &mut out);
+        out
+    };
+
+    assert_eq!(normalize(CommitNormalization::Nfc, "한ㄱa"), "한ㄱa");
+    assert_eq!(
+        normalize(CommitNormalization::Nfd, "한ㄱa"),
+        "\u{1112}\u{1161}\u{11AB}ㄱa"
+    );
+    assert_eq!(
+        normalize(CommitNormalization::Conjoining, "가ㄱㅏㄳ"),
+        "\u{1100}\u{1161}\u{1100}\u{1160}\u{115F}\u{1161}\u{115F}\u{1160}\u{11AA}"
+    );
+
+    // Conjoining jamo of extended blocks aren't filled again
+    assert_eq!(
+        normalize(CommitNormalization::Conjoining, "\u{A964}\u{1161}ㅁ"),
+        "\u{A964}\u{1161}\u{1106}\u{1160}"
+    );
+    assert_eq!(
+        normalize(CommitNormalization::Conjoining, "\u{1106}\u{1161}\u{D7E0}"),
+        "\u{1106}\u{1161}\u{D7E0}"
+    );
+
+    let mut buf = "가나".to_string();
+    CommitNormalization::Nfd.normalize_from(&mut buf, "가".len());
+    assert_eq!(buf, "가\u{1102}\u{1161}");
+}
@@ -0,0 +1,150 @@
+//! Find keys which type given hangul text
+
+use num_traits::FromPrimitive;
+
+use crate::{
+    characters::{Choseong, JongToCho, Jongseong, Jungseong, KeyValue},
+    compose::ComposeRules,
+    layout::Layout,
+    Key,
+};
+
+struct KeyFinder<'a> {
+    layout: &'a Layout,
+    rules: &'a ComposeRules,
+    keys: Vec<Key>,
+}
+
+impl<'a> KeyFinder<'a> {
+    /// Prefer key which only types that jamo
+    fn find(
+        &self,
+        single: impl Fn(KeyValue) -> bool,
+        multi: impl Fn(KeyValue) -> bool,
+    ) -> Option<Key> {
+        self.layout
+            .find_key(single)
+            .or_else(|| self.layout.find_key(multi))
+    }
+
+    fn cho(&mut self, cho: Choseong) -> Option<()> {
+        let key = self.find(
+            |kv| matches!(kv, KeyValue::Choseong { cho: c } if c == cho),
+            |kv| {
+                matches!(kv, KeyValue::ChoJong { cho: c, .. } | KeyValue::ChoJung { cho: c, .. } if c == cho)
+            },
+        );
+
+        match key {
+            Some(key) => self.keys.push(key),
+            None => {
+                let (left, right) =
+                    compose_pair(|l: Choseong, r| l.try_add(r, self.rules) == Some(cho))?;
+                self.cho(left)?;
+                self.cho(right)?;
+            }
+        }
+
+        Some(())
+    }
+
+    fn jung(&mut self, jung: Jungseong) -> Option<()> {
+        let key = self.find(
+            |kv| matches!(kv, KeyValue::Jungseong { jung: j, .. } if j == jung),
+            |kv| {
+                matches!(kv, KeyValue::ChoJung { jung: j, .. } | KeyValue::JungJong { jung: j, .. } if j == jung)
+            },
+        );
+
+        match key {
+            Some(key) => self.keys.push(key),
+            None => {
+                let (left, right) =
+                    compose_pair(|l: Jungseong, r| l.try_add(r, self.rules) == Some(jung))?;
+                self.jung(left)?;
+                self.jung(right)?;
+            }
+        }
+
+        Some(())
+    }
+
+    fn jong(&mut self, jong: Jongseong) -> Option<()> {
+        let key = self.find(
+            |kv| matches!(kv, KeyValue::Jongseong { jong: j } if j == jong),
+            |kv| {
+                matches!(kv, KeyValue::ChoJong { jong: j, .. } | KeyValue::JungJong { jong: j, .. } if j == jong)
+            },
+        );
+
+        if let Some(key) = key {
+            self.keys.push(key);
+            return Some(());
+        }
+
+        let has_jong_key = self
+            .layout
+            .find_key(|kv| matches!(kv, KeyValue::Jongseong { .. }))
+            .is_some();
+
+        if has_jong_key {
+            let (left, right) =
+                compose_pair(|l: Jongseong, r| l.try_add(r, self.rules) == Some(jong))?;
+            self.jong(left)?;
+            self.jong(right)
+        } else {
+            // Layout like dubeolsik type jongseong with choseong keys
+            match jong.to_cho(self.rules)? {
+                JongToCho::Direct(cho) => self.cho(cho),
+                JongToCho::Compose(left, right) => {
+                    self.jong(left)?;
+                    self.cho(right)
+                }
+            }
+        }
+    }
+
+    fn character(&mut self, ch: char) -> Option<()> {
+        if let Some((cho, jung, jong)) = Choseong::decompose(ch) {
+            self.cho(cho)?;
+            self.jung(jung)?;
+            if let Some(jong) = jong {
+                self.jong(jong)?;
+            }
+            Some(())
+        } else if let Some(cho) = Choseong::from_jamo(ch) {
+            self.cho(cho)
+        } else if let Some(jung) = Jungseong::from_jamo(ch) {
+            self.jung(jung)
+        } else if let Some(jong) = Jongseong::from_jamo(ch) {
+            self.jong(jong)
+        } else {
+            None
+        }
+    }
+}
+
+fn compose_pair<T: FromPrimitive + Copy>(f: impl Fn(T, T) -> bool) -> Option<(T, T)> {
+    let all = || (0..).map_while(T::from_u32);
+
+    all().find_map(|left| {
+        all()
+            .find(|right| f(left, *right))
+            .map(|right| (left, right))
+    })
+}
+
+/// Keys may not type exactly same `text` caller should verify it
+pub fn keys_of(layout: &Layout, rules: &ComposeRules, text: &str) -> Option<Vec<Key>> {
+    let mut finder = KeyFinder {
+        layout,
+        rules,
+        keys: Vec::with_capacity(text.len()),
+    };
+
+    for ch in text.chars() {
+        finder.character(ch)?;
+    }
+
+    Some(finder.keys)
+}
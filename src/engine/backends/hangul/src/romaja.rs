@@ -0,0 +1,348 @@
+//! Revised Romanization of Korean input
+
+use kime_engine_backend::{Key, KeyCode, ModifierState, Preedit, PreeditAttribute};
+
+use crate::{
+    characters::{Choseong, Jongseong, Jungseong},
+    state::CharacterState,
+};
+
+/// Name of layout which types romaja
+pub const ROMAJA_LAYOUT: &str = "romaja";
+
+/// Maximum count of candidates
+const MAX_CANDIDATES: usize = 8;
+
+const ONSETS: &[(&str, Choseong)] = &[
+    ("kk", Choseong::SsangGiyeok),
+    ("tt", Choseong::SsangDigeut),
+    ("pp", Choseong::SsangBieup),
+    ("ss", Choseong::SsangSiot),
+    ("jj", Choseong::SsangJieut),
+    ("ch", Choseong::Chieut),
+    ("g", Choseong::Giyeok),
+    ("n", Choseong::Nieun),
+    ("d", Choseong::Digeut),
+    ("r", Choseong::Rieul),
+    ("l", Choseong::Rieul),
+    ("m", Choseong::Mieum),
+    ("b", Choseong::Bieup),
+    ("s", Choseong::Siot),
+    ("j", Choseong::Jieut),
+    ("k", Choseong::Kiyeok),
+    ("t", Choseong::Tieut),
+    ("p", Choseong::Pieup),
+    ("h", Choseong::Hieuh),
+];
+
+const VOWELS: &[(&str, Jungseong)] = &[
+    ("yae", Jungseong::YAE),
+    ("yeo", Jungseong::YEO),
+    ("wae", Jungseong::WAE),
+    ("ae", Jungseong::AE),
+    ("ya", Jungseong::YA),
+    ("eo", Jungseong::EO),
+    ("ye", Jungseong::YE),
+    ("wa", Jungseong::WA),
+    ("oe", Jungseong::OE),
+    ("yo", Jungseong::YO),
+    ("wo", Jungseong::WEO),
+    ("we", Jungseong::WE),
+    ("wi", Jungseong::WI),
+    ("yu", Jungseong::YU),
+    ("eu", Jungseong::EU),
+    ("ui", Jungseong::YI),
+    ("a", Jungseong::A),
+    ("e", Jungseong::E),
+    ("o", Jungseong::O),
+    ("u", Jungseong::U),
+    ("i", Jungseong::I),
+];
+
+const CODAS: &[(&str, Jongseong)] = &[
+    ("kk", Jongseong::SsangGiyeok),
+    ("ss", Jongseong::SsangSiot),
+    ("ng", Jongseong::Ieung),
+    ("ch", Jongseong::Chieut),
+    ("g", Jongseong::Giyeok),
+    ("k", Jongseong::Giyeok),
+    ("n", Jongseong::Nieun),
+    ("d", Jongseong::Digeut),
+    ("t", Jongseong::Digeut),
+    ("l", Jongseong::Rieul),
+    ("m", Jongseong::Mieum),
+    ("b", Jongseong::Bieup),
+    ("p", Jongseong::Bieup),
+    ("s", Jongseong::Siot),
+    ("j", Jongseong::Jieut),
+    ("h", Jongseong::Hieuh),
+];
+
+/// Explicit syllable boundary e.g. `jeong-eum`
+fn is_boundary(ch: char) -> bool {
+    matches!(ch, '-' | '\'')
+}
+
+/// Latin letter of key, boundary chars are also typed
+pub fn key_char(key: Key) -> Option<char> {
+    let key = key.without_locks();
+
+    if !(key.state.is_empty() || key.state == ModifierState::SHIFT) {
+        return None;
+    }
+
+    let ch = match key.code {
+        KeyCode::A => 'a',
+        KeyCode::B => 'b',
+        KeyCode::C => 'c',
+        KeyCode::D => 'd',
+        KeyCode::E => 'e',
+        KeyCode::F => 'f',
+        KeyCode::G => 'g',
+        KeyCode::H => 'h',
+        KeyCode::I => 'i',
+        KeyCode::J => 'j',
+        KeyCode::K => 'k',
+        KeyCode::L => 'l',
+        KeyCode::M => 'm',
+        KeyCode::N => 'n',
+        KeyCode::O => 'o',
+        KeyCode::P => 'p',
+        KeyCode::Q => 'q',
+        KeyCode::R => 'r',
+        KeyCode::S => 's',
+        KeyCode::T => 't',
+        KeyCode::U => 'u',
+        KeyCode::V => 'v',
+        KeyCode::W => 'w',
+        KeyCode::X => 'x',
+        KeyCode::Y => 'y',
+        KeyCode::Z => 'z',
+        KeyCode::Minus if key.state.is_empty() => '-',
+        KeyCode::Quote if key.state.is_empty() => '\'',
+        _ => return None,
+    };
+
+    Some(ch)
+}
+
+/// Syllable which starts at some position of text
+struct Syllable {
+    end: usize,
+    state: CharacterState,
+    /// Letters which aren't jamo yet, only last syllable can have it
+    rest: usize,
+    /// `ng` coda e.g. `jeong` of `jeongeum`
+    ng: bool,
+}
+
+fn prefixes<'a, T: Copy>(
+    table: &'a [(&'static str, T)],
+    text: &'a str,
+) -> impl Iterator<Item = (usize, T)> + 'a {
+    table
+        .iter()
+        .filter(move |(s, _)| text.starts_with(s))
+        .map(|(s, jamo)| (s.len(), *jamo))
+}
+
+/// `text` is a part of some item of `table` but not an item
+fn is_partial<T>(table: &[(&str, T)], text: &str) -> bool {
+    table.iter().all(|(s, _)| *s != text) && table.iter().any(|(s, _)| s.starts_with(text))
+}
+
+/// Syllables which can start at `start`, shorter one comes first so that consonant goes to next syllable
+fn syllables(text: &str, start: usize) -> Vec<Syllable> {
+    let mut ret = Vec::new();
+    let onsets = std::iter::once((0, None))
+        .chain(prefixes(ONSETS, &text[start..]).map(|(len, cho)| (len, Some(cho))));
+
+    for (onset_len, cho) in onsets {
+        let vowel_start = start + onset_len;
+
+        for (vowel_len, jung) in prefixes(VOWELS, &text[vowel_start..]) {
+            let coda_start = vowel_start + vowel_len;
+            let cho = cho.unwrap_or(Choseong::Ieung);
+
+            ret.push(Syllable {
+                end: coda_start,
+                state: CharacterState::with_jamo(Some(cho), Some(jung), None),
+                rest: 0,
+                ng: false,
+            });
+
+            for (coda_len, jong) in prefixes(CODAS, &text[coda_start..]) {
+                ret.push(Syllable {
+                    end: coda_start + coda_len,
+                    state: CharacterState::with_jamo(Some(cho), Some(jung), Some(jong)),
+                    rest: 0,
+                    ng: jong == Jongseong::Ieung,
+                });
+            }
+        }
+
+        // Last syllable which is being typed e.g. `ny` of `anny`
+        let rest = &text[vowel_start..];
+        let partial = if rest.is_empty() {
+            cho.is_some()
+        } else {
+            is_partial(VOWELS, rest) || cho.is_none() && is_partial(ONSETS, rest)
+        };
+
+        if partial {
+            ret.push(Syllable {
+                end: text.len(),
+                state: CharacterState::with_jamo(cho, None, None),
+                rest: rest.len(),
+                ng: false,
+            });
+        }
+    }
+
+    ret.sort_by_key(|s| s.end);
+    ret
+}
+
+/// Segmentations of `text` which have least syllables
+fn segment(text: &str) -> Vec<String> {
+    // `best[i]` is (syllable count, candidates) of `text[i..]`
+    let mut best: Vec<Option<(usize, Vec<String>)>> = vec![None; text.len() + 1];
+    best[text.len()] = Some((0, vec![String::new()]));
+
+    for start in (0..text.len()).rev() {
+        if !text.is_char_boundary(start) {
+            continue;
+        }
+
+        if text[start..].starts_with(is_boundary) {
+            best[start] = best[start + 1].clone();
+            continue;
+        }
+
+        let mut count = usize::MAX;
+        let mut candidates = Vec::new();
+        let mut syllables = syllables(text, start);
+
+        // `ng` comes first only when `n` and `g` can't be split e.g. `jeong-eum` is needed for `정음`
+        syllables.sort_by_key(|s| !(s.ng && best[s.end - 1].is_none()));
+
+        for syllable in syllables {
+            let (next_count, next) = match &best[syllable.end] {
+                Some(next) => next,
+                None => continue,
+            };
+
+            if next_count + 1 > count {
+                continue;
+            }
+
+            if next_count + 1 < count {
+                count = next_count + 1;
+                candidates.clear();
+            }
+
+            let mut prefix = String::new();
+            syllable.state.commit(&mut prefix);
+            prefix.push_str(&text[syllable.end - syllable.rest..syllable.end]);
+
+            for next in next.iter() {
+                if candidates.len() >= MAX_CANDIDATES {
+                    break;
+                }
+                candidates.push(format!("{}{}", prefix, next));
+            }
+        }
+
+        if !candidates.is_empty() {
+            best[start] = Some((count, candidates));
+        }
+    }
+
+    best.swap_remove(0)
+        .map(|(_, candidates)| candidates)
+        .unwrap_or_default()
+}
+
+/// Romaja which is being typed
+#[derive(Clone, Debug, Default)]
+pub struct RomajaState {
+    text: String,
+    candidates: Vec<String>,
+}
+
+impl RomajaState {
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    fn update(&mut self) {
+        self.candidates = segment(&self.text);
+    }
+
+    /// Boundary can't start romaja
+    pub fn push(&mut self, ch: char) -> bool {
+        if self.text.is_empty() && is_boundary(ch) {
+            return false;
+        }
+
+        self.text.push(ch);
+        self.update();
+        true
+    }
+
+    pub fn backspace(&mut self) -> bool {
+        if self.text.pop().is_some() {
+            self.update();
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Candidates of ambiguous segmentation e.g. `jeongeum` is `정음` or `전금`
+    pub fn candidates(&self) -> &[String] {
+        &self.candidates
+    }
+
+    /// First candidate is typed, text which isn't romaja is typed as it is
+    pub fn text(&self) -> &str {
+        self.candidates.first().map_or(&self.text, String::as_str)
+    }
+
+    /// Ambiguous text is selected and invalid text is shown as error
+    pub fn preedit(&self, preedit: &mut Preedit) {
+        let attribute = match self.candidates.len() {
+            0 => PreeditAttribute::Error,
+            1 => PreeditAttribute::Composing,
+            _ => PreeditAttribute::Selected,
+        };
+        preedit.push_str(self.text(), attribute);
+    }
+
+    pub fn commit(&mut self, commit_buf: &mut String) {
+        commit_buf.push_str(self.text());
+        self.clear();
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.candidates.clear();
+    }
+}
+
+#[test]
+fn segment_romaja() {
+    assert_eq!(segment("annyeonghaseyo")[0], "안녕하세요");
+    assert_eq!(segment("hangeul"), ["한글", "항을"]);
+    assert_eq!(segment("han-geul"), ["한글"]);
+    assert_eq!(segment("jeongeum"), ["전금", "정음"]);
+    assert_eq!(segment("hanguk")[0], "한국");
+    assert!(!segment("hangugeo")[0].starts_with('항'));
+    assert!(segment("hangugeo").iter().any(|s| s == "한국어"));
+    assert_eq!(segment("jeong-eum"), ["정음"]);
+    assert_eq!(segment("seoul")[0], "서울");
+    assert_eq!(segment("kkachi")[0], "까치");
+    assert_eq!(segment("anny")[0], "안ㄴy");
+    assert_eq!(segment("c"), ["c"]);
+    assert!(segment("fox").is_empty());
+}
@@ -1,7 +1,11 @@
-use enumset::EnumSet;
+use kime_engine_backend::{Key, KeyCode, Preedit, PreeditAttribute};
+use serde::{Deserialize, Serialize};
+use std::time::{Duration, Instant};
 
 use crate::{
     characters::{Choseong, JongToCho, Jongseong, Jungseong, KeyValue},
+    compose::ComposeRules,
+    romaja::{self, RomajaState},
     Addon, PreeditJohabLevel,
 };
 
@@ -12,6 +16,34 @@ pub struct HangulEngine {
     word_commit: bool,
     preedit_johab: PreeditJohabLevel,
     word_buf: String,
+    /// Index of active layout in `HangulData`
+    layout: usize,
+    layout_name: String,
+    /// Keys which are being pressed together in moachigi
+    chord: Option<Chord>,
+    /// `Some` when layout types romaja
+    romaja: Option<RomajaState>,
+}
+
+/// Keys which are typed as one syllable in moachigi
+#[derive(Debug, Clone)]
+struct Chord {
+    /// State before first key of chord
+    state: CharacterState,
+    word_buf: String,
+    keys: Vec<(KeyCode, KeyValue)>,
+    /// Keys of chord which aren't released yet
+    held: Vec<KeyCode>,
+    start: Instant,
+    /// Text committed by keys of chord, it's shown as preedit until chord ends
+    commit: String,
+}
+
+impl Chord {
+    fn can_join(&self, code: KeyCode, time: Instant, window: Duration) -> bool {
+        !self.keys.iter().any(|(c, _)| *c == code)
+            && (!self.held.is_empty() || time.saturating_duration_since(self.start) <= window)
+    }
 }
 
 impl HangulEngine {
@@ -21,30 +53,192 @@ impl HangulEngine {
             word_commit,
             preedit_johab,
             word_buf: String::new(),
+            layout: 0,
+            layout_name: String::new(),
+            chord: None,
+            romaja: None,
         }
     }
 
+    pub const fn layout(&self) -> usize {
+        self.layout
+    }
+
+    pub fn layout_name(&self) -> &str {
+        &self.layout_name
+    }
+
+    pub fn set_layout(&mut self, layout: usize, name: &str, romaja: bool) {
+        self.layout = layout;
+        self.layout_name.clear();
+        self.layout_name.push_str(name);
+        self.romaja = if romaja {
+            Some(RomajaState::default())
+        } else {
+            None
+        };
+    }
+
     pub fn has_preedit(&self) -> bool {
-        self.state.need_display() || !self.word_buf.is_empty()
+        self.state.need_display()
+            || !self.word_buf.is_empty()
+            || !self.chord_commit().is_empty()
+            || self.romaja.as_ref().is_some_and(|r| !r.is_empty())
+    }
+
+    /// Candidates of romaja which is being typed, empty when it's not ambiguous
+    pub fn romaja_candidates(&self) -> &[String] {
+        match self.romaja.as_ref().map(RomajaState::candidates) {
+            Some(candidates) if candidates.len() > 1 => candidates,
+            _ => &[],
+        }
+    }
+
+    /// Type romaja, alternatives of ambiguous romaja are shown by candidate window
+    pub fn romaja_key(&mut self, key: Key) -> bool {
+        let romaja = match self.romaja.as_mut() {
+            Some(romaja) => romaja,
+            None => return false,
+        };
+
+        match key.code {
+            KeyCode::Backspace => romaja.backspace(),
+            _ => match romaja::key_char(key) {
+                Some(ch) => romaja.push(ch),
+                None => false,
+            },
+        }
+    }
+
+    fn chord_commit(&self) -> &str {
+        self.chord
+            .as_ref()
+            .map_or("", |chord| chord.commit.as_str())
     }
 
     pub fn preedit_str(&self, buf: &mut String) {
+        buf.push_str(self.chord_commit());
         buf.push_str(&self.word_buf);
+        if let Some(romaja) = self.romaja.as_ref() {
+            buf.push_str(romaja.text());
+        }
         self.state.preedit(self.preedit_johab, buf);
     }
 
+    /// Current syllable is selected when word buffer isn't empty, romaja has its own attribute
+    pub fn preedit(&self, preedit: &mut Preedit) {
+        preedit.push_str(self.chord_commit(), PreeditAttribute::Composing);
+        preedit.push_str(&self.word_buf, PreeditAttribute::Composing);
+
+        let attribute = if let Some(romaja) = self.romaja.as_ref() {
+            romaja.preedit(preedit);
+            PreeditAttribute::Composing
+        } else if self.word_buf.is_empty() {
+            PreeditAttribute::Composing
+        } else {
+            PreeditAttribute::Selected
+        };
+        preedit.push_with(attribute, |buf| self.state.preedit(self.preedit_johab, buf));
+    }
+
     pub fn clear_preedit(&mut self, commit_buf: &mut String) {
+        self.end_chord(commit_buf);
         commit_buf.push_str(&self.word_buf);
         self.word_buf.clear();
+        if let Some(romaja) = self.romaja.as_mut() {
+            romaja.commit(commit_buf);
+        }
         self.state.commit(commit_buf);
         self.state.reset();
     }
 
     pub fn reset(&mut self) {
+        self.chord = None;
+        if let Some(romaja) = self.romaja.as_mut() {
+            romaja.clear();
+        }
         self.word_buf.clear();
         self.state.reset();
     }
 
+    /// Type `kv` together with other keys of current chord, new chord is started when `key` can't join it
+    ///
+    /// Keys of chord are typed again from the state before the chord in [`KeyValue::chord_order`]
+    pub fn chord_key(
+        &mut self,
+        key: Key,
+        kv: KeyValue,
+        rules: &ComposeRules,
+        time: Instant,
+        window: Duration,
+        commit_buf: &mut String,
+    ) -> bool {
+        let mut chord = match self.chord.take() {
+            Some(chord) if chord.can_join(key.code, time, window) => chord,
+            prev => {
+                if let Some(prev) = prev {
+                    commit_buf.push_str(&prev.commit);
+                }
+                Chord {
+                    state: self.state,
+                    word_buf: self.word_buf.clone(),
+                    keys: Vec::new(),
+                    held: Vec::new(),
+                    start: time,
+                    commit: String::new(),
+                }
+            }
+        };
+
+        chord.keys.push((key.code, kv));
+        chord.keys.sort_by_key(|(_, kv)| kv.chord_order());
+        chord.held.push(key.code);
+        chord.commit.clear();
+        self.state = chord.state;
+        self.word_buf.clone_from(&chord.word_buf);
+
+        for (_, kv) in chord.keys.iter() {
+            self.key(*kv, rules, &mut chord.commit);
+        }
+
+        self.chord = Some(chord);
+        true
+    }
+
+    /// Chord ends when all keys of it are released after `window`
+    ///
+    /// # Return
+    ///
+    /// `true` when chord has ended
+    pub fn release_chord_key(
+        &mut self,
+        key: Key,
+        time: Instant,
+        window: Duration,
+        commit_buf: &mut String,
+    ) -> bool {
+        let chord = match self.chord.as_mut() {
+            Some(chord) => chord,
+            None => return false,
+        };
+
+        chord.held.retain(|code| *code != key.code);
+
+        if chord.held.is_empty() && time.saturating_duration_since(chord.start) > window {
+            self.end_chord(commit_buf);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Commit text of current chord, later keys are typed as new chord
+    pub fn end_chord(&mut self, commit_buf: &mut String) {
+        if let Some(chord) = self.chord.take() {
+            commit_buf.push_str(&chord.commit);
+        }
+    }
+
     fn convert_result(&mut self, ret: CharacterResult, commit_buf: &mut String) -> bool {
         match ret {
             CharacterResult::Consume => true,
@@ -60,54 +254,114 @@ impl HangulEngine {
         }
     }
 
-    pub fn backspace(&mut self, addons: EnumSet<Addon>, commit_buf: &mut String) -> bool {
-        if self.state.backspace(addons) {
+    pub fn backspace(&mut self, rules: &ComposeRules) -> bool {
+        if self.state.backspace(rules) {
             true
-        } else if commit_buf.pop().is_some() {
+        } else if let Some(ch) = self.word_buf.pop() {
+            // Edit last character of word again
+            if let Some(state) = CharacterState::from_char(ch) {
+                self.state = state;
+                self.state.backspace(rules);
+            }
             true
         } else {
             false
         }
     }
 
-    pub fn key(&mut self, kv: KeyValue, addons: EnumSet<Addon>, commit_buf: &mut String) -> bool {
+    /// Serialize preedit state
+    pub fn save_state(&self) -> Option<String> {
+        if !self.has_preedit() {
+            return None;
+        }
+
+        let romaja = self.romaja.as_ref().map_or("", RomajaState::text);
+        serde_yaml::to_string(&SavedState {
+            word_buf: format!("{}{}{}", self.chord_commit(), self.word_buf, romaja),
+            state: self.state,
+        })
+        .ok()
+    }
+
+    /// Restore preedit state which is made by [`HangulEngine::save_state`]
+    pub fn restore_state(&mut self, state: &str) -> bool {
+        match serde_yaml::from_str::<SavedState>(state) {
+            Ok(saved) => {
+                self.chord = None;
+                if let Some(romaja) = self.romaja.as_mut() {
+                    romaja.clear();
+                }
+                self.word_buf = saved.word_buf;
+                self.state = saved.state;
+                true
+            }
+            Err(_) => false,
+        }
+    }
+
+    /// Take last character of `text` into preedit
+    ///
+    /// # Return
+    ///
+    /// count of chars taken from `text`
+    pub fn reopen(&mut self, text: &str) -> usize {
+        if self.has_preedit() || self.romaja.is_some() {
+            return 0;
+        }
+
+        match text.chars().next_back().and_then(CharacterState::from_char) {
+            Some(state) => {
+                self.state = state;
+                1
+            }
+            None => 0,
+        }
+    }
+
+    pub fn key(&mut self, kv: KeyValue, rules: &ComposeRules, commit_buf: &mut String) -> bool {
         let ret = match kv {
             KeyValue::Pass(pass) => {
                 self.clear_preedit(commit_buf);
                 commit_buf.push(pass);
                 return true;
             }
-            KeyValue::Choseong { cho } => self.state.cho(cho, addons),
-            KeyValue::Jungseong { jung, compose } => self.state.jung(jung, compose, addons),
-            KeyValue::Jongseong { jong } => self.state.jong(jong, addons),
-            KeyValue::ChoJong { cho, jong, first } => self.state.cho_jong(cho, jong, first, addons),
+            KeyValue::Choseong { cho } => self.state.cho(cho, rules),
+            KeyValue::Jungseong { jung, compose } => self.state.jung(jung, compose, rules),
+            KeyValue::Jongseong { jong } => self.state.jong(jong, rules),
+            KeyValue::ChoJong { cho, jong, first } => self.state.cho_jong(cho, jong, first, rules),
             KeyValue::ChoJung {
                 cho,
                 jung,
                 first,
                 compose,
-            } => self.state.cho_jung(cho, jung, first, compose, addons),
+            } => self.state.cho_jung(cho, jung, first, compose, rules),
             KeyValue::JungJong {
                 jung,
                 jong,
                 first,
                 compose,
-            } => self.state.jung_jong(jung, jong, first, compose, addons),
+            } => self.state.jung_jong(jung, jong, first, compose, rules),
         };
 
         self.convert_result(ret, commit_buf)
     }
 }
 
+#[derive(Serialize, Deserialize)]
+struct SavedState {
+    word_buf: String,
+    state: CharacterState,
+}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-enum CharacterResult {
+pub(crate) enum CharacterResult {
     Consume,
     NewCharacter(CharacterState),
 }
 
 /// 한글 글자 상태
-#[derive(Default, Clone, Copy, Debug, PartialEq, Eq)]
-struct CharacterState {
+#[derive(Default, Clone, Copy, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub(crate) struct CharacterState {
     cho: Option<Choseong>,
     jung: Option<Jungseong>,
     /// 조합용 중성
@@ -125,12 +379,61 @@ impl CharacterState {
         }
     }
 
+    pub const fn with_jamo(
+        cho: Option<Choseong>,
+        jung: Option<Jungseong>,
+        jong: Option<Jongseong>,
+    ) -> Self {
+        Self {
+            cho,
+            jung,
+            compose_jung: false,
+            jong,
+        }
+    }
+
+    /// Decompose committed character
+    pub fn from_char(ch: char) -> Option<Self> {
+        let mut state = Self::new();
+
+        if let Some((cho, jung, jong)) = Choseong::decompose(ch) {
+            state.cho = Some(cho);
+            state.jung = Some(jung);
+            state.jong = jong;
+        } else if let Some(cho) = Choseong::from_jamo(ch) {
+            state.cho = Some(cho);
+        } else if let Some(jung) = Jungseong::from_jamo(ch) {
+            state.jung = Some(jung);
+        } else if let Some(jong) = Jongseong::from_jamo(ch) {
+            state.jong = Some(jong);
+        } else {
+            return None;
+        }
+
+        Some(state)
+    }
+
     pub fn reset(&mut self) {
         self.cho = None;
         self.jung = None;
         self.jong = None;
     }
 
+    /// Old hangul syllable can't be precomposed so it's written as conjoining jamo
+    fn is_modern(&self) -> bool {
+        self.cho.is_none_or(Choseong::is_modern)
+            && self.jung.is_none_or(Jungseong::is_modern)
+            && self.jong.is_none_or(Jongseong::is_modern)
+    }
+
+    fn push_conjoining(cho: Choseong, jung: Jungseong, jong: Option<Jongseong>, out: &mut String) {
+        out.push(cho.into());
+        out.push(jung.into());
+        if let Some(jong) = jong {
+            out.push(jong.into());
+        }
+    }
+
     pub fn preedit(&self, preedit_johab: PreeditJohabLevel, out: &mut String) {
         macro_rules! to_char {
             ($jamo:expr) => {
@@ -169,16 +472,12 @@ impl CharacterState {
                 out.push(Jungseong::FILLER);
                 out.push(jong.into());
             }
-            (Some(cho), Some(jung), jong, PreeditJohabLevel::Needed | PreeditJohabLevel::Never) => {
+            (Some(cho), Some(jung), jong, PreeditJohabLevel::Needed | PreeditJohabLevel::Never)
+                if self.is_modern() =>
+            {
                 out.push(cho.compose(jung, jong))
             }
-            (Some(cho), Some(jung), jong, PreeditJohabLevel::Always) => {
-                out.push(cho.into());
-                out.push(jung.into());
-                if let Some(jong) = jong {
-                    out.push(jong.into());
-                }
-            }
+            (Some(cho), Some(jung), jong, _) => Self::push_conjoining(cho, jung, jong, out),
             (Some(cho), None, None, _) => out.push(to_char!(cho)),
             (None, Some(jung), None, _) => out.push(to_char!(jung)),
             (None, None, Some(jong), _) => out.push(to_char!(jong)),
@@ -196,7 +495,8 @@ impl CharacterState {
                 out.push(cho.jamo());
                 out.push(jong.jamo());
             }
-            (Some(cho), Some(jung), jong) => out.push(cho.compose(jung, jong)),
+            (Some(cho), Some(jung), jong) if self.is_modern() => out.push(cho.compose(jung, jong)),
+            (Some(cho), Some(jung), jong) => Self::push_conjoining(cho, jung, jong, out),
             (Some(cho), None, None) => out.push(cho.jamo()),
             (None, Some(jung), None) => out.push(jung.jamo()),
             (None, None, Some(jong)) => out.push(jong.jamo()),
@@ -210,15 +510,15 @@ impl CharacterState {
         }
     }
 
-    pub fn backspace(&mut self, addons: EnumSet<Addon>) -> bool {
+    pub fn backspace(&mut self, rules: &ComposeRules) -> bool {
         if let Some(jong) = self.jong.as_mut() {
-            if let Some(new_jong) = jong.backspace(addons) {
+            if let Some(new_jong) = jong.backspace(rules) {
                 *jong = new_jong;
             } else {
                 self.jong = None;
             }
         } else if let Some(jung) = self.jung.as_mut() {
-            if let Some(new_jung) = jung.backspace(addons) {
+            if let Some(new_jung) = jung.backspace(rules) {
                 *jung = new_jung;
                 self.compose_jung = true;
             } else {
@@ -226,7 +526,7 @@ impl CharacterState {
                 self.compose_jung = false;
             }
         } else if let Some(cho) = self.cho.as_mut() {
-            if let Some(new_cho) = cho.backspace(addons) {
+            if let Some(new_cho) = cho.backspace(rules) {
                 *cho = new_cho;
             } else {
                 self.cho = None;
@@ -239,11 +539,12 @@ impl CharacterState {
         true
     }
 
-    fn choseong_can_compose_jongseong(&self, cho: Choseong, addons: EnumSet<Addon>) -> bool {
-        self.jong.map_or(false, |j| match j.to_cho(addons) {
-            JongToCho::Direct(prev_cho) | JongToCho::Compose(_, prev_cho) => {
-                prev_cho.try_add(cho, addons).is_some()
+    fn choseong_can_compose_jongseong(&self, cho: Choseong, rules: &ComposeRules) -> bool {
+        self.jong.is_some_and(|j| match j.to_cho(rules) {
+            Some(JongToCho::Direct(prev_cho)) | Some(JongToCho::Compose(_, prev_cho)) => {
+                prev_cho.try_add(cho, rules).is_some()
             }
+            None => false,
         })
     }
 
@@ -252,19 +553,19 @@ impl CharacterState {
         ori_compose_jung: bool,
         jung: Jungseong,
         compose_jung: bool,
-        addons: EnumSet<Addon>,
+        rules: &ComposeRules,
     ) -> Option<Jungseong> {
-        if addons.contains(Addon::FlexibleComposeOrder) {
+        if rules.has_addon(Addon::FlexibleComposeOrder) {
             if ori_compose_jung || compose_jung {
                 ori_jung
-                    .try_add(jung, addons)
-                    .or_else(|| jung.try_add(ori_jung, addons))
+                    .try_add(jung, rules)
+                    .or_else(|| jung.try_add(ori_jung, rules))
             } else {
                 None
             }
         } else {
             if ori_compose_jung {
-                ori_jung.try_add(jung, addons)
+                ori_jung.try_add(jung, rules)
             } else {
                 None
             }
@@ -278,18 +579,18 @@ impl CharacterState {
         cho: Choseong,
         jong: Jongseong,
         first: bool,
-        addons: EnumSet<Addon>,
+        rules: &ComposeRules,
     ) -> CharacterResult {
         if self.cho.is_none()
             || self.jung.is_none()
-            || addons.contains(Addon::TreatJongseongAsChoseongCompose)
-                && self.choseong_can_compose_jongseong(cho, addons)
+            || rules.has_addon(Addon::TreatJongseongAsChoseongCompose)
+                && self.choseong_can_compose_jongseong(cho, rules)
         {
-            self.cho(cho, addons)
+            self.cho(cho, rules)
         } else if self.jung.is_some() || !first {
-            self.jong(jong, addons)
+            self.jong(jong, rules)
         } else {
-            self.cho(cho, addons)
+            self.cho(cho, rules)
         }
     }
 
@@ -299,18 +600,18 @@ impl CharacterState {
         jung: Jungseong,
         first: bool,
         compose_jung: bool,
-        addons: EnumSet<Addon>,
+        rules: &ComposeRules,
     ) -> CharacterResult {
         if self.cho.is_some()
             && self.jung.map_or(true, |j| {
-                Self::try_add_jungseong(j, self.compose_jung, jung, compose_jung, addons).is_some()
+                Self::try_add_jungseong(j, self.compose_jung, jung, compose_jung, rules).is_some()
             })
         {
-            self.jung(jung, compose_jung, addons)
+            self.jung(jung, compose_jung, rules)
         } else if self.cho.is_none() || first {
-            self.cho(cho, addons)
+            self.cho(cho, rules)
         } else {
-            self.jung(jung, compose_jung, addons)
+            self.jung(jung, compose_jung, rules)
         }
     }
 
@@ -320,7 +621,7 @@ impl CharacterState {
         jong: Jongseong,
         first: bool,
         compose_jung: bool,
-        addons: EnumSet<Addon>,
+        rules: &ComposeRules,
     ) -> CharacterResult {
         // 아 + $ㄴㅖ = 안
         // ㅇ + $ㅜ + $ㅊㅔ = 웨
@@ -329,38 +630,39 @@ impl CharacterState {
 
         if self.cho.is_none() && self.jung.is_none() {
             if first {
-                self.jung(jung, compose_jung, addons)
+                self.jung(jung, compose_jung, rules)
             } else {
-                self.jong(jong, addons)
+                self.jong(jong, rules)
             }
         } else if self.jung.map_or(true, |j| {
-            self.compose_jung && j.try_add(jung, addons).is_some()
+            self.compose_jung && j.try_add(jung, rules).is_some()
         }) {
-            self.jung(jung, compose_jung, addons)
+            self.jung(jung, compose_jung, rules)
         } else {
-            self.jong(jong, addons)
+            self.jong(jong, rules)
         }
     }
 
     // 일반 입력
 
-    pub fn cho(&mut self, mut cho: Choseong, addons: EnumSet<Addon>) -> CharacterResult {
+    pub fn cho(&mut self, mut cho: Choseong, rules: &ComposeRules) -> CharacterResult {
         if let Some(prev_cho) = self.cho {
             if let Some(jong) = self.jong {
-                if addons.contains(Addon::TreatJongseongAsChoseongCompose) {
-                    match jong.to_cho(addons) {
-                        JongToCho::Direct(prev_cho) => {
-                            if let Some(new_cho) = prev_cho.try_add(cho, addons) {
+                if rules.has_addon(Addon::TreatJongseongAsChoseongCompose) {
+                    match jong.to_cho(rules) {
+                        Some(JongToCho::Direct(prev_cho)) => {
+                            if let Some(new_cho) = prev_cho.try_add(cho, rules) {
                                 self.jong = None;
                                 cho = new_cho;
                             }
                         }
-                        JongToCho::Compose(jong, prev_cho) => {
-                            if let Some(new_cho) = prev_cho.try_add(cho, addons) {
+                        Some(JongToCho::Compose(jong, prev_cho)) => {
+                            if let Some(new_cho) = prev_cho.try_add(cho, rules) {
                                 self.jong = Some(jong);
                                 cho = new_cho;
                             }
                         }
+                        None => {}
                     }
                 }
 
@@ -369,7 +671,7 @@ impl CharacterState {
                     ..Default::default()
                 })
             } else {
-                match prev_cho.try_add(cho, addons) {
+                match prev_cho.try_add(cho, rules) {
                     Some(new) if self.jung.is_none() => {
                         self.cho = Some(new);
                         CharacterResult::Consume
@@ -380,7 +682,7 @@ impl CharacterState {
                     }),
                 }
             }
-        } else if addons.contains(Addon::FlexibleComposeOrder)
+        } else if rules.has_addon(Addon::FlexibleComposeOrder)
             || self.jung.is_none() && self.jong.is_none()
         {
             self.cho = Some(cho);
@@ -397,16 +699,16 @@ impl CharacterState {
         &mut self,
         jung: Jungseong,
         compose_jung: bool,
-        addons: EnumSet<Addon>,
+        rules: &ComposeRules,
     ) -> CharacterResult {
-        if addons.contains(Addon::TreatJongseongAsChoseong) {
+        if rules.has_addon(Addon::TreatJongseongAsChoseong) {
             if let Some(jong) = self.jong {
                 if self.cho.is_some() {
                     // has choseong move jongseong to next choseong
                     let new;
 
-                    match jong.to_cho(addons) {
-                        JongToCho::Direct(cho) => {
+                    match jong.to_cho(rules) {
+                        Some(JongToCho::Direct(cho)) => {
                             self.jong = None;
                             new = Self {
                                 cho: Some(cho),
@@ -415,7 +717,7 @@ impl CharacterState {
                                 compose_jung,
                             };
                         }
-                        JongToCho::Compose(jong, cho) => {
+                        Some(JongToCho::Compose(jong, cho)) => {
                             self.jong = Some(jong);
                             new = Self {
                                 cho: Some(cho),
@@ -424,6 +726,15 @@ impl CharacterState {
                                 compose_jung,
                             };
                         }
+                        // Jongseong stays when it can't be choseong
+                        None => {
+                            new = Self {
+                                cho: None,
+                                jung: Some(jung),
+                                jong: None,
+                                compose_jung,
+                            };
+                        }
                     }
 
                     return CharacterResult::NewCharacter(new);
@@ -440,8 +751,7 @@ impl CharacterState {
         }
 
         if let Some(prev_jung) = self.jung {
-            match Self::try_add_jungseong(prev_jung, self.compose_jung, jung, compose_jung, addons)
-            {
+            match Self::try_add_jungseong(prev_jung, self.compose_jung, jung, compose_jung, rules) {
                 Some(new) => {
                     self.jung = Some(new);
                     self.compose_jung = false;
@@ -453,7 +763,7 @@ impl CharacterState {
                     ..Default::default()
                 }),
             }
-        } else if self.jong.is_some() && !addons.contains(Addon::FlexibleComposeOrder) {
+        } else if self.jong.is_some() && !rules.has_addon(Addon::FlexibleComposeOrder) {
             // $ㅁ + ㅏ = ㅁㅏ
             // 초성없이 중성과 종성만 있는 경우를 배제
             CharacterResult::NewCharacter(Self {
@@ -468,9 +778,9 @@ impl CharacterState {
         }
     }
 
-    pub fn jong(&mut self, jong: Jongseong, addons: EnumSet<Addon>) -> CharacterResult {
+    pub fn jong(&mut self, jong: Jongseong, rules: &ComposeRules) -> CharacterResult {
         if let Some(prev_jong) = self.jong {
-            match prev_jong.try_add(jong, addons) {
+            match prev_jong.try_add(jong, rules) {
                 Some(new) => {
                     self.jong = Some(new);
                     CharacterResult::Consume
@@ -478,15 +788,15 @@ impl CharacterState {
                 None => {
                     let new;
 
-                    if addons.contains(Addon::TreatJongseongAsChoseong) {
-                        match jong.to_cho(addons) {
-                            JongToCho::Direct(cho) => {
+                    if rules.has_addon(Addon::TreatJongseongAsChoseong) {
+                        match jong.to_cho(rules) {
+                            Some(JongToCho::Direct(cho)) => {
                                 new = Self {
                                     cho: Some(cho),
                                     ..Default::default()
                                 };
                             }
-                            JongToCho::Compose(..) => {
+                            Some(JongToCho::Compose(..)) | None => {
                                 new = Self {
                                     jong: Some(jong),
                                     ..Default::default()
@@ -513,15 +823,20 @@ impl CharacterState {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::layout::Layout;
+    use enumset::EnumSet;
 
     #[test]
     fn jong() {
         let mut state = CharacterState::default();
-        let addons = EnumSet::only(Addon::TreatJongseongAsChoseong);
+        let rules = ComposeRules::new(
+            EnumSet::only(Addon::TreatJongseongAsChoseong),
+            &Default::default(),
+        );
 
-        state.cho_jong(Choseong::Ieung, Jongseong::Ieung, true, addons);
-        state.jung(Jungseong::A, true, addons);
-        state.cho_jong(Choseong::Ieung, Jongseong::Ieung, true, addons);
+        state.cho_jong(Choseong::Ieung, Jongseong::Ieung, true, &rules);
+        state.jung(Jungseong::A, true, &rules);
+        state.cho_jong(Choseong::Ieung, Jongseong::Ieung, true, &rules);
 
         assert_eq!(
             CharacterResult::NewCharacter(CharacterState {
@@ -530,7 +845,44 @@ mod tests {
                 compose_jung: true,
                 jong: None
             }),
-            state.jung(Jungseong::A, true, addons)
+            state.jung(Jungseong::A, true, &rules)
+        );
+    }
+
+    #[test]
+    fn compose_table() {
+        let layout = Layout::load_from(
+            "
+compose:
+  choseong:
+    ㄱㄱ: ㄲ
+  jungseong:
+    ㅗㅗ: ㅛ
+  jongseong:
+    ㄱㅅ: ~
+",
+        )
+        .unwrap();
+        let rules = ComposeRules::new(EnumSet::empty(), layout.compose_table());
+
+        assert_eq!(
+            Some(Choseong::SsangGiyeok),
+            Choseong::Giyeok.try_add(Choseong::Giyeok, &rules)
+        );
+        assert_eq!(
+            Some(Jungseong::YO),
+            Jungseong::O.try_add(Jungseong::O, &rules)
+        );
+        assert_eq!(Some(Jungseong::O), Jungseong::YO.backspace(&rules));
+        // builtin rules still work
+        assert_eq!(
+            Some(Jungseong::WA),
+            Jungseong::O.try_add(Jungseong::A, &rules)
+        );
+        assert_eq!(None, Jongseong::Giyeok.try_add(Jongseong::Siot, &rules));
+        assert_eq!(
+            Some(Jongseong::NieunHieuh),
+            Jongseong::Nieun.try_add(Jongseong::Hieuh, &rules)
         );
     }
 
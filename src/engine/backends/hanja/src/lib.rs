@@ -4,41 +4,194 @@ use kime_engine_backend::{
     Key,
 };
 
-use kime_engine_candidate::client::Client;
+use kime_engine_candidate::client::{CandidateWindow, Client};
+use kime_engine_dict::user::{self, FileStamp, UserDict};
+use std::collections::VecDeque;
+use std::path::PathBuf;
+
+type Entries = Vec<(String, String)>;
+
+/// Where hanja mode finds its dictionary and candidate window
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct HanjaConfig {
+    /// Directory of user dictionary, selection isn't saved when it's `None`
+    pub dict_dir: Option<PathBuf>,
+    pub candidate_window: CandidateWindow,
+}
+
+impl Default for HanjaConfig {
+    fn default() -> Self {
+        Self {
+            dict_dir: user::user_dict_dir(),
+            candidate_window: CandidateWindow::default(),
+        }
+    }
+}
 
 #[derive(Debug)]
 pub struct HanjaMode {
+    config: HanjaConfig,
     client: Option<Client>,
+    /// Segment which is shown by `client`
+    current: String,
+    /// Segments which are converted after `current`
+    segments: VecDeque<(String, Option<Entries>)>,
+    /// Converted text of previous segments
+    converted: String,
+    /// Selection is recorded to user dictionary, alternatives of preedit aren't hanja
+    record: bool,
+    /// Dictionary of user, it's reloaded when its files are changed
+    dict: UserDict,
+    dict_stamps: [FileStamp; 3],
 }
 
 impl Default for HanjaMode {
     fn default() -> Self {
-        Self::new()
+        Self::new(HanjaConfig::default())
     }
 }
 
 impl HanjaMode {
-    pub fn new() -> Self {
-        Self { client: None }
+    pub fn new(config: HanjaConfig) -> Self {
+        Self {
+            config,
+            client: None,
+            current: String::new(),
+            segments: VecDeque::new(),
+            converted: String::new(),
+            record: true,
+            dict: UserDict::default(),
+            dict_stamps: Default::default(),
+        }
     }
 
+    /// Convert `key` by segments, candidates of each segment are shown in order
     pub fn set_key(&mut self, key: &str) -> bool {
-        if let Some(entires) = kime_engine_dict::lookup(key) {
-            match Client::new(entires) {
-                Ok(client) => {
-                    self.client = Some(client);
-                    true
-                }
-                Err(err) => {
-                    #[cfg(debug_assertions)]
-                    eprintln!("Can't spawn candidate window {:#?}", err);
-                    false
-                }
-            }
+        self.clear();
+
+        self.reload_dict();
+        let segments = self.dict.segment(key);
+
+        if segments.iter().all(|(_, entries)| entries.is_none()) {
+            return false;
+        }
+
+        self.segments = segments
+            .into_iter()
+            .map(|(text, entries)| {
+                let entries = entries.map(|entries| {
+                    entries
+                        .into_iter()
+                        .map(|(hanja, description)| (hanja.into(), description.into()))
+                        .collect()
+                });
+                (text.into(), entries)
+            })
+            .collect();
+
+        if self.next_segment() {
+            true
         } else {
+            self.clear();
             false
         }
     }
+
+    /// Show `alternatives` of preedit e.g. ambiguous romaja, first one is kept when nothing is selected
+    pub fn set_alternatives(&mut self, alternatives: &[String]) -> bool {
+        self.clear();
+
+        let current = match alternatives.first() {
+            Some(current) => current.clone(),
+            None => return false,
+        };
+        let entries = alternatives
+            .iter()
+            .map(|alternative| (alternative.clone(), String::new()))
+            .collect();
+        self.segments.push_back((current, Some(entries)));
+        self.record = false;
+
+        if self.next_segment() {
+            true
+        } else {
+            self.clear();
+            false
+        }
+    }
+
+    /// Show candidates of next segment which is in dictionary, segments before it are kept as hangul
+    ///
+    /// Return `false` when there is no segment to show
+    fn next_segment(&mut self) -> bool {
+        while let Some((text, entries)) = self.segments.pop_front() {
+            if let Some(entries) = entries {
+                let entries: Vec<(&str, &str)> = entries
+                    .iter()
+                    .map(|(hanja, description)| (hanja.as_str(), description.as_str()))
+                    .collect();
+
+                match Client::new(&self.config.candidate_window, &entries) {
+                    Ok(client) => {
+                        self.client = Some(client);
+                        self.current = text;
+                        return true;
+                    }
+                    Err(err) => {
+                        #[cfg(debug_assertions)]
+                        eprintln!("Can't spawn candidate window {:#?}", err);
+                    }
+                }
+            }
+
+            self.converted.push_str(&text);
+        }
+
+        false
+    }
+
+    /// Dictionary can be changed by other engine or `kime-hanja`
+    fn reload_dict(&mut self) {
+        if let Some(dir) = self.config.dict_dir.as_deref() {
+            let stamps = user::file_stamps(dir);
+
+            if stamps != self.dict_stamps {
+                self.dict = UserDict::load_from(dir);
+                self.dict_stamps = stamps;
+            }
+        }
+    }
+
+    /// Selected candidate comes first next time
+    fn record_selection(&mut self, hanja: &str) {
+        self.reload_dict();
+        self.dict.record(&self.current, hanja);
+
+        let dir = match self.config.dict_dir.as_deref() {
+            Some(dir) => dir,
+            None => return,
+        };
+
+        match self.dict.save_freq_to(dir) {
+            Ok(()) => {
+                self.dict_stamps = user::file_stamps(dir);
+            }
+            Err(_err) => {
+                #[cfg(debug_assertions)]
+                eprintln!("Can't save user hanja dictionary {:#?}", _err);
+            }
+        }
+    }
+
+    fn clear(&mut self) {
+        if let Some(client) = self.client.take() {
+            client.close().ok();
+        }
+        self.current.clear();
+        self.segments.clear();
+        self.converted.clear();
+        self.record = true;
+    }
 }
 
 impl InputEngineMode for HanjaMode {
@@ -57,7 +210,7 @@ impl InputEngineMode for HanjaMode {
     }
 
     fn reset(&mut self) -> InputEngineModeResult<()> {
-        self.client.take().and_then(|c| c.close().ok());
+        self.clear();
 
         ExitHandled(())
     }
@@ -71,16 +224,28 @@ impl InputEngineMode for HanjaMode {
     }
 
     fn end_ready(&mut self, commit_buf: &mut String) -> InputEngineModeResult<()> {
-        match self.client.take() {
-            Some(client) => {
-                if let Ok(Some(res)) = client.close() {
-                    commit_buf.push_str(&res);
-                    ExitHandled(())
-                } else {
-                    Exit
+        let client = match self.client.take() {
+            Some(client) => client,
+            None => return Exit,
+        };
+
+        // Keep hangul when candidate window is closed without selection
+        match client.close() {
+            Ok(Some(res)) if !res.is_empty() => {
+                if self.record {
+                    self.record_selection(&res);
                 }
+                self.converted.push_str(&res);
             }
-            None => Exit,
+            _ => self.converted.push_str(&self.current),
+        }
+
+        if self.next_segment() {
+            Continue(())
+        } else {
+            commit_buf.push_str(&self.converted);
+            self.clear();
+            ExitHandled(())
         }
     }
 }
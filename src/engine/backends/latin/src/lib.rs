@@ -1,4 +1,4 @@
-use kime_engine_backend::{InputEngineBackend, Key, KeyMap};
+use kime_engine_backend::{InputEngineBackend, Key, KeyMap, ModifierState};
 use serde::{Deserialize, Serialize};
 
 #[derive(Serialize, Deserialize)]
@@ -30,20 +30,52 @@ impl Default for LatinConfig {
     }
 }
 
+#[derive(Clone, PartialEq)]
 pub struct LatinData {
     keymap: KeyMap<char>,
+    preferred_direct: bool,
 }
 
 impl LatinData {
     pub fn new(config: &LatinConfig) -> Self {
         Self {
             keymap: load_layout(config),
+            preferred_direct: config.preferred_direct,
         }
     }
 
     #[inline]
+    pub fn preferred_direct(&self) -> bool {
+        self.preferred_direct
+    }
+
+    /// Char of key, CapsLock inverts Shift only for letters
     pub fn lookup(&self, key: Key) -> Option<char> {
-        self.keymap.get(key)
+        if key.code.is_numlock_key() && !key.state.contains(ModifierState::NUMLOCK) {
+            // Keypad works as cursor keys
+            return None;
+        }
+
+        let ch = self.keymap.get(key)?;
+
+        if key.state.contains(ModifierState::CAPSLOCK) && ch.is_alphabetic() {
+            let inverted = Key::new(key.code, key.state ^ ModifierState::SHIFT);
+            Some(
+                self.keymap
+                    .get(inverted)
+                    .filter(|ch| ch.is_alphabetic())
+                    .unwrap_or(ch),
+            )
+        } else {
+            Some(ch)
+        }
+    }
+
+    /// Find keys which type `text`
+    pub fn keys_of(&self, text: &str) -> Option<Vec<Key>> {
+        text.chars()
+            .map(|ch| self.keymap.find_key(|c| c == ch))
+            .collect()
     }
 }
 
@@ -1,8 +1,8 @@
-use kime_engine_candidate::client::Client;
+use kime_engine_candidate::client::{CandidateWindow, Client};
 
 fn main() {
     let candidate_list = kime_engine_dict::lookup("가").unwrap();
-    let client = Client::new(candidate_list).unwrap();
+    let client = Client::new(&CandidateWindow::default(), candidate_list).unwrap();
 
     while !client.is_ready() {}
 
@@ -1,53 +1,178 @@
+use crate::protocol::{socket_path, write_hide, write_show, write_update};
 use nix::poll;
 use std::fmt;
-use std::io::{self, BufWriter, Write};
+use std::io::{self, BufWriter, Read};
+use std::net::Shutdown;
 use std::os::unix::io::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::path::{Path, PathBuf};
 use std::process::{Child, Stdio};
+use std::sync::{Mutex, PoisonError};
+use std::time::Duration;
 
 pub const CANDIDATE_PROCESS_NAME: &str = "kime-candidate-window";
 
+/// Candidate server which is spawned by this process, it's spawned again after it's exited
+static SERVER: Mutex<Option<Child>> = Mutex::new(None);
+
+/// Candidate window which is run by [`Client`]
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CandidateWindow {
+    /// Program which shows candidates of stdin, it's run with `--server` for candidate server
+    pub program: PathBuf,
+    /// Socket of candidate server
+    pub socket: PathBuf,
+}
+
+impl Default for CandidateWindow {
+    fn default() -> Self {
+        Self {
+            program: CANDIDATE_PROCESS_NAME.into(),
+            socket: socket_path(),
+        }
+    }
+}
+
+enum Connection {
+    /// Candidate server which keeps fonts loaded
+    Server(UnixStream),
+    /// Process which shows only this candidates, it's spawned again by update
+    Process(Child, PathBuf),
+}
+
 pub struct Client {
-    child: Child,
-    stdout_fd: RawFd,
+    connection: Connection,
 }
 
+/// Time to wait for candidate server which doesn't respond
+const SERVER_TIMEOUT: Duration = Duration::from_secs(2);
+
 impl Client {
-    pub fn new(candidate_list: &[(&str, &str)]) -> io::Result<Self> {
-        let mut child = std::process::Command::new(CANDIDATE_PROCESS_NAME)
+    /// Show candidates with candidate server
+    ///
+    /// When server is absent, it's spawned for next candidates and these are shown by new process
+    /// so that key isn't blocked until server is started
+    pub fn new(window: &CandidateWindow, candidate_list: &[(&str, &str)]) -> io::Result<Self> {
+        if let Ok(client) = Self::connect(&window.socket, candidate_list) {
+            return Ok(client);
+        }
+
+        if let Err(_err) = Self::spawn_server(&window.program) {
+            #[cfg(debug_assertions)]
+            eprintln!("Can't start candidate server {:#?}", _err);
+        }
+
+        Self::spawn(&window.program, candidate_list)
+    }
+
+    /// Show candidates with candidate server of `path`
+    pub fn connect(path: &Path, candidate_list: &[(&str, &str)]) -> io::Result<Self> {
+        let stream = UnixStream::connect(path)?;
+        stream.set_read_timeout(Some(SERVER_TIMEOUT))?;
+        stream.set_write_timeout(Some(SERVER_TIMEOUT))?;
+        write_show(&stream, candidate_list)?;
+
+        Ok(Self {
+            connection: Connection::Server(stream),
+        })
+    }
+
+    /// Show candidates with new process of `program`
+    pub fn spawn(program: &Path, candidate_list: &[(&str, &str)]) -> io::Result<Self> {
+        let mut child = std::process::Command::new(program)
             .stdin(Stdio::piped())
             .stdout(Stdio::piped())
             .stderr(Stdio::inherit())
             .spawn()?;
 
-        let mut stdin = BufWriter::new(child.stdin.take().unwrap());
+        write_show(BufWriter::new(child.stdin.take().unwrap()), candidate_list)?;
+
+        Ok(Self {
+            connection: Connection::Process(child, program.into()),
+        })
+    }
 
-        for (key, value) in candidate_list {
-            stdin.write_all(key.as_bytes())?;
-            stdin.write_all(b"\n")?;
-            stdin.write_all(value.as_bytes())?;
-            stdin.write_all(b"\n")?;
+    /// Replace candidates which are shown now
+    pub fn update(&mut self, candidate_list: &[(&str, &str)]) -> io::Result<()> {
+        match &mut self.connection {
+            Connection::Server(stream) => write_update(&*stream, candidate_list),
+            Connection::Process(child, program) => {
+                let program = std::mem::take(program);
+                child.kill().ok();
+                child.wait().ok();
+                *self = Self::spawn(&program, candidate_list)?;
+                Ok(())
+            }
         }
+    }
 
-        stdin.flush()?;
+    /// Close candidates without selection
+    pub fn hide(self) -> io::Result<()> {
+        match self.connection {
+            Connection::Server(stream) => {
+                let ret = write_hide(&stream);
+                stream.shutdown(Shutdown::Both).ok();
+                ret
+            }
+            Connection::Process(mut child, _) => {
+                child.kill()?;
+                child.wait().map(drop)
+            }
+        }
+    }
+
+    /// Spawn candidate server without waiting for it, server which is still starting isn't spawned again
+    fn spawn_server(program: &Path) -> io::Result<()> {
+        let mut server = SERVER.lock().unwrap_or_else(PoisonError::into_inner);
+
+        if let Some(child) = server.as_mut() {
+            if child.try_wait()?.is_none() {
+                return Ok(());
+            }
+        }
 
-        drop(stdin);
+        *server = Some(
+            std::process::Command::new(program)
+                .arg("--server")
+                .stdin(Stdio::null())
+                .stdout(Stdio::null())
+                .stderr(Stdio::inherit())
+                .spawn()?,
+        );
 
-        let stdout_fd = child.stdout.as_ref().unwrap().as_raw_fd();
+        Ok(())
+    }
 
-        Ok(Self { stdout_fd, child })
+    fn fd(&self) -> RawFd {
+        match &self.connection {
+            Connection::Server(stream) => stream.as_raw_fd(),
+            Connection::Process(child, _) => child.stdout.as_ref().unwrap().as_raw_fd(),
+        }
     }
 
     pub fn is_ready(&self) -> bool {
-        let fds = &mut [poll::PollFd::new(self.stdout_fd, poll::PollFlags::POLLIN)];
+        let fds = &mut [poll::PollFd::new(self.fd(), poll::PollFlags::POLLIN)];
         poll::poll(fds, 200) == Ok(1)
     }
 
-    pub fn close(mut self) -> io::Result<Option<String>> {
-        if self.is_ready() {
-            Ok(String::from_utf8(self.child.wait_with_output()?.stdout).ok())
-        } else {
-            self.child.kill()?;
-            Ok(None)
+    /// Return selected candidate, it's empty when candidate window is closed without selection
+    ///
+    /// Candidate window is closed when it's not ready
+    pub fn close(self) -> io::Result<Option<String>> {
+        if !self.is_ready() {
+            self.hide()?;
+            return Ok(None);
+        }
+
+        match self.connection {
+            Connection::Server(mut stream) => {
+                let mut buf = Vec::new();
+                stream.read_to_end(&mut buf)?;
+                Ok(String::from_utf8(buf).ok())
+            }
+            Connection::Process(child, _) => {
+                Ok(String::from_utf8(child.wait_with_output()?.stdout).ok())
+            }
         }
     }
 }
@@ -57,3 +182,82 @@ impl fmt::Debug for Client {
         write!(f, "CandidateClient")
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::Client;
+    use crate::protocol::{read_message, Message};
+    use std::io::{BufReader, Write};
+    use std::net::Shutdown;
+    use std::os::unix::net::UnixListener;
+
+    fn list(candidate_list: &[(&str, &str)]) -> Vec<(String, String)> {
+        candidate_list
+            .iter()
+            .map(|(key, value)| (key.to_string(), value.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn server() {
+        let dir = std::env::temp_dir().join(format!("kime-candidate-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("candidate.sock");
+        let listener = UnixListener::bind(&path).unwrap();
+
+        let server = std::thread::spawn(move || {
+            // Select last candidate after update
+            let mut stream = listener.accept().unwrap().0;
+            let mut reader = BufReader::new(stream.try_clone().unwrap());
+            let mut messages = vec![read_message(&mut reader).unwrap()];
+            messages.push(read_message(&mut reader).unwrap());
+            if let Some(Message::Update(candidate_list)) = messages.last().unwrap() {
+                let selected = &candidate_list.last().unwrap().0;
+                stream.write_all(selected.as_bytes()).unwrap();
+            }
+            stream.shutdown(Shutdown::Both).unwrap();
+
+            // Client hides candidates before selection
+            for _ in 0..2 {
+                let stream = listener.accept().unwrap().0;
+                let mut reader = BufReader::new(stream);
+                while let Some(message) = read_message(&mut reader).unwrap() {
+                    messages.push(Some(message));
+                }
+            }
+
+            messages
+        });
+
+        let mut client = Client::connect(&path, &[("可", "옳을 가"), ("家", "집 가")]).unwrap();
+        client
+            .update(&[("韓", "나라 한"), ("漢", "한수 한")])
+            .unwrap();
+        assert!((0..50).any(|_| client.is_ready()));
+        assert_eq!(client.close().unwrap().as_deref(), Some("漢"));
+
+        let client = Client::connect(&path, &[("韓", "나라 한")]).unwrap();
+        client.hide().unwrap();
+
+        // Client which isn't ready hides candidates when it's closed
+        let client = Client::connect(&path, &[("\n", "a\nb")]).unwrap();
+        assert_eq!(client.close().unwrap(), None);
+
+        assert_eq!(
+            server.join().unwrap(),
+            [
+                Some(Message::Show(list(&[("可", "옳을 가"), ("家", "집 가")]))),
+                Some(Message::Update(list(&[
+                    ("韓", "나라 한"),
+                    ("漢", "한수 한")
+                ]))),
+                Some(Message::Show(list(&[("韓", "나라 한")]))),
+                Some(Message::Hide),
+                Some(Message::Show(list(&[("\n", "a\nb")]))),
+                Some(Message::Hide),
+            ]
+        );
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -0,0 +1,173 @@
+use std::io::{self, BufRead, Write};
+use std::path::PathBuf;
+
+/// Socket of candidate server in run directory
+pub const CANDIDATE_SOCKET_NAME: &str = "kime-candidate.sock";
+
+pub fn socket_path() -> PathBuf {
+    kime_run_dir::get_run_dir().join(CANDIDATE_SOCKET_NAME)
+}
+
+/// Message from client to candidate window
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum Message {
+    /// Show candidates, selected key is written back when they're closed
+    Show(Vec<(String, String)>),
+    /// Replace candidates which are shown now
+    Update(Vec<(String, String)>),
+    /// Close candidates without selection
+    Hide,
+}
+
+const SHOW: &str = "show";
+const UPDATE: &str = "update";
+const HIDE: &str = "hide";
+
+/// Field is written in one line, `\` and newline are escaped
+fn write_field(writer: &mut impl Write, field: &str) -> io::Result<()> {
+    for ch in field.chars() {
+        match ch {
+            '\\' => writer.write_all(b"\\\\")?,
+            '\n' => writer.write_all(b"\\n")?,
+            _ => write!(writer, "{}", ch)?,
+        }
+    }
+    writer.write_all(b"\n")
+}
+
+fn write_list(
+    mut writer: impl Write,
+    command: &str,
+    candidate_list: &[(&str, &str)],
+) -> io::Result<()> {
+    writeln!(writer, "{}\n{}", command, candidate_list.len())?;
+
+    for (key, value) in candidate_list {
+        write_field(&mut writer, key)?;
+        write_field(&mut writer, value)?;
+    }
+
+    writer.flush()
+}
+
+/// Write `show` line, count of candidates and their key and value lines
+///
+/// Connection of candidate server is kept open after this to wait for selection
+pub fn write_show(writer: impl Write, candidate_list: &[(&str, &str)]) -> io::Result<()> {
+    write_list(writer, SHOW, candidate_list)
+}
+
+/// Write `update` line with candidates like [`write_show`]
+pub fn write_update(writer: impl Write, candidate_list: &[(&str, &str)]) -> io::Result<()> {
+    write_list(writer, UPDATE, candidate_list)
+}
+
+/// Write `hide` line
+pub fn write_hide(mut writer: impl Write) -> io::Result<()> {
+    writeln!(writer, "{}", HIDE)?;
+    writer.flush()
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+/// Read one line without newline, `None` at EOF
+fn read_line(reader: &mut impl BufRead) -> io::Result<Option<String>> {
+    let mut line = String::new();
+
+    if reader.read_line(&mut line)? == 0 {
+        return Ok(None);
+    }
+
+    if line.ends_with('\n') {
+        line.pop();
+    }
+
+    Ok(Some(line))
+}
+
+fn read_field(reader: &mut impl BufRead) -> io::Result<String> {
+    let line = read_line(reader)?.ok_or_else(|| invalid_data("Candidate is truncated"))?;
+    let mut field = String::with_capacity(line.len());
+    let mut chars = line.chars();
+
+    while let Some(ch) = chars.next() {
+        match ch {
+            '\\' => match chars.next() {
+                Some('\\') => field.push('\\'),
+                Some('n') => field.push('\n'),
+                _ => return Err(invalid_data("Invalid escape of candidate")),
+            },
+            _ => field.push(ch),
+        }
+    }
+
+    Ok(field)
+}
+
+fn read_list(reader: &mut impl BufRead) -> io::Result<Vec<(String, String)>> {
+    let count: usize = read_line(reader)?
+        .and_then(|count| count.parse().ok())
+        .ok_or_else(|| invalid_data("Invalid count of candidates"))?;
+    let mut candidate_list = Vec::with_capacity(count.min(1024));
+
+    for _ in 0..count {
+        let key = read_field(reader)?;
+        let value = read_field(reader)?;
+        candidate_list.push((key, value));
+    }
+
+    Ok(candidate_list)
+}
+
+/// Read next message, `None` when client is closed
+pub fn read_message(mut reader: impl BufRead) -> io::Result<Option<Message>> {
+    let command = match read_line(&mut reader)? {
+        Some(command) => command,
+        None => return Ok(None),
+    };
+
+    let message = match command.as_str() {
+        SHOW => Message::Show(read_list(&mut reader)?),
+        UPDATE => Message::Update(read_list(&mut reader)?),
+        HIDE => Message::Hide,
+        _ => return Err(invalid_data("Unknown message of candidate window")),
+    };
+
+    Ok(Some(message))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{read_message, write_hide, write_show, write_update, Message};
+
+    #[test]
+    fn messages() {
+        let mut buf = Vec::new();
+        write_show(&mut buf, &[("可", "옳을 가"), ("家", "집 가")]).unwrap();
+        assert_eq!(buf, "show\n2\n可\n옳을 가\n家\n집 가\n".as_bytes());
+
+        // Newline and empty key don't break framing
+        write_update(&mut buf, &[("", "a\nb\\n")]).unwrap();
+        write_hide(&mut buf).unwrap();
+
+        let mut reader = &buf[..];
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            Some(Message::Show(vec![
+                ("可".into(), "옳을 가".into()),
+                ("家".into(), "집 가".into())
+            ]))
+        );
+        assert_eq!(
+            read_message(&mut reader).unwrap(),
+            Some(Message::Update(vec![("".into(), "a\nb\\n".into())]))
+        );
+        assert_eq!(read_message(&mut reader).unwrap(), Some(Message::Hide));
+        assert_eq!(read_message(&mut reader).unwrap(), None);
+
+        assert!(read_message(&b"show\n1\nkey\n"[..]).is_err());
+        assert!(read_message(&b"close\n"[..]).is_err());
+    }
+}
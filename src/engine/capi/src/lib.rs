@@ -2,11 +2,14 @@
 
 pub use kime_engine_core::{
     load_engine_config_from_config_dir, load_other_configs_from_config_dir, Config, DaemonConfig,
-    DaemonModule, IconColor, IndicatorConfig, InputCategory, InputEngine, InputResult, LogConfig,
-    ModifierState,
+    DaemonModule, EngineSnapshot, IconColor, IndicatorConfig, InputCategory, InputEngine,
+    InputResult, LogConfig, ModifierState, PreeditAttribute, PreeditSegment,
 };
 
-pub const KIME_API_VERSION: usize = 6;
+#[cfg(unix)]
+pub use kime_engine_core::ConfigWatcher;
+
+pub const KIME_API_VERSION: usize = 7;
 
 #[repr(C)]
 pub struct RustStr {
@@ -21,6 +24,17 @@ impl RustStr {
             len: s.len(),
         }
     }
+
+    /// # Safety
+    ///
+    /// ptr must point valid utf8 string which has len bytes
+    pub unsafe fn as_str<'a>(&self) -> &'a str {
+        if self.len == 0 {
+            ""
+        } else {
+            std::str::from_utf8_unchecked(std::slice::from_raw_parts(self.ptr, self.len))
+        }
+    }
 }
 
 #[repr(C)]
@@ -45,6 +59,13 @@ pub struct FontData {
     size: f32,
 }
 
+/// Key which should be sent to client, hardware code is same as `kime_engine_press_key`
+#[repr(C)]
+pub struct BypassKey {
+    hardware_code: u16,
+    state: ModifierState,
+}
+
 /// Return API version
 #[no_mangle]
 pub extern "C" fn kime_api_version() -> usize {
@@ -57,13 +78,64 @@ pub extern "C" fn kime_engine_new(config: &Config) -> *mut InputEngine {
     Box::into_raw(Box::new(InputEngine::new(config)))
 }
 
-/// Set hangul enable state
+/// Set input category by its name e.g. `Hangul`
+///
+/// ## Return
+///
+/// false when category is not registered
+///
+/// # Safety
+///
+/// category must be valid utf8 string
 #[no_mangle]
-pub extern "C" fn kime_engine_set_input_category(
+pub unsafe extern "C" fn kime_engine_set_input_category(
     engine: &mut InputEngine,
-    category: InputCategory,
-) {
-    engine.set_input_category(category);
+    category: RustStr,
+) -> bool {
+    match InputCategory::find(category.as_str()) {
+        Some(category) => engine.set_input_category(category),
+        None => false,
+    }
+}
+
+/// Set application which has focus e.g. WM_CLASS or program name
+///
+/// input category can be changed by `app_category` config
+///
+/// ## Return
+///
+/// input result
+///
+/// # Safety
+///
+/// app_id must be valid utf8 string
+#[no_mangle]
+pub unsafe extern "C" fn kime_engine_set_app_id(
+    engine: &mut InputEngine,
+    config: &Config,
+    app_id: RustStr,
+) -> InputResult {
+    engine.set_app_id(app_id.as_str(), config)
+}
+
+/// Get name of current input category
+///
+/// ## Return
+///
+/// valid utf8 string
+#[no_mangle]
+pub extern "C" fn kime_engine_input_category(engine: &InputEngine) -> RustStr {
+    RustStr::new(engine.category().name())
+}
+
+/// Get name of active layout of current input category
+///
+/// ## Return
+///
+/// valid utf8 string, empty when current input category doesn't have layouts
+#[no_mangle]
+pub extern "C" fn kime_engine_layout(engine: &InputEngine) -> RustStr {
+    RustStr::new(engine.layout().unwrap_or_default())
 }
 
 /// Delete engine
@@ -114,12 +186,117 @@ pub extern "C" fn kime_engine_preedit_str(engine: &mut InputEngine) -> RustStr {
     RustStr::new(engine.preedit_str())
 }
 
-/// Clear commit string
+/// Get count of preedit segments
+///
+/// only valid after `kime_engine_preedit_str` is called
+#[no_mangle]
+pub extern "C" fn kime_engine_preedit_segment_count(engine: &InputEngine) -> usize {
+    engine.last_preedit().segments().len()
+}
+
+/// Get preedit segment, start and end are byte offsets of preedit string
+///
+/// ## Return
+///
+/// empty segment when idx is out of range
+#[no_mangle]
+pub extern "C" fn kime_engine_preedit_segment(engine: &InputEngine, idx: usize) -> PreeditSegment {
+    engine
+        .last_preedit()
+        .segments()
+        .get(idx)
+        .copied()
+        .unwrap_or(PreeditSegment {
+            start: 0,
+            end: 0,
+            attribute: PreeditAttribute::Composing,
+        })
+}
+
+/// Get cursor of preedit string as byte offset
+///
+/// only valid after `kime_engine_preedit_str` is called
+#[no_mangle]
+pub extern "C" fn kime_engine_preedit_cursor(engine: &InputEngine) -> usize {
+    engine.last_preedit().cursor()
+}
+
+/// Clear commit string, pending surrounding deletion and bypass keys
 #[no_mangle]
 pub extern "C" fn kime_engine_clear_commit(engine: &mut InputEngine) {
     engine.clear_commit();
 }
 
+/// Set text around cursor cursor is byte offset of text
+///
+/// it become invalid after commit string is cleared,
+/// frontend which sets it must delete text before cursor when `DELETE_SURROUNDING` is set
+///
+/// # Safety
+///
+/// text must be valid utf8 string
+#[no_mangle]
+pub unsafe extern "C" fn kime_engine_set_surrounding_text(
+    engine: &mut InputEngine,
+    text: RustStr,
+    cursor: usize,
+) {
+    engine.set_surrounding_text(text.as_str(), cursor);
+}
+
+/// Clear surrounding text
+#[no_mangle]
+pub extern "C" fn kime_engine_clear_surrounding_text(engine: &mut InputEngine) {
+    engine.clear_surrounding_text();
+}
+
+/// Set selected text, empty text means there is no selection
+///
+/// hanja mode converts selected text instead of preedit
+///
+/// # Safety
+///
+/// text must be valid utf8 string
+#[no_mangle]
+pub unsafe extern "C" fn kime_engine_set_selection_text(engine: &mut InputEngine, text: RustStr) {
+    engine.set_selection_text(text.as_str());
+}
+
+/// Get count of characters before cursor which should be deleted
+///
+/// only valid when `DELETE_SURROUNDING` is set
+#[no_mangle]
+pub extern "C" fn kime_engine_delete_surrounding_len(engine: &InputEngine) -> usize {
+    engine.delete_surrounding_len()
+}
+
+/// Get count of keys which should be pressed and released in client before current key
+///
+/// only valid when `BYPASS_KEYS` is set
+#[no_mangle]
+pub extern "C" fn kime_engine_bypass_key_count(engine: &InputEngine) -> usize {
+    engine.bypass_keys().len()
+}
+
+/// Get key which should be pressed and released in client
+///
+/// ## Return
+///
+/// key which hardware code is 0 when idx is out of range
+#[no_mangle]
+pub extern "C" fn kime_engine_bypass_key(engine: &InputEngine, idx: usize) -> BypassKey {
+    match engine.bypass_keys().get(idx) {
+        Some(key) => BypassKey {
+            hardware_code: key.code.to_hardware_code().unwrap_or(0),
+            state: key.state,
+        },
+        None => BypassKey {
+            hardware_code: 0,
+            state: ModifierState::empty(),
+        },
+    }
+}
+
 /// Clear preedit state this function may append to commit string
 #[no_mangle]
 pub extern "C" fn kime_engine_clear_preedit(engine: &mut InputEngine) {
@@ -140,6 +317,8 @@ pub extern "C" fn kime_engine_reset(engine: &mut InputEngine) {
 
 /// Press key when modifier state
 ///
+/// `time` is millisecond timestamp of key event, `0` when it's unknown
+///
 /// ## Return
 ///
 /// input result
@@ -149,8 +328,116 @@ pub extern "C" fn kime_engine_press_key(
     config: &Config,
     hardware_code: u16,
     state: ModifierState,
+    time: u32,
 ) -> InputResult {
-    engine.press_key_code(hardware_code, state, config)
+    engine.press_key_code_at(hardware_code, state, config, time)
+}
+
+/// Release key when modifier state
+///
+/// `time` is millisecond timestamp of key event, `0` when it's unknown
+///
+/// ## Return
+///
+/// input result, empty when nothing has changed
+/// it contains `CONSUMED` only when press of the key was consumed as a part of hotkey trigger,
+/// otherwise release event should be forwarded to client
+#[no_mangle]
+pub extern "C" fn kime_engine_release_key(
+    engine: &mut InputEngine,
+    config: &Config,
+    hardware_code: u16,
+    state: ModifierState,
+    time: u32,
+) -> InputResult {
+    engine.release_key_code_at(hardware_code, state, config, time)
+}
+
+/// Press key which is identified by XKB keysym when modifier state
+///
+/// `time` is millisecond timestamp of key event, `0` when it's unknown
+///
+/// ## Return
+///
+/// input result
+#[no_mangle]
+pub extern "C" fn kime_engine_press_keysym(
+    engine: &mut InputEngine,
+    config: &Config,
+    keysym: u32,
+    state: ModifierState,
+    time: u32,
+) -> InputResult {
+    engine.press_keysym_at(keysym, state, config, time)
+}
+
+/// Release key which is identified by XKB keysym when modifier state
+///
+/// `time` is millisecond timestamp of key event, `0` when it's unknown
+///
+/// ## Return
+///
+/// input result, empty when nothing has changed
+/// it contains `CONSUMED` only when press of the key was consumed as a part of hotkey trigger,
+/// otherwise release event should be forwarded to client
+#[no_mangle]
+pub extern "C" fn kime_engine_release_keysym(
+    engine: &mut InputEngine,
+    config: &Config,
+    keysym: u32,
+    state: ModifierState,
+    time: u32,
+) -> InputResult {
+    engine.release_keysym_at(keysym, state, config, time)
+}
+
+/// Apply reloaded config to engine
+///
+/// preedit will be committed when hangul layout or addons has changed
+///
+/// ## Return
+///
+/// input result
+#[no_mangle]
+pub extern "C" fn kime_engine_update_config(
+    engine: &mut InputEngine,
+    old_config: &Config,
+    new_config: &Config,
+) -> InputResult {
+    engine.update_config(old_config, new_config)
+}
+
+/// Save category, mode and preedit of engine
+///
+/// ## Return
+///
+/// snapshot which must be deleted by `kime_snapshot_delete`
+#[no_mangle]
+pub extern "C" fn kime_engine_snapshot(engine: &InputEngine) -> *mut EngineSnapshot {
+    Box::into_raw(Box::new(engine.snapshot()))
+}
+
+/// Restore engine state from snapshot current preedit will be dropped
+///
+/// ## Return
+///
+/// input result
+#[no_mangle]
+pub extern "C" fn kime_engine_restore(
+    engine: &mut InputEngine,
+    snapshot: &EngineSnapshot,
+) -> InputResult {
+    engine.restore(snapshot)
+}
+
+/// Delete snapshot
+///
+/// # Safety
+///
+/// snapshot must be created by `kime_engine_snapshot` and never call delete more than once
+#[no_mangle]
+pub unsafe extern "C" fn kime_snapshot_delete(snapshot: *mut EngineSnapshot) {
+    drop(Box::from_raw(snapshot));
 }
 
 /// Load config from local file
@@ -173,6 +460,12 @@ pub unsafe extern "C" fn kime_config_delete(config: *mut Config) {
     drop(Box::from_raw(config));
 }
 
+/// Whether frontend should use `kime_engine_press_keysym` instead of `kime_engine_press_key`
+#[no_mangle]
+pub extern "C" fn kime_config_keysym_layout(config: &Config) -> bool {
+    config.keysym_layout
+}
+
 /// Get candidate_font config
 /// font_data only valid while config is live
 #[no_mangle]
@@ -199,6 +492,61 @@ pub extern "C" fn kime_config_xim_preedit_font(config: &Config) -> FontData {
     }
 }
 
+/// Get count of registered input categories
+#[no_mangle]
+pub extern "C" fn kime_config_category_count(config: &Config) -> usize {
+    config.categories().count()
+}
+
+/// Get name of registered input category
+///
+/// ## Return
+///
+/// valid utf8 string, empty string when idx is out of range
+#[no_mangle]
+pub extern "C" fn kime_config_category(config: &Config, idx: usize) -> RustStr {
+    RustStr::new(config.categories().nth(idx).map_or("", |c| c.name()))
+}
+
+/// Create config watcher
+///
+/// ## Return
+///
+/// NULL if there is no config directory to watch
+#[cfg(unix)]
+#[no_mangle]
+pub extern "C" fn kime_config_watcher_new() -> *mut ConfigWatcher {
+    match ConfigWatcher::new() {
+        Ok(watcher) => Box::into_raw(Box::new(watcher)),
+        Err(_) => std::ptr::null_mut(),
+    }
+}
+
+/// Get file descriptor of config watcher it become readable when config files are modified
+#[cfg(unix)]
+#[no_mangle]
+pub extern "C" fn kime_config_watcher_fd(watcher: &ConfigWatcher) -> i32 {
+    watcher.fd()
+}
+
+/// Drain pending events of config watcher
+///
+/// ## Return
+///
+/// true when config should be reloaded
+#[cfg(unix)]
+#[no_mangle]
+pub extern "C" fn kime_config_watcher_changed(watcher: &mut ConfigWatcher) -> bool {
+    watcher.changed()
+}
+
+/// Delete config watcher
+#[cfg(unix)]
+#[no_mangle]
+pub unsafe extern "C" fn kime_config_watcher_delete(watcher: *mut ConfigWatcher) {
+    drop(Box::from_raw(watcher));
+}
+
 /// Load daemon config
 #[cfg(unix)]
 #[no_mangle]
@@ -12,10 +12,11 @@ extern "C" {}
 pub use kime_engine_config::{DaemonModule, EnumSet};
 
 pub use ffi::{
-    IconColor, InputCategory, InputResult, InputResult_CONSUMED, InputResult_HAS_COMMIT,
-    InputResult_HAS_PREEDIT, InputResult_LANGUAGE_CHANGED, InputResult_NOT_READY, ModifierState,
-    ModifierState_ALT, ModifierState_CONTROL, ModifierState_SHIFT, ModifierState_SUPER,
-    KIME_API_VERSION,
+    BypassKey, IconColor, InputResult, InputResult_BYPASS_KEYS, InputResult_CONSUMED,
+    InputResult_DELETE_SURROUNDING, InputResult_HAS_COMMIT, InputResult_HAS_PREEDIT,
+    InputResult_LANGUAGE_CHANGED, InputResult_NOT_READY, ModifierState, ModifierState_ALT,
+    ModifierState_CAPSLOCK, ModifierState_CONTROL, ModifierState_LEVEL3, ModifierState_NUMLOCK,
+    ModifierState_SHIFT, ModifierState_SUPER, PreeditAttribute, PreeditSegment, KIME_API_VERSION,
 };
 
 pub fn check_api_version() -> bool {
@@ -45,8 +46,48 @@ impl InputEngine {
         unsafe { ffi::kime_engine_check_ready(self.engine) }
     }
 
-    pub fn set_input_category(&mut self, category: InputCategory) {
-        unsafe { ffi::kime_engine_set_input_category(self.engine, category) };
+    pub fn set_input_category(&mut self, category: &str) -> bool {
+        unsafe {
+            ffi::kime_engine_set_input_category(
+                self.engine,
+                ffi::RustStr {
+                    ptr: category.as_ptr(),
+                    len: category.len(),
+                },
+            )
+        }
+    }
+
+    pub fn set_app_id(&mut self, config: &Config, app_id: &str) -> InputResult {
+        unsafe {
+            ffi::kime_engine_set_app_id(
+                self.engine,
+                config.config,
+                ffi::RustStr {
+                    ptr: app_id.as_ptr(),
+                    len: app_id.len(),
+                },
+            )
+        }
+    }
+
+    pub fn category(&self) -> &str {
+        unsafe {
+            let s = ffi::kime_engine_input_category(self.engine);
+            core::str::from_utf8_unchecked(core::slice::from_raw_parts(s.ptr, s.len))
+        }
+    }
+
+    /// Empty when current category doesn't have layouts
+    pub fn layout(&self) -> &str {
+        unsafe {
+            let s = ffi::kime_engine_layout(self.engine);
+            core::str::from_utf8_unchecked(core::slice::from_raw_parts(s.ptr, s.len))
+        }
+    }
+
+    pub fn update_config(&mut self, old_config: &Config, new_config: &Config) -> InputResult {
+        unsafe { ffi::kime_engine_update_config(self.engine, old_config.config, new_config.config) }
     }
 
     pub fn press_key(
@@ -54,8 +95,43 @@ impl InputEngine {
         config: &Config,
         hardware_code: u16,
         state: ModifierState,
+        time: u32,
+    ) -> InputResult {
+        unsafe {
+            ffi::kime_engine_press_key(self.engine, config.config, hardware_code, state, time)
+        }
+    }
+
+    pub fn release_key(
+        &mut self,
+        config: &Config,
+        hardware_code: u16,
+        state: ModifierState,
+        time: u32,
     ) -> InputResult {
-        unsafe { ffi::kime_engine_press_key(self.engine, config.config, hardware_code, state) }
+        unsafe {
+            ffi::kime_engine_release_key(self.engine, config.config, hardware_code, state, time)
+        }
+    }
+
+    pub fn press_keysym(
+        &mut self,
+        config: &Config,
+        keysym: u32,
+        state: ModifierState,
+        time: u32,
+    ) -> InputResult {
+        unsafe { ffi::kime_engine_press_keysym(self.engine, config.config, keysym, state, time) }
+    }
+
+    pub fn release_keysym(
+        &mut self,
+        config: &Config,
+        keysym: u32,
+        state: ModifierState,
+        time: u32,
+    ) -> InputResult {
+        unsafe { ffi::kime_engine_release_keysym(self.engine, config.config, keysym, state, time) }
     }
 
     pub fn preedit_str(&mut self) -> &str {
@@ -65,6 +141,17 @@ impl InputEngine {
         }
     }
 
+    /// Segments of last preedit string
+    pub fn preedit_segments(&self) -> impl Iterator<Item = PreeditSegment> + '_ {
+        let count = unsafe { ffi::kime_engine_preedit_segment_count(self.engine) };
+        (0..count).map(move |idx| unsafe { ffi::kime_engine_preedit_segment(self.engine, idx) })
+    }
+
+    /// Cursor of last preedit string
+    pub fn preedit_cursor(&self) -> usize {
+        unsafe { ffi::kime_engine_preedit_cursor(self.engine) }
+    }
+
     pub fn commit_str(&self) -> &str {
         unsafe {
             let s = ffi::kime_engine_commit_str(self.engine);
@@ -78,6 +165,47 @@ impl InputEngine {
         }
     }
 
+    pub fn set_surrounding_text(&mut self, text: &str, cursor: usize) {
+        unsafe {
+            ffi::kime_engine_set_surrounding_text(
+                self.engine,
+                ffi::RustStr {
+                    ptr: text.as_ptr(),
+                    len: text.len(),
+                },
+                cursor,
+            );
+        }
+    }
+
+    pub fn set_selection_text(&mut self, text: &str) {
+        unsafe {
+            ffi::kime_engine_set_selection_text(
+                self.engine,
+                ffi::RustStr {
+                    ptr: text.as_ptr(),
+                    len: text.len(),
+                },
+            );
+        }
+    }
+
+    pub fn clear_surrounding_text(&mut self) {
+        unsafe {
+            ffi::kime_engine_clear_surrounding_text(self.engine);
+        }
+    }
+
+    pub fn delete_surrounding_len(&self) -> usize {
+        unsafe { ffi::kime_engine_delete_surrounding_len(self.engine) }
+    }
+
+    /// Keys which should be pressed and released in client before current key
+    pub fn bypass_keys(&self) -> impl Iterator<Item = BypassKey> + '_ {
+        let count = unsafe { ffi::kime_engine_bypass_key_count(self.engine) };
+        (0..count).map(move |idx| unsafe { ffi::kime_engine_bypass_key(self.engine, idx) })
+    }
+
     pub fn clear_preedit(&mut self) {
         unsafe {
             ffi::kime_engine_clear_preedit(self.engine);
@@ -95,6 +223,18 @@ impl InputEngine {
             ffi::kime_engine_reset(self.engine);
         }
     }
+
+    /// Save category, mode and preedit
+    pub fn snapshot(&self) -> EngineSnapshot {
+        EngineSnapshot {
+            snapshot: unsafe { ffi::kime_engine_snapshot(self.engine) },
+        }
+    }
+
+    /// Restore state from snapshot current preedit will be dropped
+    pub fn restore(&mut self, snapshot: &EngineSnapshot) -> InputResult {
+        unsafe { ffi::kime_engine_restore(self.engine, snapshot.snapshot) }
+    }
 }
 
 impl Drop for InputEngine {
@@ -105,6 +245,18 @@ impl Drop for InputEngine {
     }
 }
 
+pub struct EngineSnapshot {
+    snapshot: *mut ffi::EngineSnapshot,
+}
+
+impl Drop for EngineSnapshot {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::kime_snapshot_delete(self.snapshot);
+        }
+    }
+}
+
 pub struct Config {
     config: *mut ffi::Config,
 }
@@ -125,6 +277,11 @@ impl Config {
         }
     }
 
+    /// Key should be passed by keysym instead of hardware code
+    pub fn keysym_layout(&self) -> bool {
+        unsafe { ffi::kime_config_keysym_layout(self.config) }
+    }
+
     pub fn candidate_font(&self) -> (&[u8], u32) {
         unsafe {
             let font = ffi::kime_config_candidate_font(self.config);
@@ -136,6 +293,18 @@ impl Config {
         }
     }
 
+    /// Names of registered input categories
+    pub fn categories(&self) -> Vec<&str> {
+        unsafe {
+            (0..ffi::kime_config_category_count(self.config))
+                .map(|idx| {
+                    let s = ffi::kime_config_category(self.config, idx);
+                    core::str::from_utf8_unchecked(core::slice::from_raw_parts(s.ptr, s.len))
+                })
+                .collect()
+        }
+    }
+
     pub fn xim_font(&self) -> (&[u8], u32, f32) {
         unsafe {
             let font = ffi::kime_config_xim_preedit_font(self.config);
@@ -157,6 +326,39 @@ impl Drop for Config {
     }
 }
 
+pub struct ConfigWatcher {
+    watcher: *mut ffi::ConfigWatcher,
+}
+
+impl ConfigWatcher {
+    #[cfg(unix)]
+    pub fn new() -> Option<Self> {
+        let watcher = unsafe { ffi::kime_config_watcher_new() };
+
+        if watcher.is_null() {
+            None
+        } else {
+            Some(Self { watcher })
+        }
+    }
+
+    pub fn fd(&self) -> i32 {
+        unsafe { ffi::kime_config_watcher_fd(self.watcher) }
+    }
+
+    pub fn changed(&mut self) -> bool {
+        unsafe { ffi::kime_config_watcher_changed(self.watcher) }
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        unsafe {
+            ffi::kime_config_watcher_delete(self.watcher);
+        }
+    }
+}
+
 pub struct DaemonConfig {
     config: *mut ffi::DaemonConfig,
 }
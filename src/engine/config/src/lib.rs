@@ -3,23 +3,151 @@ use enumset::{enum_set, EnumSetType};
 use maplit::btreemap;
 #[cfg(feature = "serde")]
 use serde::{Deserialize, Serialize};
-use std::collections::BTreeMap;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    fmt,
+    str::FromStr,
+    sync::Mutex,
+};
 
 pub use kime_engine_backend::{Key, KeyCode, ModifierState};
-pub use kime_engine_backend_hangul::{HangulConfig, HangulData};
+pub use kime_engine_backend_hangul::{CommitNormalization, HangulConfig, HangulData};
 pub use kime_engine_backend_latin::{LatinConfig, LatinData};
 
 pub use enum_map::{enum_map, EnumMap};
 pub use enumset::EnumSet;
 pub use log::LevelFilter;
 
-#[derive(Debug, EnumSetType, Enum, PartialOrd, Ord)]
-#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
-#[cfg_attr(feature = "serde", enumset(serialize_as_list))]
-#[repr(u32)]
-pub enum InputCategory {
-    Latin,
-    Hangul,
+static NAMES: Mutex<BTreeSet<&'static str>> = Mutex::new(BTreeSet::new());
+
+/// Leak `name` once so that same names share `&'static str`
+fn intern(name: &str) -> &'static str {
+    let mut names = NAMES.lock().unwrap();
+
+    match names.get(name) {
+        Some(name) => name,
+        None => {
+            let name: &'static str = Box::leak(name.into());
+            names.insert(name);
+            name
+        }
+    }
+}
+
+/// Identifier of input category
+///
+/// Categories are named in config so new backend doesn't need new variant,
+/// names are interned so it can be copied and compared cheaply
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct InputCategory(&'static str);
+
+impl InputCategory {
+    pub const LATIN: Self = Self("Latin");
+    pub const HANGUL: Self = Self("Hangul");
+
+    pub fn new(name: &str) -> Self {
+        match name {
+            "Latin" => Self::LATIN,
+            "Hangul" => Self::HANGUL,
+            name => Self(intern(name)),
+        }
+    }
+
+    /// Category of `name` which is already known e.g. named in config
+    ///
+    /// Unlike [`InputCategory::new`] it doesn't intern `name`,
+    /// so it's used for names from outside of process
+    pub fn find(name: &str) -> Option<Self> {
+        match name {
+            "Latin" => Some(Self::LATIN),
+            "Hangul" => Some(Self::HANGUL),
+            name => NAMES.lock().unwrap().get(name).map(|name| Self(name)),
+        }
+    }
+
+    pub const fn name(self) -> &'static str {
+        self.0
+    }
+}
+
+impl fmt::Debug for InputCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl fmt::Display for InputCategory {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for InputCategory {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for InputCategory {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+        Ok(Self::new(&name))
+    }
+}
+
+/// Name of layout which is interned like [`InputCategory`]
+#[derive(Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct LayoutName(&'static str);
+
+impl LayoutName {
+    pub fn new(name: &str) -> Self {
+        Self(intern(name))
+    }
+
+    pub const fn name(self) -> &'static str {
+        self.0
+    }
+}
+
+impl fmt::Debug for LayoutName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+impl fmt::Display for LayoutName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for LayoutName {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(self.0)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for LayoutName {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let name = String::deserialize(deserializer)?;
+        Ok(Self::new(&name))
+    }
 }
 
 #[derive(Debug, EnumSetType, Enum, PartialOrd, Ord)]
@@ -39,12 +167,18 @@ pub enum HotkeyBehavior {
     Toggle(InputCategory, InputCategory),
     Mode(InputMode),
     Commit,
+    /// Retype last word with keys of other category between `Latin` and `Hangul`
+    Convert,
+    /// Select next layout of current category
+    CycleLayout,
+    /// Select layout of current category by name
+    Layout(LayoutName),
     Ignore,
 }
 
 impl HotkeyBehavior {
     pub const fn toggle_hangul_latin() -> Self {
-        Self::Toggle(InputCategory::Hangul, InputCategory::Latin)
+        Self::Toggle(InputCategory::HANGUL, InputCategory::LATIN)
     }
 }
 
@@ -76,6 +210,206 @@ impl Hotkey {
     }
 }
 
+/// Keys which fire hotkey
+///
+/// Written as `C-Space H` for sequence, `Double-Shift` for double tap, `Hold-X` for long press
+/// and `Tap-Shift` for tap
+#[derive(Clone, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum HotkeyTrigger {
+    Key(Key),
+    /// Keys which are pressed in order
+    Sequence(Vec<Key>),
+    /// Same key is pressed twice
+    Double(Key),
+    /// Key is pressed for a while
+    Hold(Key),
+    /// Key is released without pressing other keys, mostly used for modifier keys
+    Tap(Key),
+}
+
+impl From<Key> for HotkeyTrigger {
+    fn from(key: Key) -> Self {
+        Self::Key(key)
+    }
+}
+
+impl fmt::Display for HotkeyTrigger {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Key(key) => write!(f, "{}", key),
+            Self::Sequence(keys) => {
+                for (idx, key) in keys.iter().enumerate() {
+                    if idx != 0 {
+                        f.write_str(" ")?;
+                    }
+                    write!(f, "{}", key)?;
+                }
+                Ok(())
+            }
+            Self::Double(key) => write!(f, "Double-{}", key),
+            Self::Hold(key) => write!(f, "Hold-{}", key),
+            Self::Tap(key) => write!(f, "Tap-{}", key),
+        }
+    }
+}
+
+impl FromStr for HotkeyTrigger {
+    type Err = <Key as FromStr>::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+
+        if s.contains(char::is_whitespace) {
+            s.split_whitespace()
+                .map(str::parse)
+                .collect::<Result<_, _>>()
+                .map(Self::Sequence)
+        } else if let Some(key) = s.strip_prefix("Double-") {
+            key.parse().map(Self::Double)
+        } else if let Some(key) = s.strip_prefix("Hold-") {
+            key.parse().map(Self::Hold)
+        } else if let Some(key) = s.strip_prefix("Tap-") {
+            key.parse().map(Self::Tap)
+        } else {
+            s.parse().map(Self::Key)
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for HotkeyTrigger {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for HotkeyTrigger {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        s.parse().map_err(|_e| {
+            <D::Error as serde::de::Error>::invalid_value(
+                serde::de::Unexpected::Str(&s),
+                &"HotkeyTrigger",
+            )
+        })
+    }
+}
+
+/// What remapped key acts as
+///
+/// Written as key e.g. `Hangul` or modifier e.g. `Mod-Control`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum KeyRemap {
+    Key(Key),
+    /// Other keys have this modifier while remapped key is pressed
+    Modifier(ModifierState),
+}
+
+const REMAP_MODIFIERS: &[(&str, ModifierState)] = &[
+    ("Shift", ModifierState::SHIFT),
+    ("Control", ModifierState::CONTROL),
+    ("Alt", ModifierState::ALT),
+    ("Super", ModifierState::SUPER),
+    ("AltGr", ModifierState::LEVEL3),
+];
+
+impl fmt::Display for KeyRemap {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::Key(key) => write!(f, "{}", key),
+            Self::Modifier(state) => {
+                let name = REMAP_MODIFIERS
+                    .iter()
+                    .find_map(|(name, s)| (s == state).then_some(*name))
+                    .unwrap_or("None");
+                write!(f, "Mod-{}", name)
+            }
+        }
+    }
+}
+
+impl FromStr for KeyRemap {
+    type Err = <Key as FromStr>::Err;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.strip_prefix("Mod-") {
+            Some(modifier) => REMAP_MODIFIERS
+                .iter()
+                .find_map(|(name, state)| (*name == modifier).then_some(Self::Modifier(*state)))
+                .ok_or(strum::ParseError::VariantNotFound),
+            None => s.parse().map(Self::Key),
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl Serialize for KeyRemap {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+#[cfg(feature = "serde")]
+impl<'de> Deserialize<'de> for KeyRemap {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+
+        s.parse().map_err(|_e| {
+            <D::Error as serde::de::Error>::invalid_value(
+                serde::de::Unexpected::Str(&s),
+                &"KeyRemap",
+            )
+        })
+    }
+}
+
+/// Keys which are rewritten before hotkeys and layouts
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Clone, Debug, Default)]
+pub struct RemapConfig {
+    pub global: BTreeMap<Key, KeyRemap>,
+    /// Overrides `global` in the category
+    pub category: BTreeMap<InputCategory, BTreeMap<Key, KeyRemap>>,
+}
+
+/// Timeouts of hotkey triggers in milliseconds
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Clone, Copy, Debug)]
+pub struct HotkeyTimeoutConfig {
+    /// Maximum interval between keys of sequence
+    pub sequence: u64,
+    /// Maximum interval between two presses of double tap
+    pub double_tap: u64,
+    /// Minimum duration of long press, also maximum duration of tap
+    pub hold: u64,
+}
+
+impl Default for HotkeyTimeoutConfig {
+    fn default() -> Self {
+        Self {
+            sequence: 1000,
+            double_tap: 300,
+            hold: 500,
+        }
+    }
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[derive(Clone, Copy)]
 #[repr(C)]
@@ -134,18 +468,67 @@ impl Default for LogConfig {
     }
 }
 
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum AutoConvertAction {
+    /// Convert word to hangul and switch to hangul category
+    Convert,
+    /// Show converted word as preedit, `Convert` hotkey accept it
+    Suggest,
+}
+
+/// Detect hangul word which is typed in latin category
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Clone, Copy, Debug)]
+pub struct AutoConvertConfig {
+    pub enable: bool,
+    pub action: AutoConvertAction,
+    /// Minimum count of keys in word
+    pub min_keys: usize,
+    /// Minimum count of hangul syllables in converted word
+    pub min_syllables: usize,
+}
+
+impl Default for AutoConvertConfig {
+    fn default() -> Self {
+        Self {
+            enable: false,
+            action: AutoConvertAction::Convert,
+            min_keys: 4,
+            min_syllables: 2,
+        }
+    }
+}
+
+/// Input category policy of each application
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+#[cfg_attr(feature = "serde", serde(default))]
+#[derive(Clone, Debug, Default)]
+pub struct AppCategoryConfig {
+    /// Restore last input category when application get focus again
+    pub remember: bool,
+    /// Input category which application always starts with, key is application id
+    pub rules: BTreeMap<String, InputCategory>,
+}
+
 #[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 #[cfg_attr(feature = "serde", serde(default))]
 pub struct EngineConfig {
+    pub categories: Vec<InputCategory>,
     pub default_category: InputCategory,
     pub global_category_state: bool,
-    pub global_hotkeys: BTreeMap<Key, Hotkey>,
-    pub category_hotkeys: BTreeMap<InputCategory, BTreeMap<Key, Hotkey>>,
-    pub mode_hotkeys: BTreeMap<InputMode, BTreeMap<Key, Hotkey>>,
+    pub global_hotkeys: BTreeMap<HotkeyTrigger, Hotkey>,
+    pub category_hotkeys: BTreeMap<InputCategory, BTreeMap<HotkeyTrigger, Hotkey>>,
+    pub mode_hotkeys: BTreeMap<InputMode, BTreeMap<HotkeyTrigger, Hotkey>>,
+    pub hotkey_timeout: HotkeyTimeoutConfig,
+    pub remap: RemapConfig,
     pub candidate_font: String,
     pub xim_preedit_font: (String, f32),
     pub latin: LatinConfig,
     pub hangul: HangulConfig,
+    pub auto_convert: AutoConvertConfig,
+    pub app_category: AppCategoryConfig,
 }
 
 impl Default for EngineConfig {
@@ -153,40 +536,45 @@ impl Default for EngineConfig {
         Self {
             latin: LatinConfig::default(),
             hangul: HangulConfig::default(),
-            default_category: InputCategory::Latin,
+            categories: vec![InputCategory::LATIN, InputCategory::HANGUL],
+            default_category: InputCategory::LATIN,
             global_category_state: false,
             global_hotkeys: btreemap! {
-                Key::normal(KeyCode::Esc) => Hotkey::new(HotkeyBehavior::Switch(InputCategory::Latin), HotkeyResult::Bypass),
-                Key::normal(KeyCode::AltR) => Hotkey::new(HotkeyBehavior::toggle_hangul_latin(), HotkeyResult::Consume),
-                Key::normal(KeyCode::Hangul) => Hotkey::new(HotkeyBehavior::toggle_hangul_latin(), HotkeyResult::Consume),
-                Key::super_(KeyCode::Space) => Hotkey::new(HotkeyBehavior::toggle_hangul_latin(), HotkeyResult::Consume),
-                Key::normal(KeyCode::Muhenkan) => Hotkey::new(HotkeyBehavior::toggle_hangul_latin(), HotkeyResult::Consume),
-                Key::new(KeyCode::E, ModifierState::CONTROL | ModifierState::ALT) => Hotkey::new(HotkeyBehavior::Mode(InputMode::Emoji), HotkeyResult::ConsumeIfProcessed),
-                Key::new(KeyCode::Backslash, ModifierState::CONTROL | ModifierState::ALT) => Hotkey::new(HotkeyBehavior::Mode(InputMode::Math), HotkeyResult::ConsumeIfProcessed),
+                Key::normal(KeyCode::Esc).into() => Hotkey::new(HotkeyBehavior::Switch(InputCategory::LATIN), HotkeyResult::Bypass),
+                Key::normal(KeyCode::AltR).into() => Hotkey::new(HotkeyBehavior::toggle_hangul_latin(), HotkeyResult::Consume),
+                Key::normal(KeyCode::Hangul).into() => Hotkey::new(HotkeyBehavior::toggle_hangul_latin(), HotkeyResult::Consume),
+                Key::super_(KeyCode::Space).into() => Hotkey::new(HotkeyBehavior::toggle_hangul_latin(), HotkeyResult::Consume),
+                Key::normal(KeyCode::Muhenkan).into() => Hotkey::new(HotkeyBehavior::toggle_hangul_latin(), HotkeyResult::Consume),
+                Key::new(KeyCode::E, ModifierState::CONTROL | ModifierState::ALT).into() => Hotkey::new(HotkeyBehavior::Mode(InputMode::Emoji), HotkeyResult::ConsumeIfProcessed),
+                Key::new(KeyCode::Backslash, ModifierState::CONTROL | ModifierState::ALT).into() => Hotkey::new(HotkeyBehavior::Mode(InputMode::Math), HotkeyResult::ConsumeIfProcessed),
             },
             category_hotkeys: btreemap! {
-                InputCategory::Hangul => btreemap! {
-                    Key::normal(KeyCode::F9) => Hotkey::new(HotkeyBehavior::Mode(InputMode::Hanja), HotkeyResult::ConsumeIfProcessed),
-                    Key::normal(KeyCode::HangulHanja) => Hotkey::new(HotkeyBehavior::Mode(InputMode::Hanja), HotkeyResult::Consume),
-                    Key::normal(KeyCode::ControlR) => Hotkey::new(HotkeyBehavior::Mode(InputMode::Hanja), HotkeyResult::Consume),
+                InputCategory::HANGUL => btreemap! {
+                    Key::normal(KeyCode::F9).into() => Hotkey::new(HotkeyBehavior::Mode(InputMode::Hanja), HotkeyResult::ConsumeIfProcessed),
+                    Key::normal(KeyCode::HangulHanja).into() => Hotkey::new(HotkeyBehavior::Mode(InputMode::Hanja), HotkeyResult::Consume),
+                    Key::normal(KeyCode::ControlR).into() => Hotkey::new(HotkeyBehavior::Mode(InputMode::Hanja), HotkeyResult::Consume),
                 },
             },
             mode_hotkeys: btreemap! {
                 InputMode::Hanja => btreemap! {
-                    Key::normal(KeyCode::Enter) => Hotkey::new(HotkeyBehavior::Commit, HotkeyResult::ConsumeIfProcessed),
-                    Key::normal(KeyCode::Tab) => Hotkey::new(HotkeyBehavior::Commit, HotkeyResult::ConsumeIfProcessed),
+                    Key::normal(KeyCode::Enter).into() => Hotkey::new(HotkeyBehavior::Commit, HotkeyResult::ConsumeIfProcessed),
+                    Key::normal(KeyCode::Tab).into() => Hotkey::new(HotkeyBehavior::Commit, HotkeyResult::ConsumeIfProcessed),
                 },
                 InputMode::Emoji => btreemap! {
-                    Key::normal(KeyCode::Enter) => Hotkey::new(HotkeyBehavior::Commit, HotkeyResult::ConsumeIfProcessed),
-                    Key::normal(KeyCode::Tab) => Hotkey::new(HotkeyBehavior::Commit, HotkeyResult::ConsumeIfProcessed),
+                    Key::normal(KeyCode::Enter).into() => Hotkey::new(HotkeyBehavior::Commit, HotkeyResult::ConsumeIfProcessed),
+                    Key::normal(KeyCode::Tab).into() => Hotkey::new(HotkeyBehavior::Commit, HotkeyResult::ConsumeIfProcessed),
                 },
                 InputMode::Math => btreemap! {
-                    Key::normal(KeyCode::Enter) => Hotkey::new(HotkeyBehavior::Commit, HotkeyResult::ConsumeIfProcessed),
-                    Key::normal(KeyCode::Tab) => Hotkey::new(HotkeyBehavior::Commit, HotkeyResult::ConsumeIfProcessed),
+                    Key::normal(KeyCode::Enter).into() => Hotkey::new(HotkeyBehavior::Commit, HotkeyResult::ConsumeIfProcessed),
+                    Key::normal(KeyCode::Tab).into() => Hotkey::new(HotkeyBehavior::Commit, HotkeyResult::ConsumeIfProcessed),
                 },
             },
             xim_preedit_font: ("D2Coding".to_string(), 15.0),
             candidate_font: "D2Coding".to_string(),
+            auto_convert: AutoConvertConfig::default(),
+            app_category: AppCategoryConfig::default(),
+            hotkey_timeout: HotkeyTimeoutConfig::default(),
+            remap: RemapConfig::default(),
         }
     }
 }
@@ -200,3 +588,58 @@ pub struct RawConfig {
     pub log: LogConfig,
     pub engine: EngineConfig,
 }
+
+#[test]
+fn trigger_parse() {
+    assert_eq!(
+        "C-Space H".parse::<HotkeyTrigger>().unwrap(),
+        HotkeyTrigger::Sequence(vec![Key::ctrl(KeyCode::Space), Key::normal(KeyCode::H)])
+    );
+    assert_eq!(
+        "Double-Shift".parse::<HotkeyTrigger>().unwrap(),
+        HotkeyTrigger::Double(Key::normal(KeyCode::Shift))
+    );
+    assert_eq!(
+        "Hold-S-X".parse::<HotkeyTrigger>().unwrap(),
+        HotkeyTrigger::Hold(Key::shift(KeyCode::X))
+    );
+    assert_eq!(
+        "Tap-ControlL".parse::<HotkeyTrigger>().unwrap(),
+        HotkeyTrigger::Tap(Key::normal(KeyCode::ControlL))
+    );
+    assert_eq!(
+        "Esc".parse::<HotkeyTrigger>().unwrap(),
+        HotkeyTrigger::Key(Key::normal(KeyCode::Esc))
+    );
+    assert_eq!(
+        HotkeyTrigger::Sequence(vec![Key::ctrl(KeyCode::Space), Key::normal(KeyCode::H)])
+            .to_string(),
+        "C-Space H"
+    );
+}
+
+#[test]
+fn remap_parse() {
+    assert_eq!(
+        "Hangul".parse::<KeyRemap>().unwrap(),
+        KeyRemap::Key(Key::normal(KeyCode::Hangul))
+    );
+    assert_eq!(
+        "Mod-Control".parse::<KeyRemap>().unwrap(),
+        KeyRemap::Modifier(ModifierState::CONTROL)
+    );
+    assert!("Mod-Hyper".parse::<KeyRemap>().is_err());
+    assert_eq!(
+        KeyRemap::Modifier(ModifierState::LEVEL3).to_string(),
+        "Mod-AltGr"
+    );
+}
+
+#[test]
+fn find_category() {
+    assert_eq!(InputCategory::find("Hangul"), Some(InputCategory::HANGUL));
+    assert_eq!(InputCategory::find("kime-find-category-unknown"), None);
+
+    let kana = InputCategory::new("kime-find-category-known");
+    assert_eq!(InputCategory::find("kime-find-category-known"), Some(kana));
+}
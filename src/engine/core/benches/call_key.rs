@@ -6,7 +6,7 @@ fn simple(c: &mut Criterion) {
 
     c.bench_function("simple 100", |b| {
         let mut engine = InputEngine::new(&config);
-        engine.set_input_category(InputCategory::Hangul);
+        engine.set_input_category(InputCategory::HANGUL);
         b.iter(|| {
             for _ in 0..100 {
                 engine.press_key(Key::normal(A), &config);
@@ -17,7 +17,7 @@ fn simple(c: &mut Criterion) {
 
     c.bench_function("simple 1000", |b| {
         let mut engine = InputEngine::new(&config);
-        engine.set_input_category(InputCategory::Hangul);
+        engine.set_input_category(InputCategory::HANGUL);
         b.iter(|| {
             for _ in 0..1000 {
                 engine.press_key(Key::normal(A), &config);
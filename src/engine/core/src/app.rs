@@ -0,0 +1,41 @@
+use crate::{Config, InputCategory};
+use parking_lot::Mutex;
+use std::{collections::BTreeMap, sync::Arc};
+
+/// Last input category of each application, shared by engines which are created with same config
+#[derive(Clone, Default)]
+pub(crate) struct LastCategories(Arc<Mutex<BTreeMap<String, InputCategory>>>);
+
+impl LastCategories {
+    pub fn remember(&self, app_id: &str, category: InputCategory) {
+        self.0.lock().insert(app_id.into(), category);
+    }
+
+    fn get(&self, app_id: &str) -> Option<InputCategory> {
+        self.0.lock().get(app_id).copied()
+    }
+}
+
+/// Application ids are compared case insensitively
+pub(crate) fn normalize_app_id(app_id: &str) -> String {
+    app_id.trim().to_lowercase()
+}
+
+/// Input category which application should start with
+///
+/// `None` means there is no policy for this application
+pub(crate) fn start_category(
+    config: &Config,
+    last: &LastCategories,
+    app_id: &str,
+) -> Option<InputCategory> {
+    if let Some(category) = config.app_category.rules.get(app_id) {
+        return Some(*category);
+    }
+
+    if config.app_category.remember {
+        Some(last.get(app_id).unwrap_or(config.default_category))
+    } else {
+        None
+    }
+}
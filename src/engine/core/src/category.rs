@@ -0,0 +1,184 @@
+use crate::{InputCategory, Key, Preedit};
+use kime_engine_backend::InputEngineBackend;
+use std::{any::Any, time::Instant};
+
+/// Type erased [`InputEngineBackend`] so that backends of each category can be stored together
+pub(crate) trait CategoryBackend {
+    fn press_key(
+        &mut self,
+        data: &dyn Any,
+        key: Key,
+        time: Instant,
+        commit_buf: &mut String,
+    ) -> bool;
+    fn release_key(
+        &mut self,
+        data: &dyn Any,
+        key: Key,
+        time: Instant,
+        commit_buf: &mut String,
+    ) -> bool;
+    fn clear_preedit(&mut self, commit_buf: &mut String);
+    fn reset(&mut self);
+    fn has_preedit(&self) -> bool;
+    fn preedit_str(&self, buf: &mut String);
+    fn preedit(&self, preedit: &mut Preedit);
+    fn reopen_surrounding(&mut self, text: &str) -> usize;
+    fn save_state(&self) -> Option<String>;
+    fn restore_state(&mut self, state: &str) -> bool;
+    fn layout(&self) -> Option<&str>;
+    fn select_layout(&mut self, data: &dyn Any, name: Option<&str>) -> bool;
+    fn alternatives(&self) -> &[String];
+}
+
+impl<B> CategoryBackend for B
+where
+    B: InputEngineBackend + 'static,
+    B::ConfigData: 'static,
+{
+    fn press_key(
+        &mut self,
+        data: &dyn Any,
+        key: Key,
+        time: Instant,
+        commit_buf: &mut String,
+    ) -> bool {
+        match data.downcast_ref() {
+            Some(data) => InputEngineBackend::press_key_at(self, data, key, time, commit_buf),
+            None => false,
+        }
+    }
+
+    fn release_key(
+        &mut self,
+        data: &dyn Any,
+        key: Key,
+        time: Instant,
+        commit_buf: &mut String,
+    ) -> bool {
+        match data.downcast_ref() {
+            Some(data) => InputEngineBackend::release_key(self, data, key, time, commit_buf),
+            None => false,
+        }
+    }
+
+    #[inline]
+    fn clear_preedit(&mut self, commit_buf: &mut String) {
+        InputEngineBackend::clear_preedit(self, commit_buf);
+    }
+
+    #[inline]
+    fn reset(&mut self) {
+        InputEngineBackend::reset(self);
+    }
+
+    #[inline]
+    fn has_preedit(&self) -> bool {
+        InputEngineBackend::has_preedit(self)
+    }
+
+    #[inline]
+    fn preedit_str(&self, buf: &mut String) {
+        InputEngineBackend::preedit_str(self, buf);
+    }
+
+    #[inline]
+    fn preedit(&self, preedit: &mut Preedit) {
+        InputEngineBackend::preedit(self, preedit);
+    }
+
+    #[inline]
+    fn reopen_surrounding(&mut self, text: &str) -> usize {
+        InputEngineBackend::reopen_surrounding(self, text)
+    }
+
+    #[inline]
+    fn save_state(&self) -> Option<String> {
+        InputEngineBackend::save_state(self)
+    }
+
+    #[inline]
+    fn restore_state(&mut self, state: &str) -> bool {
+        InputEngineBackend::restore_state(self, state)
+    }
+
+    #[inline]
+    fn layout(&self) -> Option<&str> {
+        InputEngineBackend::layout(self)
+    }
+
+    fn select_layout(&mut self, data: &dyn Any, name: Option<&str>) -> bool {
+        match data.downcast_ref() {
+            Some(data) => InputEngineBackend::select_layout(self, data, name),
+            None => false,
+        }
+    }
+
+    #[inline]
+    fn alternatives(&self) -> &[String] {
+        InputEngineBackend::alternatives(self)
+    }
+}
+
+type NewBackend = dyn Fn(&dyn Any) -> Box<dyn CategoryBackend> + Send + Sync;
+type DataEq = dyn Fn(&dyn Any, &dyn Any) -> bool + Send + Sync;
+
+pub(crate) struct CategoryEntry {
+    pub category: InputCategory,
+    pub data: Box<dyn Any + Send + Sync>,
+    new_backend: Box<NewBackend>,
+    data_eq: Box<DataEq>,
+}
+
+impl CategoryEntry {
+    pub fn new_backend(&self) -> Box<dyn CategoryBackend> {
+        (self.new_backend)(&*self.data)
+    }
+
+    /// Compare config data of same category
+    pub fn same_data(&self, other: &Self) -> bool {
+        (self.data_eq)(&*self.data, &*other.data)
+    }
+}
+
+/// Registry of backends for each input category
+#[derive(Default)]
+pub(crate) struct CategoryRegistry {
+    entries: Vec<CategoryEntry>,
+}
+
+impl CategoryRegistry {
+    pub fn register<B, F>(&mut self, category: InputCategory, data: B::ConfigData, new_backend: F)
+    where
+        B: InputEngineBackend + 'static,
+        B::ConfigData: PartialEq + Send + Sync + 'static,
+        F: Fn(&B::ConfigData) -> B + Send + Sync + 'static,
+    {
+        let entry = CategoryEntry {
+            category,
+            data: Box::new(data),
+            new_backend: Box::new(move |data| {
+                let data = data
+                    .downcast_ref::<B::ConfigData>()
+                    .expect("Registered data type");
+                Box::new(new_backend(data))
+            }),
+            data_eq: Box::new(|left, right| {
+                left.downcast_ref::<B::ConfigData>() == right.downcast_ref::<B::ConfigData>()
+            }),
+        };
+
+        match self.entries.iter_mut().find(|e| e.category == category) {
+            Some(e) => *e = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    pub fn get(&self, category: InputCategory) -> Option<&CategoryEntry> {
+        self.entries.iter().find(|e| e.category == category)
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &CategoryEntry> {
+        self.entries.iter()
+    }
+}
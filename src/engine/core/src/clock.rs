@@ -0,0 +1,33 @@
+use std::time::{Duration, Instant};
+
+/// Convert millisecond timestamps of key events into [`Instant`]
+///
+/// Base of timestamps is unknown so first event is paired with current time,
+/// timestamps are 32bit so they can wrap around
+#[derive(Default)]
+pub(crate) struct EventClock {
+    base: Option<(u32, Instant)>,
+}
+
+impl EventClock {
+    /// `0` means event doesn't have timestamp
+    pub fn instant(&mut self, timestamp: u32) -> Instant {
+        let now = Instant::now();
+
+        if timestamp == 0 {
+            return now;
+        }
+
+        if let Some((base_timestamp, base)) = self.base {
+            let time = base + Duration::from_millis(timestamp.wrapping_sub(base_timestamp).into());
+
+            // Event can't be later than now, base was paired with late event or timestamp went back
+            if time <= now {
+                return time;
+            }
+        }
+
+        self.base = Some((timestamp, now));
+        now
+    }
+}
@@ -1,18 +1,39 @@
+use crate::app::LastCategories;
+use crate::category::CategoryRegistry;
 use fontconfig_parser::FontConfig;
 use fontdb::{Family, Query};
+use kime_engine_backend::InputEngineBackend;
+pub use kime_engine_backend_hanja::HanjaConfig;
+use kime_engine_backend_latin::LatinEngine;
 pub use kime_engine_config::*;
+use std::collections::BTreeMap;
 
 /// Preprocessed engine config
 pub struct Config {
     pub default_category: InputCategory,
     pub global_category_state: bool,
-    pub category_hotkeys: EnumMap<InputCategory, Vec<(Key, Hotkey)>>,
-    pub mode_hotkeys: EnumMap<InputMode, Vec<(Key, Hotkey)>>,
+    pub global_hotkeys: Vec<(HotkeyTrigger, Hotkey)>,
+    pub category_hotkeys: BTreeMap<InputCategory, Vec<(HotkeyTrigger, Hotkey)>>,
+    pub mode_hotkeys: EnumMap<InputMode, Vec<(HotkeyTrigger, Hotkey)>>,
+    pub hotkey_timeout: HotkeyTimeoutConfig,
+    pub global_remap: BTreeMap<Key, KeyRemap>,
+    /// Effective remap of categories which have their own remap
+    pub category_remap: BTreeMap<InputCategory, BTreeMap<Key, KeyRemap>>,
     pub candidate_font: (Vec<u8>, u32),
     pub xim_preedit_font: (Vec<u8>, u32, f32),
-    pub hangul_data: HangulData,
-    pub preferred_direct: bool,
     pub latin_data: LatinData,
+    /// Frontend should use `press_keysym` instead of `press_key_code`
+    pub keysym_layout: bool,
+    /// Normalization of committed hangul, text of modes e.g. hanja is also normalized
+    pub commit_normalization: CommitNormalization,
+    /// `None` when auto convert is disabled
+    pub auto_convert: Option<AutoConvertConfig>,
+    /// Application ids of rules are lowercase
+    pub app_category: AppCategoryConfig,
+    /// Dictionary directory and candidate window of hanja mode, it's used when engine is created
+    pub hanja: HanjaConfig,
+    pub(crate) categories: CategoryRegistry,
+    pub(crate) last_categories: LastCategories,
 }
 
 impl Default for Config {
@@ -42,32 +63,76 @@ impl Config {
             .unwrap_or_default()
         };
 
+        let latin_data = LatinData::new(&engine.latin);
+        let mut categories = CategoryRegistry::default();
+
+        for category in engine.categories.iter().copied() {
+            match category {
+                InputCategory::LATIN => {
+                    categories.register(category, latin_data.clone(), |data: &LatinData| {
+                        LatinEngine::new(data.preferred_direct())
+                    });
+                }
+                InputCategory::HANGUL => {
+                    categories.register(category, hangul_data.clone(), HangulData::new_engine);
+                }
+                // Should be registered by `register_category`
+                _ => {}
+            }
+        }
+
         Self {
             default_category: engine.default_category,
             global_category_state: engine.global_category_state,
-            category_hotkeys: enum_map! {
-                cat => {
-                    if let Some(map) = engine.category_hotkeys.get_mut(&cat) { for (k, v) in engine.global_hotkeys.iter() {
-                            map.entry(*k).or_insert(*v);
+            global_hotkeys: engine
+                .global_hotkeys
+                .iter()
+                .map(|(k, v)| (k.clone(), *v))
+                .collect(),
+            category_hotkeys: {
+                let global_hotkeys = &engine.global_hotkeys;
+                let category_hotkeys = &mut engine.category_hotkeys;
+                engine
+                    .categories
+                    .iter()
+                    .filter_map(|cat| {
+                        let map = category_hotkeys.get_mut(cat)?;
+                        for (k, v) in global_hotkeys.iter() {
+                            map.entry(k.clone()).or_insert(*v);
                         }
-                        map.iter().map(|(k, v)| (*k, *v)).collect()
-                    } else {
-                        engine.global_hotkeys.iter().map(|(k, v)| (*k, *v)).collect()
-                    }
-                }
+                        Some((*cat, map.iter().map(|(k, v)| (k.clone(), *v)).collect()))
+                    })
+                    .collect()
             },
             mode_hotkeys: enum_map! {
                 mode => {
                     if let Some(map) = engine.mode_hotkeys.get_mut(&mode) {
                         for (k, v) in engine.global_hotkeys.iter() {
-                            map.entry(*k).or_insert(*v);
+                            map.entry(k.clone()).or_insert(*v);
                         }
-                        map.iter().map(|(k, v)| (*k, *v)).collect()
+                        map.iter().map(|(k, v)| (k.clone(), *v)).collect()
                     } else {
-                        engine.global_hotkeys.iter().map(|(k, v)| (*k, *v)).collect()
+                        engine.global_hotkeys.iter().map(|(k, v)| (k.clone(), *v)).collect()
                     }
                 }
             },
+            hotkey_timeout: engine.hotkey_timeout,
+            category_remap: {
+                let global_remap = &engine.remap.global;
+                let category_remap = &mut engine.remap.category;
+                engine
+                    .categories
+                    .iter()
+                    .filter_map(|cat| {
+                        let mut map = category_remap.remove(cat)?;
+                        for (k, v) in global_remap.iter() {
+                            map.entry(*k).or_insert(*v);
+                        }
+                        Some((*cat, map))
+                    })
+                    .collect()
+            },
+            global_remap: engine.remap.global.clone(),
             xim_preedit_font: {
                 let (font, index) = load_font(&engine.xim_preedit_font.0);
                 (font, index, engine.xim_preedit_font.1)
@@ -76,12 +141,77 @@ impl Config {
                 let (font, index) = load_font(&engine.candidate_font);
                 (font, index)
             },
-            preferred_direct: engine.latin.preferred_direct,
-            latin_data: LatinData::new(&engine.latin),
-            hangul_data,
+            latin_data,
+            keysym_layout: engine.hangul.keysym_layout,
+            commit_normalization: engine.hangul.commit_normalization,
+            auto_convert: if engine.auto_convert.enable {
+                Some(engine.auto_convert)
+            } else {
+                None
+            },
+            app_category: AppCategoryConfig {
+                remember: engine.app_category.remember,
+                rules: engine
+                    .app_category
+                    .rules
+                    .iter()
+                    .map(|(app_id, category)| (crate::app::normalize_app_id(app_id), *category))
+                    .collect(),
+            },
+            hanja: HanjaConfig::default(),
+            categories,
+            last_categories: LastCategories::default(),
         }
     }
 
+    /// Register backend of input category
+    ///
+    /// previous backend of same category will be replaced
+    pub fn register_category<B, F>(
+        &mut self,
+        category: InputCategory,
+        data: B::ConfigData,
+        new_backend: F,
+    ) where
+        B: InputEngineBackend + 'static,
+        B::ConfigData: PartialEq + Send + Sync + 'static,
+        F: Fn(&B::ConfigData) -> B + Send + Sync + 'static,
+    {
+        self.categories.register(category, data, new_backend);
+    }
+
+    /// Registered input categories
+    pub fn categories(&self) -> impl Iterator<Item = InputCategory> + '_ {
+        self.categories.iter().map(|e| e.category)
+    }
+
+    pub fn has_category(&self, category: InputCategory) -> bool {
+        self.categories.get(category).is_some()
+    }
+
+    /// Get config data of input category
+    pub fn category_data<T: 'static>(&self, category: InputCategory) -> Option<&T> {
+        self.categories.get(category)?.data.downcast_ref()
+    }
+
+    pub fn hangul_data(&self) -> Option<&HangulData> {
+        self.category_data(InputCategory::HANGUL)
+    }
+
+    /// Hotkeys of input category
+    pub fn category_hotkeys(&self, category: InputCategory) -> &[(HotkeyTrigger, Hotkey)] {
+        self.category_hotkeys
+            .get(&category)
+            .unwrap_or(&self.global_hotkeys)
+    }
+
+    /// Remap of input category
+    pub fn remap(&self, category: InputCategory) -> &BTreeMap<Key, KeyRemap> {
+        self.category_remap
+            .get(&category)
+            .unwrap_or(&self.global_remap)
+    }
+
     pub fn new(engine: EngineConfig) -> Self {
         let hangul_data = HangulData::new(
             &engine.hangul,
@@ -0,0 +1,215 @@
+use crate::{config::AutoConvertConfig, Config, Key, KeyCode, ModifierState};
+
+/// Keys of the word which is being typed
+#[derive(Default)]
+pub(crate) struct WordHistory {
+    keys: Vec<Key>,
+    /// Count of chars of this word which are already sent to client
+    committed: usize,
+}
+
+impl WordHistory {
+    #[inline]
+    pub fn clear(&mut self) {
+        self.keys.clear();
+        self.committed = 0;
+    }
+
+    #[inline]
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    #[inline]
+    pub fn keys(&self) -> &[Key] {
+        &self.keys
+    }
+
+    #[inline]
+    pub fn committed(&self) -> usize {
+        self.committed
+    }
+
+    /// Take keys and count of committed chars
+    pub fn take(&mut self) -> (Vec<Key>, usize) {
+        let committed = self.committed;
+        self.committed = 0;
+        (std::mem::take(&mut self.keys), committed)
+    }
+
+    /// Record pressed key
+    ///
+    /// `consumed` means engine has handled key otherwise client will type it
+    /// `commit` is commit string which is made by this key
+    pub fn record(
+        &mut self,
+        config: &Config,
+        key: Key,
+        consumed: bool,
+        can_pop_committed: bool,
+        commit: &str,
+    ) {
+        if key.code == KeyCode::Backspace {
+            if consumed {
+                self.keys.pop();
+            } else if can_pop_committed && self.committed > 0 {
+                self.keys.pop();
+                self.committed -= 1;
+            } else {
+                self.clear();
+            }
+            return;
+        }
+
+        let is_word_key = !key
+            .state
+            .intersects(!(ModifierState::SHIFT | ModifierState::LOCKS))
+            && matches!(config.latin_data.lookup(key), Some(ch) if !ch.is_whitespace());
+
+        if is_word_key {
+            self.keys.push(key);
+            self.committed += commit.chars().count();
+            if !consumed {
+                self.committed += 1;
+            }
+        } else {
+            self.clear();
+        }
+    }
+}
+
+/// Converted word which is shown as preedit until `Convert` hotkey accept it
+pub(crate) struct Suggestion {
+    pub keys: Vec<Key>,
+    /// Count of chars of the word and boundary which are already sent to client
+    pub committed: usize,
+    pub text: String,
+    /// Char which has ended the word
+    pub boundary: char,
+}
+
+/// Whitespace which is typed by `key`
+pub(crate) fn boundary_char(config: &Config, key: Key) -> Option<char> {
+    match key.code {
+        KeyCode::Space => Some(' '),
+        _ => config
+            .latin_data
+            .lookup(key)
+            .filter(|ch| ch.is_whitespace()),
+    }
+}
+
+/// Hangul text of `keys` when they look like hangul word typed in latin category
+pub(crate) fn detect_mistyped(
+    config: &Config,
+    auto_convert: &AutoConvertConfig,
+    layout: &str,
+    keys: &[Key],
+) -> Option<String> {
+    if keys.is_empty() || keys.len() < auto_convert.min_keys {
+        return None;
+    }
+
+    let word = keys
+        .iter()
+        .map(|key| config.latin_data.lookup(*key))
+        .collect::<Option<String>>()?;
+
+    if !word.chars().all(|ch| ch.is_ascii_alphabetic())
+        || kime_engine_dict::is_english_word(&word.to_ascii_lowercase())
+    {
+        return None;
+    }
+
+    let text = config.hangul_data()?.type_keys(layout, keys)?;
+
+    // Every key should make complete syllable without leftover jamo
+    if text.chars().all(|ch| matches!(ch, '\u{AC00}'..='\u{D7A3}'))
+        && text.chars().count() >= auto_convert.min_syllables
+    {
+        Some(text)
+    } else {
+        None
+    }
+}
+
+/// Last word of text
+pub(crate) fn last_word(text: &str) -> &str {
+    let start = text
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| !ch.is_whitespace())
+        .last()
+        .map_or(text.len(), |(idx, _)| idx);
+
+    &text[start..]
+}
+
+/// Hangul syllables at the end of text
+pub(crate) fn last_hangul_word(text: &str) -> &str {
+    let start = text
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| ('가'..='힣').contains(ch))
+        .last()
+        .map_or(text.len(), |(idx, _)| idx);
+
+    &text[start..]
+}
+
+/// Choseong jamo at the end of text which are committed one by one e.g. `ㄷㅎㅁ`
+pub(crate) fn last_choseong_word(text: &str) -> &str {
+    let start = text
+        .char_indices()
+        .rev()
+        .take_while(|(_, ch)| kime_engine_dict::choseong::is_choseong(*ch))
+        .last()
+        .map_or(text.len(), |(idx, _)| idx);
+
+    &text[start..]
+}
+
+/// Text is one hangul compatibility jamo e.g. `ㅁ`
+pub(crate) fn is_single_jamo(text: &str) -> bool {
+    let mut chars = text.chars();
+    matches!(
+        (chars.next(), chars.next()),
+        (Some('\u{3131}'..='\u{318E}'), None)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{is_single_jamo, last_choseong_word, last_hangul_word, last_word};
+
+    #[test]
+    fn last_word_test() {
+        assert_eq!(last_word("hello dkssud"), "dkssud");
+        assert_eq!(last_word("안녕"), "안녕");
+        assert_eq!(last_word("hello "), "");
+        assert_eq!(last_word(""), "");
+    }
+
+    #[test]
+    fn last_hangul_word_test() {
+        assert_eq!(last_hangul_word("오늘은 대한민국"), "대한민국");
+        assert_eq!(last_hangul_word("hello대학"), "대학");
+        assert_eq!(last_hangul_word("대학 "), "");
+    }
+
+    #[test]
+    fn last_choseong_word_test() {
+        assert_eq!(last_choseong_word("오늘 ㄷㅎㅁ"), "ㄷㅎㅁ");
+        assert_eq!(last_choseong_word("ㅏㄷ"), "ㄷ");
+        assert_eq!(last_choseong_word("ㄷㅎ "), "");
+    }
+
+    #[test]
+    fn single_jamo_test() {
+        assert!(is_single_jamo("ㅁ"));
+        assert!(is_single_jamo("ㆆ"));
+        assert!(!is_single_jamo("ㅁㅁ"));
+        assert!(!is_single_jamo("마"));
+        assert!(!is_single_jamo(""));
+    }
+}
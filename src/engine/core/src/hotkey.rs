@@ -0,0 +1,227 @@
+use crate::{Hotkey, HotkeyTimeoutConfig, HotkeyTrigger, Key, KeyCode};
+use std::time::{Duration, Instant};
+
+pub(crate) enum HotkeyMatch {
+    /// Trigger is completed
+    Fire(Hotkey),
+    /// Key is consumed as a part of trigger, release of it is also consumed
+    Pending,
+    /// Pending keys aren't a part of trigger anymore, they should be processed before the key
+    Replay(Vec<Key>),
+    None,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum TriggerState {
+    Full,
+    Prefix,
+    Mismatch,
+}
+
+/// Match multi stroke hotkey triggers
+#[derive(Default)]
+pub(crate) struct HotkeyMatcher {
+    /// Keys which are consumed while waiting rest of trigger
+    pending: Vec<Key>,
+    /// Time of first and last pending key
+    pending_at: Option<(Instant, Instant)>,
+    /// Pending keys which aren't released yet, client never got their presses
+    pressed: Vec<KeyCode>,
+    /// Key of fired `Hold` trigger with time of last press, its repeated presses are consumed
+    holding: Option<(Key, Instant)>,
+    /// Key of `Tap` trigger which is pressed without other keys
+    tap: Option<(Key, Instant)>,
+}
+
+impl HotkeyMatcher {
+    pub fn clear(&mut self) {
+        self.pending.clear();
+        self.pending_at = None;
+        self.pressed.clear();
+        self.holding = None;
+        self.tap = None;
+    }
+
+    pub fn press(
+        &mut self,
+        hotkeys: &[(HotkeyTrigger, Hotkey)],
+        timeout: &HotkeyTimeoutConfig,
+        key: Key,
+        now: Instant,
+    ) -> HotkeyMatch {
+        let hold = Duration::from_millis(timeout.hold);
+        // Pending keys keep lock modifiers for replay
+        let pressed = key;
+        let key = key.without_locks();
+
+        match self.tap {
+            // Key repeat
+            Some((tap, _)) if tap.code == key.code => {}
+            _ => {
+                self.tap = hotkeys
+                    .iter()
+                    .any(|(trigger, _)| matches!(trigger, HotkeyTrigger::Tap(k) if *k == key))
+                    .then_some((key, now));
+            }
+        }
+
+        if let Some((held, last)) = self.holding.take() {
+            if held == key && now - last < hold {
+                // Key is still repeated
+                self.holding = Some((key, now));
+                return HotkeyMatch::Pending;
+            }
+        }
+
+        let mut prefix = false;
+
+        for (trigger, hotkey) in hotkeys.iter() {
+            match self.check(trigger, timeout, key, now) {
+                TriggerState::Full => {
+                    if let HotkeyTrigger::Hold(_) = trigger {
+                        self.holding = Some((key, now));
+                    }
+                    self.pending.clear();
+                    self.pending_at = None;
+                    return HotkeyMatch::Fire(*hotkey);
+                }
+                TriggerState::Prefix => prefix = true,
+                TriggerState::Mismatch => {}
+            }
+        }
+
+        if prefix {
+            if !self.pressed.contains(&key.code) {
+                self.pressed.push(key.code);
+            }
+            self.pending.push(pressed);
+            self.pending_at = Some(match self.pending_at {
+                Some((first, _)) => (first, now),
+                None => (now, now),
+            });
+            HotkeyMatch::Pending
+        } else if self.pending.is_empty() || crate::is_modifier(key.code) {
+            // Modifier may be pressed before the next key of sequence
+            HotkeyMatch::None
+        } else {
+            self.pending_at = None;
+            HotkeyMatch::Replay(std::mem::take(&mut self.pending))
+        }
+    }
+
+    pub fn release(
+        &mut self,
+        hotkeys: &[(HotkeyTrigger, Hotkey)],
+        timeout: &HotkeyTimeoutConfig,
+        key: Key,
+        now: Instant,
+    ) -> HotkeyMatch {
+        let key = key.without_locks();
+        let consumed = match self.pressed.iter().position(|code| *code == key.code) {
+            Some(idx) => {
+                self.pressed.swap_remove(idx);
+                true
+            }
+            None => false,
+        };
+
+        // State of release event may contain the modifier itself
+        if let Some((tap, pressed_at)) = self.tap.take() {
+            if tap.code == key.code && now - pressed_at < Duration::from_millis(timeout.hold) {
+                let hotkey = hotkeys.iter().find_map(|(trigger, hotkey)| match trigger {
+                    HotkeyTrigger::Tap(k) if *k == tap => Some(*hotkey),
+                    _ => None,
+                });
+
+                if let Some(hotkey) = hotkey {
+                    return HotkeyMatch::Fire(hotkey);
+                }
+            }
+        }
+
+        if matches!(self.holding, Some((held, _)) if held.code == key.code) {
+            self.holding = None;
+        }
+
+        // Pending key can't be a long press anymore
+        let released = matches!(self.pending.last(), Some(last) if last.code == key.code);
+        let waiting = hotkeys.iter().any(|(trigger, _)| match trigger {
+            HotkeyTrigger::Sequence(keys) => self.pending_starts(keys),
+            HotkeyTrigger::Double(k) => self.pending_starts(&[*k, *k]),
+            _ => false,
+        });
+
+        if released && !waiting {
+            self.pending_at = None;
+            HotkeyMatch::Replay(std::mem::take(&mut self.pending))
+        } else if consumed {
+            HotkeyMatch::Pending
+        } else {
+            HotkeyMatch::None
+        }
+    }
+
+    fn check(
+        &self,
+        trigger: &HotkeyTrigger,
+        timeout: &HotkeyTimeoutConfig,
+        key: Key,
+        now: Instant,
+    ) -> TriggerState {
+        match trigger {
+            HotkeyTrigger::Key(k) => {
+                if self.pending.is_empty() && *k == key {
+                    TriggerState::Full
+                } else {
+                    TriggerState::Mismatch
+                }
+            }
+            HotkeyTrigger::Sequence(keys) => self.check_sequence(keys, timeout.sequence, key, now),
+            HotkeyTrigger::Double(k) => {
+                self.check_sequence(&[*k, *k], timeout.double_tap, key, now)
+            }
+            HotkeyTrigger::Tap(_) => TriggerState::Mismatch,
+            HotkeyTrigger::Hold(k) => {
+                if *k != key || self.pending.iter().any(|p| p.without_locks() != *k) {
+                    return TriggerState::Mismatch;
+                }
+
+                let hold = Duration::from_millis(timeout.hold);
+
+                match self.pending_at {
+                    None => TriggerState::Prefix,
+                    // Key is released and pressed again
+                    Some((_, last)) if now - last >= hold => TriggerState::Mismatch,
+                    Some((first, _)) if now - first >= hold => TriggerState::Full,
+                    Some(_) => TriggerState::Prefix,
+                }
+            }
+        }
+    }
+
+    fn check_sequence(&self, keys: &[Key], timeout: u64, key: Key, now: Instant) -> TriggerState {
+        let len = self.pending.len();
+
+        if keys.len() <= len || !self.pending_starts(keys) || keys[len] != key {
+            return TriggerState::Mismatch;
+        }
+
+        match self.pending_at {
+            Some((_, last)) if now - last > Duration::from_millis(timeout) => {
+                TriggerState::Mismatch
+            }
+            _ if keys.len() == len + 1 => TriggerState::Full,
+            _ => TriggerState::Prefix,
+        }
+    }
+
+    /// Pending keys are the beginning of `keys`
+    fn pending_starts(&self, keys: &[Key]) -> bool {
+        keys.len() >= self.pending.len()
+            && self
+                .pending
+                .iter()
+                .zip(keys)
+                .all(|(pending, key)| pending.without_locks() == *key)
+    }
+}
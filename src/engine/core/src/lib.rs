@@ -1,28 +1,67 @@
+mod app;
+mod category;
+mod clock;
 mod config;
+mod convert;
+mod hotkey;
 mod os;
+mod remap;
+mod snapshot;
+#[cfg(unix)]
+mod watcher;
 
 #[cfg(unix)]
 pub use config::{load_engine_config_from_config_dir, load_other_configs_from_config_dir};
 pub use config::{
-    Config, DaemonConfig, DaemonModule, EngineConfig, EnumSet, Hotkey, IconColor, IndicatorConfig,
-    InputCategory, InputMode, LevelFilter, LogConfig, RawConfig,
+    AppCategoryConfig, AutoConvertAction, AutoConvertConfig, CommitNormalization, Config,
+    DaemonConfig, DaemonModule, EngineConfig, EnumSet, HanjaConfig, Hotkey, HotkeyBehavior,
+    HotkeyResult, HotkeyTimeoutConfig, HotkeyTrigger, IconColor, IndicatorConfig, InputCategory,
+    InputMode, KeyRemap, LayoutName, LevelFilter, LogConfig, RawConfig, RemapConfig,
+};
+pub use kime_engine_backend::{
+    InputEngineBackend, InputResult, Key, KeyCode, KeyMap, ModifierState, Preedit,
+    PreeditAttribute, PreeditSegment,
 };
-pub use kime_engine_backend::{InputResult, Key, KeyCode, KeyMap, ModifierState};
+pub use snapshot::EngineSnapshot;
+#[cfg(unix)]
+pub use watcher::ConfigWatcher;
 
-use config::{HotkeyBehavior, HotkeyResult};
+use clock::EventClock;
+use convert::{Suggestion, WordHistory};
+use hotkey::{HotkeyMatch, HotkeyMatcher};
 use os::{DefaultOsContext, OsContext};
+use remap::Remapper;
 
-use kime_engine_backend::{InputEngineBackend, InputEngineMode, InputEngineModeResult};
+use kime_engine_backend::{InputEngineMode, InputEngineModeResult};
 use kime_engine_backend_emoji::EmojiMode;
-use kime_engine_backend_hangul::HangulEngine;
 use kime_engine_backend_hanja::HanjaMode;
-use kime_engine_backend_latin::LatinEngine;
 use kime_engine_backend_math::MathMode;
+use std::time::Instant;
 
 pub struct InputEngine {
     engine_impl: EngineImpl,
     commit_buf: String,
     preedit_buf: String,
+    preedit: Preedit,
+    /// Text before cursor
+    surrounding: Option<String>,
+    /// Selected text of client
+    selection: Option<String>,
+    /// Client supplies surrounding text so frontend can delete it
+    surrounding_supported: bool,
+    delete_surrounding: usize,
+    /// Pending keys which don't type a character, client never got them
+    bypass_keys: Vec<Key>,
+    /// Count of chars before cursor which are replaced by hanja when it's committed
+    hanja_delete: usize,
+    history: WordHistory,
+    suggestion: Option<Suggestion>,
+    hotkey_matcher: HotkeyMatcher,
+    remapper: Remapper,
+    clock: EventClock,
+    /// Normalized id of focused application
+    app_id: Option<String>,
+    last_categories: app::LastCategories,
     os_ctx: DefaultOsContext,
 }
 
@@ -38,35 +77,448 @@ impl InputEngine {
             engine_impl: EngineImpl::new(config),
             commit_buf: String::with_capacity(16),
             preedit_buf: String::with_capacity(16),
+            preedit: Preedit::default(),
+            surrounding: None,
+            selection: None,
+            surrounding_supported: false,
+            delete_surrounding: 0,
+            bypass_keys: Vec::new(),
+            hanja_delete: 0,
+            history: WordHistory::default(),
+            suggestion: None,
+            hotkey_matcher: HotkeyMatcher::default(),
+            remapper: Remapper::default(),
+            clock: EventClock::default(),
+            app_id: None,
+            last_categories: config.last_categories.clone(),
             os_ctx: DefaultOsContext::default(),
         }
     }
 
-    pub fn set_input_category(&mut self, category: InputCategory) {
+    /// Change input category
+    ///
+    /// # Return
+    ///
+    /// `false` when there is no backend for category
+    pub fn set_input_category(&mut self, category: InputCategory) -> bool {
+        if !self.engine_impl.has_backend(category) {
+            return false;
+        }
+
         // Reset previous engine
         self.engine_impl.clear_preedit(&mut self.commit_buf);
         self.engine_impl.mode = None;
         self.engine_impl.category = category;
+        self.history.clear();
+        self.suggestion = None;
+        self.remember_category();
+        true
+    }
+
+    /// Set application which has focus e.g. WM_CLASS, input category is changed by `app_category` config
+    pub fn set_app_id(&mut self, app_id: &str, config: &Config) -> InputResult {
+        let app_id = app::normalize_app_id(app_id);
+        let mut ret = InputResult::empty();
+
+        // Category of previous application is already remembered
+        self.app_id = None;
+
+        if let Some(category) = app::start_category(config, &self.last_categories, &app_id) {
+            if self.category() != category && self.set_input_category(category) {
+                ret |= InputResult::LANGUAGE_CHANGED;
+            }
+        }
+
+        self.app_id = Some(app_id);
+        self.remember_category();
+
+        ret | self.current_result()
+    }
+
+    fn remember_category(&self) {
+        if let Some(app_id) = self.app_id.as_deref() {
+            self.last_categories.remember(app_id, self.category());
+        }
     }
 
     pub fn set_input_mode(&mut self, mode: InputMode) -> bool {
-        self.engine_impl
-            .set_mode(mode, &mut self.preedit_buf, &mut self.commit_buf)
+        self.history.clear();
+        self.suggestion = None;
+
+        // Alternatives of preedit replace only preedit
+        let hanja_delete = if mode == InputMode::Hanja && self.engine_impl.alternatives().is_empty()
+        {
+            self.hanja_key()
+        } else {
+            0
+        };
+
+        if self
+            .engine_impl
+            .set_mode(mode, &self.preedit_buf, &mut self.commit_buf)
+        {
+            if mode == InputMode::Hanja {
+                self.selection = None;
+                self.hanja_delete = hanja_delete;
+            }
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Put text which is converted into hanja to `preedit_buf`
+    ///
+    /// It's selected text or preedit with hangul word before cursor,
+    /// return count of chars before cursor which are part of it
+    ///
+    /// Choseong before cursor is used with choseong preedit as abbreviation,
+    /// otherwise single jamo preedit is used alone for symbol table
+    ///
+    /// Choseong except the last one are already committed so abbreviation needs surrounding text,
+    /// client without it only gets symbol table of the last choseong
+    fn hanja_key(&mut self) -> usize {
+        self.preedit_buf.clear();
+
+        if let Some(selection) = self.selection.as_deref() {
+            self.preedit_buf.push_str(selection);
+            return 0;
+        }
+
+        self.engine_impl.preedit_str(&mut self.preedit_buf);
+
+        let surrounding = self.surrounding.as_deref().unwrap_or("");
+        let choseong = convert::last_choseong_word(surrounding);
+
+        let word = if !choseong.is_empty()
+            && self
+                .preedit_buf
+                .chars()
+                .all(kime_engine_dict::choseong::is_choseong)
+        {
+            choseong
+        } else if convert::is_single_jamo(&self.preedit_buf) {
+            ""
+        } else {
+            convert::last_hangul_word(surrounding)
+        };
+
+        self.preedit_buf.insert_str(0, word);
+        word.chars().count()
+    }
+
+    /// Retype last word with keys of other category between Latin and Hangul
+    ///
+    /// Keys are taken from typed history or surrounding text
+    fn convert_word(&mut self, config: &Config) -> bool {
+        let target = if self.category() == InputCategory::HANGUL {
+            InputCategory::LATIN
+        } else {
+            InputCategory::HANGUL
+        };
+
+        if self.engine_impl.mode.is_some() || !self.engine_impl.has_backend(target) {
+            return false;
+        }
+
+        let (keys, delete_len) = if !self.history.is_empty() {
+            if !self.can_delete_surrounding(self.history.committed()) {
+                return false;
+            }
+            self.history.take()
+        } else if self.engine_impl.has_preedit() {
+            return false;
+        } else {
+            let word = match self.surrounding.as_deref() {
+                Some(text) => convert::last_word(text),
+                None => return false,
+            };
+            let keys = match self.category() {
+                InputCategory::HANGUL => config
+                    .hangul_data()
+                    .and_then(|d| d.keys_of(self.hangul_layout(), word)),
+                InputCategory::LATIN => config.latin_data.keys_of(word),
+                _ => None,
+            };
+            match keys {
+                Some(keys) if !keys.is_empty() => {
+                    let len = word.chars().count();
+                    (keys, len)
+                }
+                _ => return false,
+            }
+        };
+
+        self.retype(config, target, keys, delete_len);
+
+        true
+    }
+
+    /// Delete `delete_len` chars before cursor then type `keys` in `target` category
+    fn retype(
+        &mut self,
+        config: &Config,
+        target: InputCategory,
+        keys: Vec<Key>,
+        delete_len: usize,
+    ) {
+        // Preedit is part of the word
+        self.engine_impl.reset();
+        self.engine_impl.category = target;
+
+        if let Some(text) = self.surrounding.as_mut() {
+            for _ in 0..delete_len {
+                text.pop();
+            }
+        }
+        self.delete_surrounding += delete_len;
+
+        for key in keys {
+            let start = self.commit_buf.len();
+            if !self
+                .engine_impl
+                .press_key(config, key, &mut self.commit_buf)
+            {
+                // Client doesn't know this key so commit it directly
+                self.engine_impl.clear_preedit(&mut self.commit_buf);
+                self.commit_buf.extend(config.latin_data.lookup(key));
+            }
+            self.history
+                .record(config, key, true, false, &self.commit_buf[start..]);
+        }
+    }
+
+    /// Retype suggested word in hangul category
+    fn accept_suggestion(&mut self, config: &Config, suggestion: Suggestion) -> bool {
+        if !self.engine_impl.has_backend(InputCategory::HANGUL)
+            || !self.can_delete_surrounding(suggestion.committed)
+        {
+            return false;
+        }
+
+        self.retype(
+            config,
+            InputCategory::HANGUL,
+            suggestion.keys,
+            suggestion.committed,
+        );
+        self.engine_impl.clear_preedit(&mut self.commit_buf);
+        self.commit_buf.push(suggestion.boundary);
+        self.history.clear();
+
+        true
+    }
+
+    /// Check word which is ended by `key` is hangul word typed in latin category
+    fn detect_auto_convert(
+        &self,
+        key: Key,
+        config: &Config,
+    ) -> Option<(AutoConvertAction, char, String)> {
+        let auto_convert = config.auto_convert.as_ref()?;
+
+        if self.category() != InputCategory::LATIN
+            || self.engine_impl.mode.is_some()
+            || key
+                .state
+                .intersects(!(ModifierState::SHIFT | ModifierState::LOCKS))
+        {
+            return None;
+        }
+
+        let boundary = convert::boundary_char(config, key)?;
+        let text = convert::detect_mistyped(
+            config,
+            auto_convert,
+            self.hangul_layout(),
+            self.history.keys(),
+        )?;
+
+        Some((auto_convert.action, boundary, text))
+    }
+
+    /// Apply reloaded config
+    ///
+    /// Preedit will be committed when config of its category has changed e.g. hangul layout or addons
+    pub fn update_config(&mut self, old: &Config, new: &Config) -> InputResult {
+        let mut backends = Vec::with_capacity(self.engine_impl.backends.len());
+
+        for entry in new.categories.iter() {
+            let prev = self
+                .engine_impl
+                .backends
+                .iter()
+                .position(|(category, _)| *category == entry.category)
+                .map(|idx| self.engine_impl.backends.swap_remove(idx).1);
+
+            let backend = match (prev, old.categories.get(entry.category)) {
+                (Some(prev), Some(old_entry)) if old_entry.same_data(entry) => prev,
+                (Some(mut prev), _) => {
+                    prev.clear_preedit(&mut self.commit_buf);
+                    entry.new_backend()
+                }
+                (None, _) => entry.new_backend(),
+            };
+
+            backends.push((entry.category, backend));
+        }
+
+        // Removed categories
+        for (_, mut backend) in self.engine_impl.backends.drain(..) {
+            backend.clear_preedit(&mut self.commit_buf);
+        }
+
+        self.engine_impl.backends = backends;
+        self.engine_impl.commit_normalization = new.commit_normalization;
+
+        if !self.engine_impl.has_backend(self.engine_impl.category) {
+            self.engine_impl.mode = None;
+            self.engine_impl.category = default_category(new);
+        }
+
+        // Engines which are updated with new config share it
+        self.last_categories = new.last_categories.clone();
+        self.remember_category();
+
+        self.current_result()
+    }
+
+    /// Save current category, mode and preedit
+    pub fn snapshot(&self) -> EngineSnapshot {
+        EngineSnapshot {
+            category: self.category(),
+            mode: self.engine_impl.mode,
+            backend: self.engine_impl.save_state(),
+        }
+    }
+
+    /// Restore state which is saved by [`InputEngine::snapshot`]
+    ///
+    /// Current preedit is dropped, category which isn't registered anymore and hanja mode can't be restored
+    pub fn restore(&mut self, snapshot: &EngineSnapshot) -> InputResult {
+        self.history.clear();
+        self.suggestion = None;
+        // Backend doesn't have preedit while mode is enabled
+        self.engine_impl.reset();
+        self.engine_impl.mode = None;
+
+        if self.engine_impl.has_backend(snapshot.category) {
+            self.engine_impl.category = snapshot.category;
+
+            if let Some(state) = snapshot.backend.as_deref() {
+                self.engine_impl.restore_state(state);
+            }
+        }
+
+        match snapshot.mode {
+            Some(mode @ InputMode::Math) | Some(mode @ InputMode::Emoji) => {
+                self.engine_impl.mode = Some(mode);
+            }
+            _ => {}
+        }
+
+        self.remember_category();
+        self.current_result()
+    }
+
+    /// Set text around cursor, `cursor` is byte offset of `text`
+    ///
+    /// It become invalid when commit string is cleared so frontend should set it again,
+    /// frontend which sets it must handle [`InputResult::DELETE_SURROUNDING`]
+    pub fn set_surrounding_text(&mut self, text: &str, cursor: usize) {
+        self.surrounding = text.get(..cursor).map(Into::into);
+        self.surrounding_supported = true;
+    }
+
+    /// Client doesn't supply surrounding text, text before cursor won't be deleted
+    #[inline]
+    pub fn clear_surrounding_text(&mut self) {
+        self.surrounding = None;
+        self.selection = None;
+        self.surrounding_supported = false;
+    }
+
+    /// `len` chars before cursor can be deleted with [`InputResult::DELETE_SURROUNDING`]
+    fn can_delete_surrounding(&self, len: usize) -> bool {
+        len == 0 || self.surrounding_supported
+    }
+
+    /// Set selected text of client, empty text means there is no selection
+    ///
+    /// Hanja mode converts it instead of preedit, it become invalid when commit string is cleared
+    pub fn set_selection_text(&mut self, text: &str) {
+        self.selection = if text.is_empty() {
+            None
+        } else {
+            Some(text.into())
+        };
+    }
+
+    /// Count of chars before cursor which should be deleted
+    ///
+    /// Only valid when [`InputResult::DELETE_SURROUNDING`] is set
+    #[inline]
+    pub fn delete_surrounding_len(&self) -> usize {
+        self.delete_surrounding
+    }
+
+    /// Keys which should be sent to client before the current key, each key is pressed and released
+    ///
+    /// Only valid when [`InputResult::BYPASS_KEYS`] is set
+    #[inline]
+    pub fn bypass_keys(&self) -> &[Key] {
+        &self.bypass_keys
+    }
+
+    /// Reopen committed text so backspace can edit it
+    fn reopen_surrounding(&mut self) {
+        if self.engine_impl.mode.is_some() || self.engine_impl.has_preedit() {
+            return;
+        }
+
+        if let Some(text) = self.surrounding.as_mut() {
+            let len = self.engine_impl.reopen_surrounding(text);
+
+            if len != 0 {
+                for _ in 0..len {
+                    text.pop();
+                }
+
+                self.delete_surrounding += len;
+                self.history.clear();
+            }
+        }
     }
 
     pub fn category(&self) -> InputCategory {
         self.engine_impl.category
     }
 
+    /// Name of active layout of current category e.g. `dubeolsik`
+    ///
+    /// `None` when backend of current category doesn't have layouts
+    pub fn layout(&self) -> Option<&str> {
+        self.engine_impl.layout(self.category())
+    }
+
+    /// Layout which is used to convert word between `Latin` and `Hangul`
+    fn hangul_layout(&self) -> &str {
+        self.engine_impl
+            .layout(InputCategory::HANGUL)
+            .unwrap_or_default()
+    }
+
     pub fn update_layout_state(&mut self) -> std::io::Result<()> {
-        self.os_ctx.update_layout_state(self.category())
+        let layout = self.engine_impl.layout(self.engine_impl.category);
+        self.os_ctx
+            .update_layout_state(self.engine_impl.category, layout)
     }
 
     fn try_get_global_input_category_state(&mut self, config: &Config) {
         if config.global_category_state {
             let global = self
                 .os_ctx
-                .read_global_hangul_state()
+                .read_global_category_state()
                 .unwrap_or(self.category());
 
             if self.category() != global {
@@ -75,117 +527,446 @@ impl InputEngine {
         }
     }
 
-    fn try_hotkey<'c>(&self, key: Key, config: &'c Config) -> Option<Hotkey> {
+    fn hotkeys<'c>(&self, config: &'c Config) -> &'c [(HotkeyTrigger, Hotkey)] {
         if let Some(mode) = self.engine_impl.mode {
-            config.mode_hotkeys[mode]
-                .iter()
-                .find_map(|(k, v)| if *k == key { Some(*v) } else { None })
+            &config.mode_hotkeys[mode]
         } else {
-            config.category_hotkeys[self.engine_impl.category]
-                .iter()
-                .find_map(|(k, v)| if *k == key { Some(*v) } else { None })
+            config.category_hotkeys(self.engine_impl.category)
         }
     }
 
+    fn try_hotkey(&mut self, key: Key, config: &Config, now: Instant) -> HotkeyMatch {
+        let hotkeys = self.hotkeys(config);
+        self.hotkey_matcher
+            .press(hotkeys, &config.hotkey_timeout, key, now)
+    }
+
     pub fn press_key(&mut self, key: Key, config: &Config) -> InputResult {
+        self.press_key_at(key, config, Instant::now())
+    }
+
+    /// Press key which is pressed at `time` e.g. timestamp of key event
+    pub fn press_key_at(&mut self, key: Key, config: &Config, time: Instant) -> InputResult {
         self.try_get_global_input_category_state(config);
 
+        let key = match self.remapper.press(config.remap(self.category()), key) {
+            Some(key) => key,
+            // Key acts as modifier
+            None => return InputResult::CONSUMED | self.current_result(),
+        };
+
         let mut ret = InputResult::empty();
 
-        if let Some(hotkey) = self.try_hotkey(key, config) {
-            let mut processed = false;
-            match hotkey.behavior() {
-                HotkeyBehavior::Switch(category) => {
-                    if self.category() != category || self.engine_impl.mode.is_some() {
-                        self.set_input_category(category);
-                        ret |= InputResult::LANGUAGE_CHANGED;
-                        processed = true;
+        let hotkey = loop {
+            match self.try_hotkey(key, config, time) {
+                HotkeyMatch::Replay(keys) => {
+                    for key in keys {
+                        ret |= self.replay_key(key, config, time);
                     }
                 }
-                HotkeyBehavior::Toggle(left, right) => {
-                    let change = if self.category() == left {
-                        right
-                    } else if self.category() == right {
-                        left
-                    } else {
-                        right
-                    };
+                hotkey => break hotkey,
+            }
+        };
+
+        match hotkey {
+            HotkeyMatch::Fire(hotkey) => {
+                ret |= self.process_hotkey(hotkey, config);
+            }
+            HotkeyMatch::Pending => {
+                ret |= InputResult::CONSUMED;
+            }
+            HotkeyMatch::None | HotkeyMatch::Replay(_) => {
+                ret |= self.process_key(key, config, time);
+            }
+        }
+
+        if ret.contains(InputResult::LANGUAGE_CHANGED) {
+            self.remember_category();
+        }
+
+        ret |= self.current_result();
+
+        ret
+    }
+
+    fn process_hotkey(&mut self, hotkey: Hotkey, config: &Config) -> InputResult {
+        let mut ret = InputResult::empty();
+        let suggestion = self.suggestion.take();
+
+        let mut processed = false;
+        if !matches!(hotkey.behavior(), HotkeyBehavior::Convert) {
+            self.history.clear();
+        }
+        match hotkey.behavior() {
+            HotkeyBehavior::Switch(category) => {
+                if (self.category() != category || self.engine_impl.mode.is_some())
+                    && self.set_input_category(category)
+                {
+                    ret |= InputResult::LANGUAGE_CHANGED;
+                    processed = true;
+                }
+            }
+            HotkeyBehavior::Toggle(left, right) => {
+                let change = if self.category() == left {
+                    right
+                } else if self.category() == right {
+                    left
+                } else {
+                    right
+                };
 
-                    self.set_input_category(change);
+                if self.set_input_category(change) {
                     ret |= InputResult::LANGUAGE_CHANGED;
                     processed = true;
                 }
-                HotkeyBehavior::Mode(mode) => {
-                    processed = self.set_input_mode(mode);
+            }
+            HotkeyBehavior::Mode(mode) => {
+                processed = self.set_input_mode(mode);
+            }
+            HotkeyBehavior::Convert => {
+                let converted = match suggestion {
+                    Some(suggestion) => self.accept_suggestion(config, suggestion),
+                    None => self.convert_word(config),
+                };
+                if converted {
+                    ret |= InputResult::LANGUAGE_CHANGED;
+                    processed = true;
                 }
-                HotkeyBehavior::Commit => {
-                    if self.engine_impl.has_preedit() {
-                        self.engine_impl.clear_preedit(&mut self.commit_buf);
-                        processed = true;
-                    }
+            }
+            HotkeyBehavior::Commit => {
+                if self.engine_impl.has_preedit() {
+                    self.engine_impl.clear_preedit(&mut self.commit_buf);
+                    processed = true;
                 }
-                HotkeyBehavior::Ignore => {
+            }
+            HotkeyBehavior::CycleLayout => {
+                if self
+                    .engine_impl
+                    .select_layout(config, None, &mut self.commit_buf)
+                {
+                    ret |= InputResult::LANGUAGE_CHANGED;
                     processed = true;
                 }
             }
-
-            match (hotkey.result(), processed) {
-                (HotkeyResult::Bypass, _) | (HotkeyResult::ConsumeIfProcessed, false) => {}
-                (HotkeyResult::Consume, _) | (HotkeyResult::ConsumeIfProcessed, true) => {
-                    ret |= InputResult::CONSUMED;
+            HotkeyBehavior::Layout(name) => {
+                if self
+                    .engine_impl
+                    .select_layout(config, Some(name.name()), &mut self.commit_buf)
+                {
+                    ret |= InputResult::LANGUAGE_CHANGED;
+                    processed = true;
                 }
             }
-        } else if self
+            HotkeyBehavior::Ignore => {
+                processed = true;
+            }
+        }
+
+        match (hotkey.result(), processed) {
+            (HotkeyResult::Bypass, _) | (HotkeyResult::ConsumeIfProcessed, false) => {}
+            (HotkeyResult::Consume, _) | (HotkeyResult::ConsumeIfProcessed, true) => {
+                ret |= InputResult::CONSUMED;
+            }
+        }
+
+        ret
+    }
+
+    /// Send key to backend
+    fn process_key(&mut self, key: Key, config: &Config, time: Instant) -> InputResult {
+        let mut ret = InputResult::empty();
+        let suggestion = self.suggestion.take();
+
+        if key.code == KeyCode::Backspace {
+            self.reopen_surrounding();
+        }
+
+        let auto_convert = self.detect_auto_convert(key, config);
+        let mut suggest = None;
+
+        match auto_convert {
+            Some((AutoConvertAction::Convert, _, _)) => {
+                let converted = self.convert_word(config);
+                ret.set(InputResult::LANGUAGE_CHANGED, converted);
+            }
+            // Suggested word can't be replaced when client can't delete it
+            Some((AutoConvertAction::Suggest, boundary, text))
+                if self.can_delete_surrounding(self.history.committed() + 1) =>
+            {
+                suggest = Some(Suggestion {
+                    keys: self.history.keys().to_vec(),
+                    committed: self.history.committed() + 1,
+                    text,
+                    boundary,
+                });
+            }
+            _ => {}
+        }
+
+        let start = self.commit_buf.len();
+        let consumed = self
             .engine_impl
-            .press_key(config, key, &mut self.commit_buf)
-        {
+            .press_key_at(config, key, time, &mut self.commit_buf);
+
+        if consumed {
             ret |= InputResult::CONSUMED;
-        } else if key.code == KeyCode::Shift {
-            // ignore shift key
+        } else if key.code.is_inert() {
+            // ignore keys which don't type or move cursor e.g. shift, locks and media keys
         } else {
             // clear preedit when get unhandled key
-            self.clear_preedit();
+            self.engine_impl.clear_preedit(&mut self.commit_buf);
         }
 
-        ret |= self.current_result();
+        if self.engine_impl.mode.is_some() {
+            self.history.clear();
+        } else if !key.code.is_inert() {
+            self.history.record(
+                config,
+                key,
+                consumed,
+                self.category() == InputCategory::LATIN,
+                &self.commit_buf[start..],
+            );
+        }
+
+        if is_modifier(key.code) || key.code.is_inert() {
+            // Modifier may be a part of `Convert` hotkey
+            self.suggestion = suggestion;
+        } else {
+            self.suggestion = suggest;
+        }
 
         ret
     }
 
-    pub fn press_key_code(
+    /// Process key which was pending as a part of hotkey trigger
+    fn replay_key(&mut self, key: Key, config: &Config, time: Instant) -> InputResult {
+        let ret = self.process_key(key, config, time);
+
+        if !ret.contains(InputResult::CONSUMED) {
+            // Client never got this key so commit it directly
+            if let Some(ch) = config.latin_data.lookup(key) {
+                self.engine_impl.clear_preedit(&mut self.commit_buf);
+                self.commit_buf.push(ch);
+            } else {
+                self.bypass_keys.push(key);
+            }
+        }
+
+        ret & InputResult::LANGUAGE_CHANGED
+    }
+
+    /// Release key, it can fire `Tap` hotkey
+    ///
+    /// Result is empty when release has changed nothing,
+    /// it contains [`InputResult::CONSUMED`] only when press of the key was consumed as a part of hotkey trigger
+    /// since client never got the press
+    pub fn release_key(&mut self, key: Key, config: &Config) -> InputResult {
+        self.release_key_at(key, config, Instant::now())
+    }
+
+    /// Release key which is released at `time` e.g. timestamp of key event
+    pub fn release_key_at(&mut self, key: Key, config: &Config, time: Instant) -> InputResult {
+        let key = match self.remapper.release(config.remap(self.category()), key) {
+            Some(key) => key,
+            None => return InputResult::empty(),
+        };
+        let hotkeys = self.hotkeys(config);
+        let ret = match self
+            .hotkey_matcher
+            .release(hotkeys, &config.hotkey_timeout, key, time)
+        {
+            HotkeyMatch::Fire(hotkey) => {
+                self.process_hotkey(hotkey, config) - InputResult::CONSUMED
+            }
+            // Released key was pending
+            HotkeyMatch::Replay(keys) => {
+                keys.into_iter().fold(InputResult::CONSUMED, |ret, key| {
+                    ret | self.replay_key(key, config, time)
+                })
+            }
+            HotkeyMatch::Pending => InputResult::CONSUMED,
+            HotkeyMatch::None => InputResult::empty(),
+        };
+
+        // Backend always sees release to keep its held keys e.g. moachigi chord
+        let changed = self
+            .engine_impl
+            .release_key(config, key, time, &mut self.commit_buf);
+
+        if ret.is_empty() && !changed {
+            return InputResult::empty();
+        }
+
+        if ret.contains(InputResult::LANGUAGE_CHANGED) {
+            self.remember_category();
+        }
+
+        ret | self.current_result()
+    }
+
+    pub fn release_key_code(
+        &mut self,
+        hardware_code: u16,
+        state: ModifierState,
+        config: &Config,
+    ) -> InputResult {
+        self.release_key_code_at(hardware_code, state, config, 0)
+    }
+
+    /// Release key with millisecond timestamp of key event, `0` means it's unknown
+    pub fn release_key_code_at(
         &mut self,
         hardware_code: u16,
         state: ModifierState,
         config: &Config,
+        timestamp: u32,
     ) -> InputResult {
         match KeyCode::from_hardward_code(hardware_code) {
-            Some(code) => self.press_key(Key::new(code, state), config),
-            None => {
+            Some(code) => {
+                let time = self.clock.instant(timestamp);
+                self.release_key_at(Key::new(code, state), config, time)
+            }
+            None => InputResult::empty(),
+        }
+    }
+
+    /// Press key which is identified by XKB keysym instead of hardware code
+    ///
+    /// Keysym is mapped to the key which types same symbol in US-QWERTY
+    /// so layouts follow keyboard layout of system e.g. AZERTY
+    pub fn press_keysym(
+        &mut self,
+        keysym: u32,
+        state: ModifierState,
+        config: &Config,
+    ) -> InputResult {
+        self.press_keysym_at(keysym, state, config, 0)
+    }
+
+    /// Press key by keysym with millisecond timestamp of key event, `0` means it's unknown
+    pub fn press_keysym_at(
+        &mut self,
+        keysym: u32,
+        state: ModifierState,
+        config: &Config,
+        timestamp: u32,
+    ) -> InputResult {
+        match Key::from_keysym(keysym, state) {
+            Some(key) => {
+                let time = self.clock.instant(timestamp);
+                self.press_key_at(key, config, time)
+            }
+            // Client types this character so preedit comes before it
+            None if Key::keysym_types_char(keysym) => {
                 self.clear_preedit();
                 self.current_result()
             }
+            // Pass through inert key without touching preedit
+            None => self.current_result(),
+        }
+    }
+
+    pub fn release_keysym(
+        &mut self,
+        keysym: u32,
+        state: ModifierState,
+        config: &Config,
+    ) -> InputResult {
+        self.release_keysym_at(keysym, state, config, 0)
+    }
+
+    /// Release key by keysym with millisecond timestamp of key event, `0` means it's unknown
+    pub fn release_keysym_at(
+        &mut self,
+        keysym: u32,
+        state: ModifierState,
+        config: &Config,
+        timestamp: u32,
+    ) -> InputResult {
+        match Key::from_keysym(keysym, state) {
+            Some(key) => {
+                let time = self.clock.instant(timestamp);
+                self.release_key_at(key, config, time)
+            }
+            None => InputResult::empty(),
+        }
+    }
+
+    pub fn press_key_code(
+        &mut self,
+        hardware_code: u16,
+        state: ModifierState,
+        config: &Config,
+    ) -> InputResult {
+        self.press_key_code_at(hardware_code, state, config, 0)
+    }
+
+    /// Press key with millisecond timestamp of key event e.g. time of `wl_keyboard.key`, `0` means it's unknown
+    pub fn press_key_code_at(
+        &mut self,
+        hardware_code: u16,
+        state: ModifierState,
+        config: &Config,
+        timestamp: u32,
+    ) -> InputResult {
+        match KeyCode::from_hardward_code(hardware_code) {
+            Some(code) => {
+                let time = self.clock.instant(timestamp);
+                self.press_key_at(Key::new(code, state), config, time)
+            }
+            // Pass through unknown key without touching preedit
+            None => self.current_result(),
         }
     }
 
+    /// Clear commit string and pending surrounding deletion
     #[inline]
     pub fn clear_commit(&mut self) {
+        if !self.commit_buf.is_empty() {
+            // Surrounding text is changed
+            self.surrounding = None;
+            self.selection = None;
+        }
         self.commit_buf.clear();
+        self.delete_surrounding = 0;
+        self.bypass_keys.clear();
     }
 
     #[inline]
     pub fn clear_preedit(&mut self) {
+        self.history.clear();
+        self.suggestion = None;
         self.engine_impl.clear_preedit(&mut self.commit_buf);
     }
 
     #[inline]
     pub fn remove_preedit(&mut self) {
+        self.suggestion = None;
         self.engine_impl.reset();
     }
 
+    #[inline]
     pub fn preedit_str(&mut self) -> &str {
-        self.preedit_buf.clear();
-        self.engine_impl.preedit_str(&mut self.preedit_buf);
-        &self.preedit_buf
+        self.preedit().text()
+    }
+
+    /// Get preedit string with segments and cursor
+    pub fn preedit(&mut self) -> &Preedit {
+        self.preedit.clear();
+        self.engine_impl.preedit(&mut self.preedit);
+        if let Some(suggestion) = self.suggestion.as_ref() {
+            self.preedit.set_cursor();
+            self.preedit
+                .push_str(&suggestion.text, PreeditAttribute::CandidateHint);
+        }
+        &self.preedit
+    }
+
+    /// Preedit which is made by last call of [`InputEngine::preedit`] or [`InputEngine::preedit_str`]
+    #[inline]
+    pub fn last_preedit(&self) -> &Preedit {
+        &self.preedit
     }
 
     #[inline]
@@ -195,7 +976,11 @@ impl InputEngine {
 
     #[inline]
     pub fn reset(&mut self) {
+        self.history.clear();
+        self.hotkey_matcher.clear();
+        self.remapper.clear();
         self.clear_commit();
+        self.clear_surrounding_text();
         self.remove_preedit();
     }
 
@@ -206,57 +991,101 @@ impl InputEngine {
 
     #[inline]
     pub fn end_ready(&mut self) -> InputResult {
+        let start = self.commit_buf.len();
         self.engine_impl.end_ready(&mut self.commit_buf);
+
+        if self.engine_impl.mode.is_none() {
+            // Hangul word before cursor is replaced by converted text
+            if self.commit_buf.len() > start && self.can_delete_surrounding(self.hanja_delete) {
+                if let Some(text) = self.surrounding.as_mut() {
+                    for _ in 0..self.hanja_delete {
+                        text.pop();
+                    }
+                }
+                self.delete_surrounding += self.hanja_delete;
+            }
+            self.hanja_delete = 0;
+        }
+
         self.current_result()
     }
 
     fn current_result(&mut self) -> InputResult {
         let mut ret = InputResult::empty();
-        if self.engine_impl.has_preedit() {
+        if self.engine_impl.has_preedit() || self.suggestion.is_some() {
             ret |= InputResult::HAS_PREEDIT;
         }
         if !self.commit_buf.is_empty() {
             ret |= InputResult::HAS_COMMIT;
         }
-        if !self.engine_impl.check_ready() {
+        if self.delete_surrounding != 0 {
+            ret |= InputResult::DELETE_SURROUNDING;
+        }
+        if !self.bypass_keys.is_empty() {
+            ret |= InputResult::BYPASS_KEYS;
+        }
+        // Hanja mode waits candidate window until it's exited, it can show next segment after `end_ready`
+        if self.engine_impl.mode == Some(InputMode::Hanja) || !self.engine_impl.check_ready() {
             ret |= InputResult::NOT_READY;
         }
         ret
     }
 }
 
+/// Modifier keys are pressed before the key of hotkey
+fn is_modifier(code: KeyCode) -> bool {
+    matches!(
+        code,
+        KeyCode::Shift | KeyCode::ControlL | KeyCode::ControlR | KeyCode::AltL | KeyCode::AltR
+    )
+}
+
+/// Use `default_category` only if it has backend
+fn default_category(config: &Config) -> InputCategory {
+    if config.has_category(config.default_category) {
+        config.default_category
+    } else {
+        config
+            .categories()
+            .next()
+            .unwrap_or(config.default_category)
+    }
+}
+
 struct EngineImpl {
     category: InputCategory,
     mode: Option<InputMode>,
-    latin_engine: LatinEngine,
-    hangul_engine: HangulEngine,
+    backends: Vec<(InputCategory, Box<dyn category::CategoryBackend>)>,
     hanja_mode: HanjaMode,
     math_mode: MathMode,
     emoji_mode: EmojiMode,
+    /// Every commit of backends and modes is normalized here
+    commit_normalization: CommitNormalization,
 }
 
 impl EngineImpl {
     pub fn new(config: &Config) -> Self {
         Self {
-            category: config.default_category,
+            category: default_category(config),
             mode: None,
-            latin_engine: LatinEngine::new(config.preferred_direct),
-            hangul_engine: HangulEngine::new(
-                config.hangul_data.word_commit(),
-                config.hangul_data.preedit_johab(),
-            ),
-            hanja_mode: HanjaMode::new(),
+            backends: config
+                .categories
+                .iter()
+                .map(|entry| (entry.category, entry.new_backend()))
+                .collect(),
+            hanja_mode: HanjaMode::new(config.hanja.clone()),
             math_mode: MathMode::new(),
             emoji_mode: EmojiMode::new(),
+            commit_normalization: config.commit_normalization,
         }
     }
 
-    pub fn set_mode(
-        &mut self,
-        mode: InputMode,
-        preedit_buf: &mut String,
-        commit_buf: &mut String,
-    ) -> bool {
+    pub fn has_backend(&self, category: InputCategory) -> bool {
+        self.backends.iter().any(|(c, _)| *c == category)
+    }
+
+    /// `key` is text which is converted by hanja mode
+    pub fn set_mode(&mut self, mode: InputMode, key: &str, commit_buf: &mut String) -> bool {
         match mode {
             InputMode::Math | InputMode::Emoji => {
                 self.clear_preedit(commit_buf);
@@ -264,11 +1093,20 @@ impl EngineImpl {
                 true
             }
             InputMode::Hanja => match self.category {
-                InputCategory::Hangul => {
-                    preedit_buf.clear();
-                    self.hangul_engine.preedit_str(preedit_buf);
-                    if self.hanja_mode.set_key(preedit_buf) {
-                        self.hangul_engine.reset();
+                InputCategory::HANGUL => {
+                    let alternatives = self
+                        .backends
+                        .iter()
+                        .find(|(c, _)| *c == self.category)
+                        .map_or(&[][..], |(_, backend)| backend.alternatives());
+                    let shown = if alternatives.is_empty() {
+                        self.hanja_mode.set_key(key)
+                    } else {
+                        self.hanja_mode.set_alternatives(alternatives)
+                    };
+
+                    if shown {
+                        self.reset();
                         self.mode = Some(InputMode::Hanja);
                         true
                     } else {
@@ -329,18 +1167,23 @@ macro_rules! do_mode {
 }
 
 macro_rules! do_engine {
-    ($self:expr, $func:ident($($arg:expr,)*)) => {
-        match $self.category {
-            InputCategory::Hangul => $self.hangul_engine.$func($($arg,)*),
-            InputCategory::Latin => $self.latin_engine.$func($($arg,)*),
+    ($self:expr, $iter:ident, $func:ident($($arg:expr,)*)) => {{
+        let category = $self.category;
+        match $self.backends.$iter().find(|(c, _)| *c == category) {
+            Some((_, backend)) => backend.$func($($arg,)*),
+            None => Default::default(),
         }
-    };
+    }};
 }
 
 macro_rules! connect {
-    (@$key:ident $self:expr, $func:ident($($arg:expr$(,)?)*)) => {{
-        do_mode!(@$key $self, $func($($arg,)*));
-        do_engine!($self, $func($($arg,)*))
+    (@ret $self:expr, $func:ident($($arg:expr$(,)?)*)) => {{
+        do_mode!(@ret $self, $func($($arg,)*));
+        do_engine!($self, iter_mut, $func($($arg,)*))
+    }};
+    (@direct $self:expr, $func:ident($($arg:expr$(,)?)*)) => {{
+        do_mode!(@direct $self, $func($($arg,)*));
+        do_engine!($self, iter, $func($($arg,)*))
     }};
 }
 
@@ -355,14 +1198,72 @@ impl EngineImpl {
     }
 
     pub fn end_ready(&mut self, commit_buf: &mut String) {
+        let start = commit_buf.len();
+        self.end_mode_ready(commit_buf);
+        self.commit_normalization.normalize_from(commit_buf, start);
+    }
+
+    fn end_mode_ready(&mut self, commit_buf: &mut String) {
         do_mode!(@ret self, end_ready(commit_buf,));
     }
-}
 
-impl InputEngineBackend for EngineImpl {
-    type ConfigData = Config;
+    pub fn reopen_surrounding(&mut self, text: &str) -> usize {
+        do_engine!(self, iter_mut, reopen_surrounding(text,))
+    }
 
-    fn press_key(&mut self, config: &Config, key: Key, commit_buf: &mut String) -> bool {
+    pub fn save_state(&self) -> Option<String> {
+        do_engine!(self, iter, save_state())
+    }
+
+    pub fn restore_state(&mut self, state: &str) -> bool {
+        do_engine!(self, iter_mut, restore_state(state,))
+    }
+
+    /// Name of active layout of `category`
+    pub fn layout(&self, category: InputCategory) -> Option<&str> {
+        self.backends
+            .iter()
+            .find(|(c, _)| *c == category)
+            .and_then(|(_, backend)| backend.layout())
+    }
+
+    /// Alternatives of preedit of current category
+    pub fn alternatives(&self) -> &[String] {
+        self.backends
+            .iter()
+            .find(|(c, _)| *c == self.category)
+            .map_or(&[], |(_, backend)| backend.alternatives())
+    }
+
+    /// Select layout of current category, preedit is committed before layout is changed
+    pub fn select_layout(
+        &mut self,
+        config: &Config,
+        name: Option<&str>,
+        commit_buf: &mut String,
+    ) -> bool {
+        if self.mode.is_some() || self.layout(self.category).is_none() {
+            return false;
+        }
+
+        let entry = match config.categories.get(self.category) {
+            Some(entry) => entry,
+            None => return false,
+        };
+
+        self.clear_preedit(commit_buf);
+        do_engine!(self, iter_mut, select_layout(&*entry.data, name,))
+    }
+}
+
+impl EngineImpl {
+    fn press_key_impl(
+        &mut self,
+        config: &Config,
+        key: Key,
+        time: Instant,
+        commit_buf: &mut String,
+    ) -> bool {
         match self.mode {
             Some(InputMode::Emoji) => {
                 do_mode!(@retarm self, emoji_mode, press_key(&config.latin_data, key, commit_buf,))
@@ -376,21 +1277,70 @@ impl InputEngineBackend for EngineImpl {
             _ => {}
         }
 
-        match self.category {
-            InputCategory::Hangul => {
-                self.hangul_engine
-                    .press_key(&config.hangul_data, key, commit_buf)
-            }
-            InputCategory::Latin => {
-                self.latin_engine
-                    .press_key(&config.latin_data, key, commit_buf)
-            }
+        match config.categories.get(self.category) {
+            Some(entry) => do_engine!(
+                self,
+                iter_mut,
+                press_key(&*entry.data, key, time, commit_buf,)
+            ),
+            None => false,
         }
     }
 
-    fn clear_preedit(&mut self, commit_buf: &mut String) {
+    fn clear_preedit_impl(&mut self, commit_buf: &mut String) {
         connect!(@ret self, clear_preedit(commit_buf));
     }
+}
+
+impl InputEngineBackend for EngineImpl {
+    type ConfigData = Config;
+
+    fn press_key(&mut self, config: &Config, key: Key, commit_buf: &mut String) -> bool {
+        self.press_key_at(config, key, Instant::now(), commit_buf)
+    }
+
+    fn press_key_at(
+        &mut self,
+        config: &Config,
+        key: Key,
+        time: Instant,
+        commit_buf: &mut String,
+    ) -> bool {
+        let start = commit_buf.len();
+        let ret = self.press_key_impl(config, key, time, commit_buf);
+        self.commit_normalization.normalize_from(commit_buf, start);
+        ret
+    }
+
+    fn release_key(
+        &mut self,
+        config: &Config,
+        key: Key,
+        time: Instant,
+        commit_buf: &mut String,
+    ) -> bool {
+        if self.mode.is_some() {
+            return false;
+        }
+
+        let start = commit_buf.len();
+        let ret = match config.categories.get(self.category) {
+            Some(entry) => do_engine!(
+                self,
+                iter_mut,
+                release_key(&*entry.data, key, time, commit_buf,)
+            ),
+            None => false,
+        };
+        self.commit_normalization.normalize_from(commit_buf, start);
+        ret
+    }
+
+    fn clear_preedit(&mut self, commit_buf: &mut String) {
+        let start = commit_buf.len();
+        self.clear_preedit_impl(commit_buf);
+        self.commit_normalization.normalize_from(commit_buf, start);
+    }
 
     fn reset(&mut self) {
         connect!(@ret self, reset());
@@ -403,4 +1353,8 @@ impl InputEngineBackend for EngineImpl {
     fn preedit_str(&self, buf: &mut String) {
         connect!(@direct self, preedit_str(buf));
     }
+
+    fn preedit(&self, preedit: &mut Preedit) {
+        connect!(@direct self, preedit(preedit));
+    }
 }
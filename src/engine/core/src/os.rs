@@ -2,8 +2,13 @@ use crate::InputCategory;
 use std::io;
 
 pub trait OsContext {
-    fn read_global_hangul_state(&mut self) -> io::Result<InputCategory>;
-    fn update_layout_state(&mut self, category: InputCategory) -> io::Result<()>;
+    fn read_global_category_state(&mut self) -> io::Result<InputCategory>;
+    /// `layout` is name of active layout of `category` if it has layouts
+    fn update_layout_state(
+        &mut self,
+        category: InputCategory,
+        layout: Option<&str>,
+    ) -> io::Result<()>;
 }
 
 #[cfg(unix)]
@@ -34,28 +39,33 @@ mod unix {
     }
 
     impl super::OsContext for OsContext {
-        fn read_global_hangul_state(&mut self) -> io::Result<InputCategory> {
-            let mut buf = [0; 1];
+        fn read_global_category_state(&mut self) -> io::Result<InputCategory> {
+            let mut buf = Vec::with_capacity(16);
             let mut client = UnixStream::connect(&self.sock_path)?;
             client.set_read_timeout(Some(Duration::from_secs(2))).ok();
             client.set_write_timeout(Some(Duration::from_secs(2))).ok();
-            client.read_exact(&mut buf)?;
-            match buf[0] {
-                1 => Ok(InputCategory::Hangul),
-                _ => Ok(InputCategory::Latin),
-            }
+            client.read_to_end(&mut buf)?;
+            let name = std::str::from_utf8(&buf)
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+            // Layout follows category after newline
+            let name = name.split('\n').next().unwrap_or_default();
+            InputCategory::find(name)
+                .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidData, "Unknown input category"))
         }
 
-        fn update_layout_state(&mut self, category: InputCategory) -> io::Result<()> {
-            let category = match category {
-                InputCategory::Hangul => 1,
-                InputCategory::Latin => 0,
-            };
-
+        fn update_layout_state(
+            &mut self,
+            category: InputCategory,
+            layout: Option<&str>,
+        ) -> io::Result<()> {
             let mut client = UnixStream::connect(&self.sock_path)?;
             client.set_read_timeout(Some(Duration::from_secs(2))).ok();
             client.set_write_timeout(Some(Duration::from_secs(2))).ok();
-            client.write_all(&[category])
+            let message = match layout {
+                Some(layout) => format!("{}\n{}", category.name(), layout),
+                None => category.name().into(),
+            };
+            client.write_all(message.as_bytes())
         }
     }
 }
@@ -68,11 +78,15 @@ mod fallback {
     pub struct OsContext;
 
     impl super::OsContext for OsContext {
-        fn read_global_hangul_state(&mut self) -> io::Result<InputCategory> {
+        fn read_global_category_state(&mut self) -> io::Result<InputCategory> {
             Err(io::Error::new(io::ErrorKind::Other, "Unsupported platform"))
         }
 
-        fn update_layout_state(&mut self, _category: InputCategory) -> io::Result<()> {
+        fn update_layout_state(
+            &mut self,
+            _category: InputCategory,
+            _layout: Option<&str>,
+        ) -> io::Result<()> {
             Err(io::Error::new(io::ErrorKind::Other, "Unsupported platform"))
         }
     }
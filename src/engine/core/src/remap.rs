@@ -0,0 +1,79 @@
+use crate::{Key, KeyCode, KeyRemap, ModifierState};
+use std::collections::BTreeMap;
+
+/// Rewrite keys with remap of config, it tracks keys which act as modifiers
+#[derive(Default)]
+pub(crate) struct Remapper {
+    /// Pressed keys which act as modifiers
+    held: Vec<(KeyCode, ModifierState)>,
+}
+
+impl Remapper {
+    pub fn clear(&mut self) {
+        self.held.clear();
+    }
+
+    /// `None` means key acts as modifier so it shouldn't be processed
+    pub fn press(&mut self, remap: &BTreeMap<Key, KeyRemap>, key: Key) -> Option<Key> {
+        let key = Key::new(key.code, key.state | self.modifiers());
+
+        match lookup(remap, key) {
+            Some(KeyRemap::Key(key)) => Some(key),
+            Some(KeyRemap::Modifier(modifier)) => {
+                // Key repeat
+                if self.held.iter().all(|(code, _)| *code != key.code) {
+                    self.held.push((key.code, modifier));
+                }
+                None
+            }
+            None => Some(key),
+        }
+    }
+
+    pub fn release(&mut self, remap: &BTreeMap<Key, KeyRemap>, key: Key) -> Option<Key> {
+        if let Some(idx) = self.held.iter().position(|(code, _)| *code == key.code) {
+            self.held.remove(idx);
+            return None;
+        }
+
+        let key = Key::new(key.code, key.state | self.modifiers());
+
+        match lookup(remap, key) {
+            Some(KeyRemap::Key(key)) => Some(key),
+            Some(KeyRemap::Modifier(_)) => None,
+            None => Some(key),
+        }
+    }
+
+    fn modifiers(&self) -> ModifierState {
+        self.held
+            .iter()
+            .fold(ModifierState::empty(), |state, (_, modifier)| {
+                state | *modifier
+            })
+    }
+}
+
+/// Exact key comes first, key without modifiers matches any modifiers and keeps them
+fn lookup(remap: &BTreeMap<Key, KeyRemap>, key: Key) -> Option<KeyRemap> {
+    if remap.is_empty() {
+        return None;
+    }
+
+    let locks = key.state & ModifierState::LOCKS;
+
+    if let Some(target) = remap.get(&key.without_locks()) {
+        return Some(match *target {
+            KeyRemap::Key(target) => KeyRemap::Key(Key::new(target.code, target.state | locks)),
+            modifier => modifier,
+        });
+    }
+
+    match *remap.get(&Key::normal(key.code))? {
+        KeyRemap::Key(target) => Some(KeyRemap::Key(Key::new(
+            target.code,
+            target.state | key.state,
+        ))),
+        modifier => Some(modifier),
+    }
+}
@@ -0,0 +1,21 @@
+use crate::{InputCategory, InputMode};
+use serde::{Deserialize, Serialize};
+
+/// Saved state of [`InputEngine`](crate::InputEngine) so that frontend can bring it back e.g. on refocus
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EngineSnapshot {
+    pub category: InputCategory,
+    pub mode: Option<InputMode>,
+    /// Serialized state of backend of `category`
+    pub backend: Option<String>,
+}
+
+impl EngineSnapshot {
+    pub fn to_yaml(&self) -> String {
+        serde_yaml::to_string(self).unwrap_or_default()
+    }
+
+    pub fn from_yaml(s: &str) -> Option<Self> {
+        serde_yaml::from_str(s).ok()
+    }
+}
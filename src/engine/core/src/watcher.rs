@@ -0,0 +1,120 @@
+use nix::sys::inotify::{AddWatchFlags, InitFlags, Inotify, WatchDescriptor};
+use std::{
+    ffi::OsStr,
+    io,
+    os::unix::io::{AsRawFd, RawFd},
+    path::{Path, PathBuf},
+};
+
+const CONFIG_FILE: &str = "config.yaml";
+const LAYOUT_DIR: &str = "layouts";
+
+/// Watch `config.yaml` and `layouts` directory of kime config directories
+///
+/// Frontends should poll [`ConfigWatcher::fd`] and call [`ConfigWatcher::changed`] when it's readable
+pub struct ConfigWatcher {
+    inotify: Inotify,
+    config_dirs: Vec<(WatchDescriptor, PathBuf)>,
+    layout_dirs: Vec<WatchDescriptor>,
+}
+
+fn watch_flags() -> AddWatchFlags {
+    AddWatchFlags::IN_CLOSE_WRITE
+        | AddWatchFlags::IN_CREATE
+        | AddWatchFlags::IN_DELETE
+        | AddWatchFlags::IN_MOVED_FROM
+        | AddWatchFlags::IN_MOVED_TO
+}
+
+impl ConfigWatcher {
+    pub fn new() -> io::Result<Self> {
+        let dirs = xdg::BaseDirectories::with_prefix("kime")?;
+        let inotify = Inotify::init(InitFlags::IN_NONBLOCK | InitFlags::IN_CLOEXEC)?;
+
+        let mut watcher = Self {
+            inotify,
+            config_dirs: Vec::new(),
+            layout_dirs: Vec::new(),
+        };
+
+        for dir in std::iter::once(dirs.get_config_home()).chain(dirs.get_config_dirs()) {
+            watcher.watch_config_dir(dir);
+        }
+
+        if watcher.config_dirs.is_empty() {
+            Err(io::Error::new(
+                io::ErrorKind::NotFound,
+                "Can't find any config directory",
+            ))
+        } else {
+            Ok(watcher)
+        }
+    }
+
+    fn watch_config_dir(&mut self, dir: PathBuf) {
+        if let Ok(wd) = self.inotify.add_watch(&dir, watch_flags()) {
+            self.watch_layout_dir(&dir.join(LAYOUT_DIR));
+            self.config_dirs.push((wd, dir));
+        }
+    }
+
+    fn watch_layout_dir(&mut self, dir: &Path) {
+        if let Ok(wd) = self.inotify.add_watch(dir, watch_flags()) {
+            if !self.layout_dirs.contains(&wd) {
+                self.layout_dirs.push(wd);
+            }
+        }
+    }
+
+    /// File descriptor which become readable when there are pending events
+    #[inline]
+    pub fn fd(&self) -> RawFd {
+        self.inotify.as_raw_fd()
+    }
+
+    /// Drain pending events
+    ///
+    /// # Return
+    ///
+    /// `true` when config file or layout files has changed
+    pub fn changed(&mut self) -> bool {
+        let mut changed = false;
+
+        while let Ok(events) = self.inotify.read_events() {
+            if events.is_empty() {
+                break;
+            }
+
+            for event in events {
+                let name = match event.name {
+                    Some(ref name) => name.as_os_str(),
+                    None => continue,
+                };
+
+                if self.layout_dirs.contains(&event.wd) {
+                    changed |= Path::new(name).extension() == Some(OsStr::new("yaml"));
+                } else if let Some(dir) =
+                    self.config_dirs
+                        .iter()
+                        .find_map(|(wd, dir)| if *wd == event.wd { Some(dir) } else { None })
+                {
+                    if name == LAYOUT_DIR {
+                        let dir = dir.join(LAYOUT_DIR);
+                        self.watch_layout_dir(&dir);
+                        changed = true;
+                    } else if name == CONFIG_FILE {
+                        changed = true;
+                    }
+                }
+            }
+        }
+
+        changed
+    }
+}
+
+impl Drop for ConfigWatcher {
+    fn drop(&mut self) {
+        nix::unistd::close(self.inotify.as_raw_fd()).ok();
+    }
+}
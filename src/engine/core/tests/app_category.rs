@@ -0,0 +1,95 @@
+use kime_engine_core::{
+    Config, EngineConfig, InputCategory, InputEngine, InputResult, Key, KeyCode::*,
+};
+use pretty_assertions::assert_eq;
+
+fn config(remember: bool) -> Config {
+    let mut engine = EngineConfig::default();
+    engine.app_category.remember = remember;
+    engine
+        .app_category
+        .rules
+        .insert("Alacritty".into(), InputCategory::LATIN);
+    Config::new(engine)
+}
+
+#[test]
+fn rule_always_applied() {
+    let config = config(false);
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    let ret = engine.set_app_id("alacritty", &config);
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert_eq!(engine.category(), InputCategory::LATIN);
+
+    engine.press_key(Key::normal(AltR), &config);
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+
+    engine.set_app_id("Alacritty", &config);
+    assert_eq!(engine.category(), InputCategory::LATIN);
+}
+
+#[test]
+fn no_policy_keep_category() {
+    let config = config(false);
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    let ret = engine.set_app_id("no-policy-app", &config);
+    assert!(!ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+}
+
+#[test]
+fn remember_last_category() {
+    let config = config(true);
+    let mut engine = InputEngine::new(&config);
+
+    engine.set_app_id("remember-editor", &config);
+    engine.press_key(Key::normal(AltR), &config);
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+
+    // Unknown application starts with default category
+    let ret = engine.set_app_id("remember-browser", &config);
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert_eq!(engine.category(), InputCategory::LATIN);
+
+    engine.set_app_id("remember-editor", &config);
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+
+    engine.set_app_id("remember-browser", &config);
+    assert_eq!(engine.category(), InputCategory::LATIN);
+}
+
+#[test]
+fn remember_shared_between_engines() {
+    let config = config(true);
+
+    let mut first = InputEngine::new(&config);
+    first.set_app_id("shared-app", &config);
+    first.set_input_category(InputCategory::HANGUL);
+
+    let mut second = InputEngine::new(&config);
+    second.set_app_id("shared-app", &config);
+    assert_eq!(second.category(), InputCategory::HANGUL);
+}
+
+#[test]
+fn remember_per_config() {
+    let first_config = config(true);
+    let mut first = InputEngine::new(&first_config);
+    first.set_app_id("config-app", &first_config);
+    first.set_input_category(InputCategory::HANGUL);
+
+    let second_config = config(true);
+    let mut second = InputEngine::new(&second_config);
+    second.set_app_id("config-app", &second_config);
+    assert_eq!(second.category(), InputCategory::LATIN);
+
+    // Remembered categories are moved to new config
+    first.update_config(&first_config, &second_config);
+    second.set_app_id("other-app", &second_config);
+    second.set_app_id("config-app", &second_config);
+    assert_eq!(second.category(), InputCategory::HANGUL);
+}
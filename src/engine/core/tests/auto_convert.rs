@@ -0,0 +1,193 @@
+#[macro_use]
+mod shared;
+
+define_layout_test!("dubeolsik", LatinLayout::Qwerty, InputCategory::LATIN);
+
+use kime_engine_core::{
+    Config, HotkeyBehavior, HotkeyResult, InputEngine, InputResult, KeyCode, PreeditAttribute,
+    PreeditSegment,
+};
+
+fn convert_hotkey() -> (Key, Hotkey) {
+    (
+        Key::normal(F2),
+        Hotkey::new(HotkeyBehavior::Convert, HotkeyResult::ConsumeIfProcessed),
+    )
+}
+
+#[test]
+fn convert_hangul_word() {
+    test_input_with_auto_convert(
+        &[
+            (Key::normal(D), "", "PASS"),
+            (Key::normal(K), "", "PASS"),
+            (Key::normal(S), "", "PASS"),
+            (Key::normal(S), "", "PASS"),
+            (Key::normal(U), "", "PASS"),
+            (Key::normal(D), "", "PASS"),
+            (Key::normal(Space), "", "안녕PASS"),
+            (Key::normal(R), "ㄱ", ""),
+        ],
+        AutoConvertAction::Convert,
+        &[],
+    );
+}
+
+#[test]
+fn keep_english_word() {
+    test_input_with_auto_convert(
+        &[
+            (Key::normal(H), "", "PASS"),
+            (Key::normal(E), "", "PASS"),
+            (Key::normal(L), "", "PASS"),
+            (Key::normal(L), "", "PASS"),
+            (Key::normal(O), "", "PASS"),
+            (Key::normal(Space), "", "PASS"),
+            (Key::normal(R), "", "PASS"),
+        ],
+        AutoConvertAction::Convert,
+        &[],
+    );
+}
+
+#[test]
+fn keep_incomplete_syllable() {
+    // `asdf` types `ㅁㄴㅇㄹ`
+    test_input_with_auto_convert(
+        &[
+            (Key::normal(A), "", "PASS"),
+            (Key::normal(S), "", "PASS"),
+            (Key::normal(D), "", "PASS"),
+            (Key::normal(F), "", "PASS"),
+            (Key::normal(Space), "", "PASS"),
+        ],
+        AutoConvertAction::Convert,
+        &[],
+    );
+}
+
+#[test]
+fn keep_short_word() {
+    // `rk` types `가` but it is shorter than `min_keys`
+    test_input_with_auto_convert(
+        &[
+            (Key::normal(R), "", "PASS"),
+            (Key::normal(K), "", "PASS"),
+            (Key::normal(Space), "", "PASS"),
+        ],
+        AutoConvertAction::Convert,
+        &[],
+    );
+}
+
+#[test]
+fn suggest_accept() {
+    test_input_with_auto_convert(
+        &[
+            (Key::normal(D), "", "PASS"),
+            (Key::normal(K), "", "PASS"),
+            (Key::normal(S), "", "PASS"),
+            (Key::normal(S), "", "PASS"),
+            (Key::normal(U), "", "PASS"),
+            (Key::normal(D), "", "PASS"),
+            (Key::normal(Space), "안녕", "PASS"),
+            (Key::normal(F2), "", "안녕 "),
+            (Key::normal(R), "ㄱ", ""),
+        ],
+        AutoConvertAction::Suggest,
+        &[convert_hotkey()],
+    );
+}
+
+#[test]
+fn suggest_dismiss() {
+    test_input_with_auto_convert(
+        &[
+            (Key::normal(D), "", "PASS"),
+            (Key::normal(K), "", "PASS"),
+            (Key::normal(S), "", "PASS"),
+            (Key::normal(S), "", "PASS"),
+            (Key::normal(U), "", "PASS"),
+            (Key::normal(D), "", "PASS"),
+            (Key::normal(Space), "안녕", "PASS"),
+            (Key::normal(A), "", "PASS"),
+            // Convert only new word
+            (Key::normal(F2), "ㅁ", ""),
+        ],
+        AutoConvertAction::Suggest,
+        &[convert_hotkey()],
+    );
+}
+
+#[test]
+fn delete_mistyped_word() {
+    let mut engine_config = default_config();
+    engine_config.auto_convert.enable = true;
+    let (key, hotkey) = convert_hotkey();
+    engine_config.global_hotkeys.insert(key.into(), hotkey);
+    engine_config.auto_convert.action = AutoConvertAction::Suggest;
+    let config = Config::new(engine_config);
+    let mut engine = InputEngine::new(&config);
+    engine.set_surrounding_text("", 0);
+
+    for code in [R, K, T, P, D, Y, Space].iter().copied() {
+        engine.press_key(Key::normal(code), &config);
+        engine.clear_commit();
+    }
+
+    let ret = engine.press_key(Key::normal(KeyCode::F2), &config);
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert!(ret.contains(InputResult::DELETE_SURROUNDING));
+    // `rktpdy` and space
+    assert_eq!(engine.delete_surrounding_len(), 7);
+    assert_eq!(engine.commit_str(), "가세요 ");
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+}
+
+#[test]
+fn suggest_as_candidate_hint() {
+    let mut engine_config = default_config();
+    engine_config.auto_convert.enable = true;
+    engine_config.auto_convert.action = AutoConvertAction::Suggest;
+    let config = Config::new(engine_config);
+    let mut engine = InputEngine::new(&config);
+    engine.set_surrounding_text("", 0);
+
+    for code in [D, K, S, S, U, D, Space].iter().copied() {
+        engine.press_key(Key::normal(code), &config);
+    }
+
+    let preedit = engine.preedit();
+    assert_eq!(preedit.cursor(), 0);
+    assert_eq!(
+        preedit.segments(),
+        &[PreeditSegment {
+            start: 0,
+            end: "안녕".len(),
+            attribute: PreeditAttribute::CandidateHint,
+        }]
+    );
+}
+
+#[test]
+fn surrounding_not_supported() {
+    for action in [AutoConvertAction::Convert, AutoConvertAction::Suggest]
+        .iter()
+        .copied()
+    {
+        let mut engine_config = default_config();
+        engine_config.auto_convert.enable = true;
+        engine_config.auto_convert.action = action;
+        let config = Config::new(engine_config);
+        // Client doesn't supply surrounding text so typed word can't be deleted
+        let mut engine = InputEngine::new(&config);
+
+        for code in [D, K, S, S, U, D, Space].iter().copied() {
+            let ret = engine.press_key(Key::normal(code), &config);
+            assert!(!ret.contains(InputResult::DELETE_SURROUNDING));
+        }
+
+        assert_eq!(engine.preedit_str(), "");
+        assert_eq!(engine.category(), InputCategory::LATIN);
+    }
+}
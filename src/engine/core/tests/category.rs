@@ -0,0 +1,114 @@
+use kime_engine_core::{
+    Config, EngineConfig, Hotkey, HotkeyBehavior, HotkeyResult, InputCategory, InputEngine,
+    InputEngineBackend, InputResult, Key, KeyCode::*,
+};
+use pretty_assertions::assert_eq;
+
+/// Commit `prefix` with pressed key code
+#[derive(Default)]
+struct PrefixEngine {
+    preedit: Option<char>,
+}
+
+impl InputEngineBackend for PrefixEngine {
+    type ConfigData = char;
+
+    fn press_key(&mut self, prefix: &char, key: Key, commit_buf: &mut String) -> bool {
+        if key.code != A {
+            return false;
+        }
+
+        match self.preedit.take() {
+            Some(c) => {
+                commit_buf.push(c);
+                commit_buf.push(*prefix);
+            }
+            None => self.preedit = Some(*prefix),
+        }
+
+        true
+    }
+
+    fn clear_preedit(&mut self, commit_buf: &mut String) {
+        if let Some(c) = self.preedit.take() {
+            commit_buf.push(c);
+        }
+    }
+
+    fn reset(&mut self) {
+        self.preedit = None;
+    }
+
+    fn has_preedit(&self) -> bool {
+        self.preedit.is_some()
+    }
+
+    fn preedit_str(&self, buf: &mut String) {
+        buf.extend(self.preedit);
+    }
+}
+
+fn config_with_kana(prefix: char) -> Config {
+    let kana = InputCategory::new("Kana");
+    let mut engine = EngineConfig::default();
+    engine.categories.push(kana);
+    engine.global_hotkeys.insert(
+        Key::normal(F1).into(),
+        Hotkey::new(HotkeyBehavior::Switch(kana), HotkeyResult::Consume),
+    );
+    let mut config = Config::new(engine);
+    config.register_category(kana, prefix, |_: &char| PrefixEngine::default());
+    config
+}
+
+#[test]
+fn switch_to_registered_category() {
+    let config = config_with_kana('あ');
+    let mut engine = InputEngine::new(&config);
+
+    let ret = engine.press_key(Key::normal(F1), &config);
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert_eq!(engine.category().name(), "Kana");
+
+    engine.press_key(Key::normal(A), &config);
+    assert_eq!(engine.preedit_str(), "あ");
+    engine.press_key(Key::normal(A), &config);
+    assert_eq!(engine.commit_str(), "ああ");
+}
+
+#[test]
+fn switch_to_unregistered_category() {
+    let config = Config::new(EngineConfig::default());
+    let mut engine = InputEngine::new(&config);
+
+    assert!(!engine.set_input_category(InputCategory::new("Kana")));
+    assert_eq!(engine.category(), InputCategory::LATIN);
+}
+
+#[test]
+fn category_data_changed_commit_preedit() {
+    let old = config_with_kana('あ');
+    let new = config_with_kana('ア');
+    let mut engine = InputEngine::new(&old);
+    assert!(engine.set_input_category(InputCategory::new("Kana")));
+
+    engine.press_key(Key::normal(A), &old);
+    let ret = engine.update_config(&old, &new);
+    assert!(ret.contains(InputResult::HAS_COMMIT));
+    assert_eq!(engine.commit_str(), "あ");
+    engine.clear_commit();
+
+    engine.press_key(Key::normal(A), &new);
+    assert_eq!(engine.preedit_str(), "ア");
+}
+
+#[test]
+fn category_removed_fallback_default() {
+    let old = config_with_kana('あ');
+    let new = Config::new(EngineConfig::default());
+    let mut engine = InputEngine::new(&old);
+    engine.set_input_category(InputCategory::new("Kana"));
+
+    engine.update_config(&old, &new);
+    assert_eq!(engine.category(), InputCategory::LATIN);
+}
@@ -0,0 +1,137 @@
+use kime_engine_core::{
+    Config, EngineConfig, Hotkey, HotkeyBehavior, HotkeyResult, InputCategory, InputEngine,
+    InputResult, Key, KeyCode, KeyCode::*,
+};
+use pretty_assertions::assert_eq;
+
+fn config() -> Config {
+    let mut engine = EngineConfig::default();
+    engine.global_hotkeys.insert(
+        Key::normal(F2).into(),
+        Hotkey::new(HotkeyBehavior::Convert, HotkeyResult::ConsumeIfProcessed),
+    );
+    Config::new(engine)
+}
+
+/// Engine of frontend which can delete surrounding text
+fn new_engine(config: &Config) -> InputEngine {
+    let mut engine = InputEngine::new(config);
+    engine.set_surrounding_text("", 0);
+    engine
+}
+
+fn type_keys(engine: &mut InputEngine, config: &Config, keys: &[KeyCode]) {
+    for key in keys.iter().copied() {
+        engine.press_key(Key::normal(key), config);
+        engine.clear_commit();
+    }
+}
+
+#[test]
+fn latin_to_hangul() {
+    let config = config();
+    let mut engine = new_engine(&config);
+
+    type_keys(&mut engine, &config, &[D, K, S, S, U, D, G, K, T, P, D, Y]);
+
+    let ret = engine.press_key(Key::normal(F2), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert!(ret.contains(InputResult::DELETE_SURROUNDING));
+    assert_eq!(engine.delete_surrounding_len(), 12);
+    assert_eq!(engine.commit_str(), "안녕하세");
+    assert_eq!(engine.preedit_str(), "요");
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+    engine.clear_commit();
+
+    // Convert again restore latin
+    let ret = engine.press_key(Key::normal(F2), &config);
+    assert!(ret.contains(InputResult::DELETE_SURROUNDING));
+    assert!(!ret.contains(InputResult::HAS_PREEDIT));
+    assert_eq!(engine.delete_surrounding_len(), 4);
+    assert_eq!(engine.commit_str(), "dkssudgktpdy");
+    assert_eq!(engine.category(), InputCategory::LATIN);
+}
+
+#[test]
+fn latin_backspace() {
+    let config = config();
+    let mut engine = new_engine(&config);
+
+    type_keys(&mut engine, &config, &[D, K, X, Backspace, S, S, U, D]);
+
+    engine.press_key(Key::normal(F2), &config);
+    assert_eq!(engine.delete_surrounding_len(), 6);
+    assert_eq!(engine.commit_str(), "안");
+    assert_eq!(engine.preedit_str(), "녕");
+}
+
+#[test]
+fn word_boundary() {
+    let config = config();
+    let mut engine = new_engine(&config);
+
+    type_keys(&mut engine, &config, &[A, B, Space, R, K]);
+
+    engine.press_key(Key::normal(F2), &config);
+    assert_eq!(engine.delete_surrounding_len(), 2);
+    assert_eq!(engine.commit_str(), "");
+    assert_eq!(engine.preedit_str(), "가");
+}
+
+#[test]
+fn hangul_surrounding_to_latin() {
+    let config = config();
+    let mut engine = new_engine(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    engine.set_surrounding_text("hello 안녕", "hello 안녕".len());
+    let ret = engine.press_key(Key::normal(F2), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.delete_surrounding_len(), 2);
+    assert_eq!(engine.commit_str(), "dkssud");
+    assert_eq!(engine.category(), InputCategory::LATIN);
+}
+
+#[test]
+fn latin_surrounding_to_hangul() {
+    let config = config();
+    let mut engine = new_engine(&config);
+
+    engine.set_surrounding_text("dhksfy", "dhksfy".len());
+    engine.press_key(Key::normal(F2), &config);
+    assert_eq!(engine.delete_surrounding_len(), 6);
+    assert_eq!(engine.commit_str(), "완");
+    assert_eq!(engine.preedit_str(), "료");
+}
+
+#[test]
+fn nothing_to_convert() {
+    let config = config();
+    let mut engine = new_engine(&config);
+
+    let ret = engine.press_key(Key::normal(F2), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.category(), InputCategory::LATIN);
+}
+
+#[test]
+fn surrounding_not_supported() {
+    let config = config();
+    let mut engine = InputEngine::new(&config);
+
+    // Typed word can't be deleted
+    type_keys(&mut engine, &config, &[D, K, S, S, U, D]);
+    let ret = engine.press_key(Key::normal(F2), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+    assert!(!ret.contains(InputResult::DELETE_SURROUNDING));
+    assert_eq!(engine.category(), InputCategory::LATIN);
+
+    // Client stops supplying surrounding text
+    engine.set_surrounding_text("", 0);
+    engine.clear_surrounding_text();
+    type_keys(&mut engine, &config, &[Space, D, K]);
+    let ret = engine.press_key(Key::normal(F2), &config);
+    assert!(!ret.contains(InputResult::DELETE_SURROUNDING));
+    assert_eq!(engine.commit_str(), "");
+}
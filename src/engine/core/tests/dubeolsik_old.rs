@@ -0,0 +1,72 @@
+#[macro_use]
+mod shared;
+
+define_layout_test!("dubeolsik-old");
+
+#[test]
+fn kapyeounbieup_araea() {
+    test_input(&[
+        (Key::normal(Q), "ㅂ", ""),
+        (Key::normal(D), "ㅸ", ""),
+        (Key::shift(K), "ᄫᆞ", ""),
+        (Key::normal(F), "ᄫᆞᆯ", ""),
+        (Key::normal(Space), "", "ᄫᆞᆯPASS"),
+    ]);
+}
+
+#[test]
+fn araea_i_backspace() {
+    test_input(&[
+        (Key::normal(R), "ㄱ", ""),
+        (Key::shift(K), "ᄀᆞ", ""),
+        (Key::normal(L), "ᄀᆡ", ""),
+        (Key::normal(Backspace), "ᄀᆞ", ""),
+        (Key::normal(Backspace), "ㄱ", ""),
+        (Key::normal(Backspace), "", ""),
+    ]);
+}
+
+#[test]
+fn yesieung() {
+    test_input(&[
+        (Key::shift(D), "ㆁ", ""),
+        (Key::normal(K), "ᅌᅡ", ""),
+        (Key::shift(D), "ᅌᅡᇰ", ""),
+    ]);
+}
+
+#[test]
+fn jongseong_cluster_move() {
+    test_input(&[
+        (Key::normal(A), "ㅁ", ""),
+        (Key::normal(K), "마", ""),
+        (Key::normal(A), "맘", ""),
+        // Old hangul syllable is written as conjoining jamo
+        (Key::normal(A), "\u{1106}\u{1161}\u{D7E0}", ""),
+        (Key::normal(K), "마", "맘"),
+    ]);
+}
+
+#[test]
+fn modern_word() {
+    test_input(&[
+        (Key::normal(D), "ㅇ", ""),
+        (Key::normal(K), "아", ""),
+        (Key::normal(S), "안", ""),
+        (Key::normal(S), "\u{110B}\u{1161}\u{11FF}", ""),
+        (Key::normal(U), "녀", "안"),
+        (Key::normal(D), "녕", ""),
+    ]);
+}
+
+#[test]
+fn without_addon() {
+    test_input_with_addon(
+        &[
+            (Key::normal(Q), "ㅂ", ""),
+            (Key::normal(D), "ㅇ", "ㅂ"),
+            (Key::shift(K), "ᄋᆞ", ""),
+        ],
+        EnumSet::empty(),
+    );
+}
@@ -1,7 +1,7 @@
 #[macro_use]
 mod shared;
 
-define_layout_test!("dubeolsik", LatinLayout::Qwerty, InputCategory::Latin);
+define_layout_test!("dubeolsik", LatinLayout::Qwerty, InputCategory::LATIN);
 
 use kime_engine_core::ModifierState;
 
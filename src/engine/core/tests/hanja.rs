@@ -0,0 +1,265 @@
+use kime_engine_candidate::protocol::{read_message, Message};
+use kime_engine_core::{
+    Config, EngineConfig, InputCategory, InputEngine, InputMode, InputResult, Key, KeyCode,
+    KeyCode::*,
+};
+use kime_engine_dict::user::UserDict;
+use pretty_assertions::assert_eq;
+use std::{
+    io::{BufReader, Write},
+    net::Shutdown,
+    os::unix::{fs::PermissionsExt, net::UnixListener},
+    path::PathBuf,
+};
+
+/// Config of hanja mode which uses fake candidate window and dictionary in temp dir of test
+struct FakeHanja {
+    dir: PathBuf,
+    config: Config,
+}
+
+impl FakeHanja {
+    /// Candidate window runs `script` with candidates in stdin
+    ///
+    /// Its server can't be started so candidates are shown by process of each lookup
+    fn new(name: &str, engine_config: EngineConfig, script: &str) -> Self {
+        let dir = std::env::temp_dir().join(format!("kime-hanja-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let program = dir.join("kime-candidate-window");
+        std::fs::write(
+            &program,
+            format!("#!/bin/sh\n[ \"$1\" = --server ] && exit 1\n{}\n", script),
+        )
+        .unwrap();
+        std::fs::set_permissions(&program, std::fs::Permissions::from_mode(0o755)).unwrap();
+
+        let mut config = Config::new(engine_config);
+        config.hanja.dict_dir = Some(dir.clone());
+        config.hanja.candidate_window.program = program;
+        config.hanja.candidate_window.socket = dir.join("kime-candidate.sock");
+
+        Self { dir, config }
+    }
+
+    /// Select first candidate
+    fn select(name: &str) -> Self {
+        Self::new(
+            name,
+            EngineConfig::default(),
+            "read -r command; read -r count; read -r hanja; cat > /dev/null; printf %s \"$hanja\"",
+        )
+    }
+
+    /// Close candidate window without selection
+    fn cancel(name: &str) -> Self {
+        Self::new(name, EngineConfig::default(), "cat > /dev/null")
+    }
+
+    fn engine(&self) -> InputEngine {
+        let mut engine = InputEngine::new(&self.config);
+        engine.set_input_category(InputCategory::HANGUL);
+        engine
+    }
+
+    fn dict(&self) -> UserDict {
+        UserDict::load_from(&self.dir)
+    }
+
+    fn type_keys(&self, engine: &mut InputEngine, codes: &[KeyCode]) {
+        for code in codes.iter().copied() {
+            engine.press_key(Key::normal(code), &self.config);
+        }
+    }
+}
+
+impl Drop for FakeHanja {
+    fn drop(&mut self) {
+        std::fs::remove_dir_all(&self.dir).ok();
+    }
+}
+
+/// Wait for candidate windows of all segments
+///
+/// `check_ready` waits for candidate window a while so this doesn't spin
+fn convert(engine: &mut InputEngine) -> InputResult {
+    loop {
+        assert!(
+            (0..50).any(|_| engine.check_ready()),
+            "Candidate window isn't closed"
+        );
+        let ret = engine.end_ready();
+        if !ret.contains(InputResult::NOT_READY) {
+            return ret;
+        }
+    }
+}
+
+#[test]
+fn surrounding_word() {
+    let hanja = FakeHanja::select("surrounding");
+    let mut engine = hanja.engine();
+
+    // Hangul word before cursor is converted with preedit
+    engine.set_surrounding_text("오늘 대한민", "오늘 대한민".len());
+    hanja.type_keys(&mut engine, &[R, N, R]);
+    assert_eq!(engine.preedit_str(), "국");
+    assert!(engine.set_input_mode(InputMode::Hanja));
+    assert_eq!(engine.commit_str(), "");
+    let ret = convert(&mut engine);
+    assert!(ret.contains(InputResult::HAS_COMMIT | InputResult::DELETE_SURROUNDING));
+    assert_eq!(engine.commit_str(), "大韓民國");
+    assert_eq!(engine.delete_surrounding_len(), 3);
+}
+
+#[test]
+fn candidate_server() {
+    // Candidates aren't shown by process when server is running
+    let hanja = FakeHanja::new("server", EngineConfig::default(), "exit 1");
+    let listener = UnixListener::bind(&hanja.config.hanja.candidate_window.socket).unwrap();
+
+    let server = std::thread::spawn(move || {
+        let mut stream = listener.accept().unwrap().0;
+        let message = read_message(BufReader::new(&stream)).unwrap();
+        if let Some(Message::Show(candidate_list)) = &message {
+            stream.write_all(candidate_list[0].0.as_bytes()).unwrap();
+        }
+        stream.shutdown(Shutdown::Both).unwrap();
+        message
+    });
+
+    let mut engine = hanja.engine();
+    engine.set_selection_text("한자");
+    assert!(engine.set_input_mode(InputMode::Hanja));
+    convert(&mut engine);
+    assert_eq!(engine.commit_str(), "漢字");
+
+    match server.join().unwrap() {
+        Some(Message::Show(candidate_list)) => assert_eq!(candidate_list[0].0, "漢字"),
+        message => panic!("Unexpected message {:?}", message),
+    }
+}
+
+#[test]
+fn record_selection() {
+    let hanja = FakeHanja::select("record");
+    let mut engine = hanja.engine();
+
+    engine.set_selection_text("대한민국");
+    assert!(engine.set_input_mode(InputMode::Hanja));
+    convert(&mut engine);
+    assert_eq!(engine.commit_str(), "大韓民國");
+
+    let dict = hanja.dict();
+    assert_ne!(dict, UserDict::default());
+    assert_eq!(dict.lookup("대한민국").unwrap()[0].0, "大韓民國");
+}
+
+#[test]
+fn selection_segments() {
+    let hanja = FakeHanja::select("selection");
+    let mut engine = hanja.engine();
+
+    // Entries of user come first
+    hanja
+        .dict()
+        .import_and_save_to(&hanja.dir, "한자:韓字:\n")
+        .unwrap();
+
+    engine.set_selection_text("대한민국, 한자!");
+    assert!(engine.set_input_mode(InputMode::Hanja));
+    let ret = convert(&mut engine);
+    assert!(!ret.contains(InputResult::DELETE_SURROUNDING));
+    assert_eq!(engine.commit_str(), "大韓民國, 韓字!");
+}
+
+#[test]
+fn no_hangul() {
+    let hanja = FakeHanja::select("no-hangul");
+    let mut engine = hanja.engine();
+
+    engine.set_selection_text("hello");
+    assert!(!engine.set_input_mode(InputMode::Hanja));
+}
+
+#[test]
+fn symbol() {
+    let hanja = FakeHanja::select("symbol");
+    let mut engine = hanja.engine();
+
+    // Single jamo shows symbols without hangul word before cursor
+    engine.set_surrounding_text("대한", "대한".len());
+    hanja.type_keys(&mut engine, &[A]);
+    assert_eq!(engine.preedit_str(), "ㅁ");
+    assert!(engine.set_input_mode(InputMode::Hanja));
+    let ret = convert(&mut engine);
+    assert!(!ret.contains(InputResult::DELETE_SURROUNDING));
+    assert_eq!(engine.commit_str(), "＃");
+}
+
+#[test]
+fn abbreviation() {
+    let hanja = FakeHanja::select("abbreviation");
+    let mut engine = hanja.engine();
+
+    // Choseong before cursor is abbreviation with preedit
+    engine.set_surrounding_text("ㄷㅎㅁ", "ㄷㅎㅁ".len());
+    hanja.type_keys(&mut engine, &[R]);
+    assert_eq!(engine.preedit_str(), "ㄱ");
+    assert!(engine.set_input_mode(InputMode::Hanja));
+    let ret = convert(&mut engine);
+    assert!(ret.contains(InputResult::HAS_COMMIT | InputResult::DELETE_SURROUNDING));
+    assert_eq!(engine.commit_str(), "대한민국");
+    assert_eq!(engine.delete_surrounding_len(), 3);
+}
+
+#[test]
+fn cancel() {
+    let hanja = FakeHanja::cancel("cancel");
+    let mut engine = hanja.engine();
+
+    engine.set_surrounding_text("대한민국", "대한민국".len());
+    assert!(engine.set_input_mode(InputMode::Hanja));
+    let ret = convert(&mut engine);
+    assert!(ret.contains(InputResult::HAS_COMMIT | InputResult::DELETE_SURROUNDING));
+    assert_eq!(engine.commit_str(), "대한민국");
+    assert_eq!(engine.delete_surrounding_len(), 4);
+    assert_eq!(hanja.dict(), UserDict::default());
+}
+
+#[test]
+fn preedit_only() {
+    let hanja = FakeHanja::cancel("preedit");
+    let mut engine = hanja.engine();
+
+    // Only preedit is converted when client can't delete word before cursor
+    hanja.type_keys(&mut engine, &[R, N, R]);
+    assert!(engine.set_input_mode(InputMode::Hanja));
+    let ret = convert(&mut engine);
+    assert!(!ret.contains(InputResult::DELETE_SURROUNDING));
+    assert_eq!(engine.commit_str(), "국");
+}
+
+#[test]
+fn romaja_alternatives() {
+    let mut engine_config = EngineConfig::default();
+    engine_config.hangul.layout = "romaja".into();
+    // Select second candidate
+    let hanja = FakeHanja::new(
+        "romaja",
+        engine_config,
+        "read -r command; read -r count; read -r first; read -r description; read -r second; \
+         cat > /dev/null; printf %s \"$second\"",
+    );
+    let mut engine = hanja.engine();
+
+    // Ambiguous romaja shows its alternatives instead of hanja
+    engine.set_surrounding_text("대한", "대한".len());
+    hanja.type_keys(&mut engine, &[J, E, O, N, G, E, U, M]);
+    assert_eq!(engine.preedit_str(), "전금");
+    assert!(engine.set_input_mode(InputMode::Hanja));
+    let ret = convert(&mut engine);
+    assert!(!ret.contains(InputResult::DELETE_SURROUNDING));
+    assert_eq!(engine.commit_str(), "정음");
+    // Alternatives aren't hanja
+    assert_eq!(hanja.dict(), UserDict::default());
+}
@@ -0,0 +1,232 @@
+use kime_engine_core::{
+    Config, EngineConfig, Hotkey, HotkeyBehavior, HotkeyResult, HotkeyTrigger, InputCategory,
+    InputEngine, InputResult, Key, KeyCode::*,
+};
+use pretty_assertions::assert_eq;
+use std::{thread::sleep, time::Duration};
+
+fn config(trigger: &str) -> Config {
+    let mut engine = EngineConfig::default();
+    engine.hotkey_timeout.sequence = 200;
+    engine.hotkey_timeout.double_tap = 200;
+    engine.hotkey_timeout.hold = 200;
+    engine.global_hotkeys.insert(
+        trigger.parse::<HotkeyTrigger>().unwrap(),
+        Hotkey::new(HotkeyBehavior::toggle_hangul_latin(), HotkeyResult::Consume),
+    );
+    Config::new(engine)
+}
+
+#[test]
+fn sequence() {
+    let config = config("C-Space H");
+    let mut engine = InputEngine::new(&config);
+
+    let ret = engine.press_key(Key::ctrl(Space), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.category(), InputCategory::LATIN);
+
+    // Modifier doesn't break sequence
+    engine.press_key(Key::normal(ControlL), &config);
+
+    let ret = engine.press_key(Key::normal(H), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+}
+
+#[test]
+fn sequence_mismatch() {
+    let config = config("C-Space H");
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    engine.press_key(Key::ctrl(Space), &config);
+    let ret = engine.press_key(Key::normal(R), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+    assert_eq!(engine.preedit_str(), "ㄱ");
+}
+
+#[test]
+fn sequence_timeout() {
+    let config = config("C-Space H");
+    let mut engine = InputEngine::new(&config);
+
+    engine.press_key(Key::ctrl(Space), &config);
+    sleep(Duration::from_millis(300));
+    engine.press_key(Key::normal(H), &config);
+    assert_eq!(engine.category(), InputCategory::LATIN);
+}
+
+#[test]
+fn double_tap() {
+    let config = config("Double-Shift");
+    let mut engine = InputEngine::new(&config);
+
+    engine.press_key(Key::normal(Shift), &config);
+    let ret = engine.press_key(Key::normal(Shift), &config);
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+
+    // Shift is used for typing
+    engine.press_key(Key::normal(Shift), &config);
+    let ret = engine.press_key(Key::shift(R), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+    assert_eq!(engine.preedit_str(), "ㄲ");
+}
+
+#[test]
+fn replay_latin() {
+    let config = config("Double-X");
+    let mut engine = InputEngine::new(&config);
+
+    let ret = engine.press_key(Key::normal(X), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.commit_str(), "");
+
+    let ret = engine.press_key(Key::normal(Y), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.commit_str(), "x");
+}
+
+#[test]
+fn replay_bypass() {
+    let config = config("C-Space H");
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    engine.press_key(Key::ctrl(Space), &config);
+    let ret = engine.press_key(Key::normal(R), &config);
+    assert!(ret.contains(InputResult::BYPASS_KEYS));
+    assert_eq!(engine.bypass_keys(), &[Key::ctrl(Space)]);
+    assert_eq!(engine.commit_str(), "");
+    assert_eq!(engine.preedit_str(), "ㄱ");
+
+    engine.clear_commit();
+    assert!(engine.bypass_keys().is_empty());
+}
+
+#[test]
+fn hold() {
+    let config = config("Hold-X");
+    let mut engine = InputEngine::new(&config);
+
+    // Key repeat while pressing X
+    for _ in 0..4 {
+        let ret = engine.press_key(Key::normal(X), &config);
+        assert!(ret.contains(InputResult::CONSUMED));
+        assert_eq!(engine.category(), InputCategory::LATIN);
+        sleep(Duration::from_millis(60));
+    }
+
+    let ret = engine.press_key(Key::normal(X), &config);
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+
+    // Rest of repeat is ignored
+    let ret = engine.press_key(Key::normal(X), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.preedit_str(), "");
+    assert_eq!(engine.commit_str(), "");
+}
+
+#[test]
+fn hold_tap() {
+    let config = config("Hold-X");
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    engine.press_key(Key::normal(X), &config);
+    engine.press_key(Key::normal(K), &config);
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+    assert_eq!(engine.preedit_str(), "타");
+}
+
+#[test]
+fn tap() {
+    let config = config("Tap-Shift");
+    let mut engine = InputEngine::new(&config);
+
+    let ret = engine.press_key(Key::normal(Shift), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+
+    // State of release contains the modifier
+    let ret = engine.release_key(Key::shift(Shift), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+}
+
+#[test]
+fn tap_with_other_key() {
+    let config = config("Tap-Shift");
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    engine.press_key(Key::normal(Shift), &config);
+    engine.press_key(Key::shift(R), &config);
+    engine.release_key(Key::shift(R), &config);
+    let ret = engine.release_key(Key::shift(Shift), &config);
+    assert_eq!(ret, InputResult::empty());
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+    assert_eq!(engine.preedit_str(), "ㄲ");
+}
+
+#[test]
+fn tap_timeout() {
+    let config = config("Tap-Shift");
+    let mut engine = InputEngine::new(&config);
+
+    engine.press_key(Key::normal(Shift), &config);
+    sleep(Duration::from_millis(300));
+    engine.release_key(Key::shift(Shift), &config);
+    assert_eq!(engine.category(), InputCategory::LATIN);
+}
+
+#[test]
+fn hold_release() {
+    let config = config("Hold-X");
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    engine.press_key(Key::normal(X), &config);
+    assert_eq!(engine.preedit_str(), "");
+
+    let ret = engine.release_key(Key::normal(X), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert!(ret.contains(InputResult::HAS_PREEDIT));
+    assert_eq!(engine.preedit_str(), "ㅌ");
+}
+
+#[test]
+fn double_tap_release() {
+    let config = config("Double-Shift");
+    let mut engine = InputEngine::new(&config);
+
+    engine.press_key(Key::normal(Shift), &config);
+    // Client never got the press
+    let ret = engine.release_key(Key::shift(Shift), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    engine.press_key(Key::normal(Shift), &config);
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+
+    let ret = engine.release_key(Key::shift(Shift), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+}
+
+#[test]
+fn sequence_release() {
+    let config = config("C-Space H");
+    let mut engine = InputEngine::new(&config);
+
+    engine.press_key(Key::ctrl(Space), &config);
+    let ret = engine.release_key(Key::ctrl(Space), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    let ret = engine.release_key(Key::normal(ControlL), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+
+    engine.press_key(Key::normal(H), &config);
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+}
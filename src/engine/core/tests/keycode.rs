@@ -0,0 +1,120 @@
+use kime_engine_core::{
+    Config, EngineConfig, Hotkey, HotkeyBehavior, HotkeyResult, InputCategory, InputEngine,
+    InputResult, Key, KeyCode::*, ModifierState,
+};
+use pretty_assertions::assert_eq;
+
+fn hangul_engine(config: &Config) -> InputEngine {
+    let mut engine = InputEngine::new(config);
+    engine.set_input_category(InputCategory::HANGUL);
+    engine.press_key(Key::normal(R), config);
+    engine.press_key(Key::normal(K), config);
+    assert_eq!(engine.preedit_str(), "가");
+    engine
+}
+
+#[test]
+fn keypad_commit_preedit() {
+    let config = Config::default();
+    let mut engine = hangul_engine(&config);
+
+    let ret = engine.press_key(Key::new(Kp1, ModifierState::NUMLOCK), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.preedit_str(), "");
+    assert_eq!(engine.commit_str(), "가");
+}
+
+#[test]
+fn inert_keep_preedit() {
+    let config = Config::default();
+    let mut engine = hangul_engine(&config);
+
+    for code in [
+        F13, F24, Menu, Compose, CapsLock, NumLock, PlayPause, SuperL,
+    ] {
+        let ret = engine.press_key(Key::normal(code), &config);
+        assert!(!ret.contains(InputResult::CONSUMED));
+        assert_eq!(engine.preedit_str(), "가");
+        assert_eq!(engine.commit_str(), "");
+    }
+
+    engine.press_key(Key::normal(S), &config);
+    assert_eq!(engine.preedit_str(), "간");
+}
+
+#[test]
+fn unknown_code_keep_preedit() {
+    let config = Config::default();
+    let mut engine = hangul_engine(&config);
+
+    let ret = engine.press_key_code(300, ModifierState::empty(), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.preedit_str(), "가");
+    assert_eq!(engine.commit_str(), "");
+}
+
+#[test]
+fn latin_keypad() {
+    let mut engine_config = EngineConfig::default();
+    engine_config.latin.preferred_direct = false;
+    let config = Config::new(engine_config);
+    let mut engine = InputEngine::new(&config);
+
+    let ret = engine.press_key(Key::new(Kp1, ModifierState::NUMLOCK), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.commit_str(), "1");
+    engine.clear_commit();
+
+    // Keypad works as End without NumLock
+    let ret = engine.press_key(Key::normal(Kp1), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+
+    let ret = engine.press_key(Key::normal(KpPlus), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.commit_str(), "+");
+}
+
+#[test]
+fn keypad_hotkey() {
+    let mut engine_config = EngineConfig::default();
+    engine_config.global_hotkeys.insert(
+        Key::normal(KpDivide).into(),
+        Hotkey::new(HotkeyBehavior::toggle_hangul_latin(), HotkeyResult::Consume),
+    );
+    let config = Config::new(engine_config);
+    let mut engine = InputEngine::new(&config);
+
+    let ret = engine.press_key(Key::new(KpDivide, ModifierState::NUMLOCK), &config);
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+}
+
+#[test]
+fn keysym() {
+    let config = Config::default();
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    // AZERTY: 'a' is on position of US 'q'
+    engine.press_keysym('a' as u32, ModifierState::empty(), &config);
+    engine.press_keysym('k' as u32, ModifierState::empty(), &config);
+    assert_eq!(engine.preedit_str(), "마");
+
+    // Shifted letter keeps Shift
+    engine.press_keysym('E' as u32, ModifierState::SHIFT, &config);
+    assert_eq!(engine.preedit_str(), "ㄸ");
+    assert_eq!(engine.commit_str(), "마");
+
+    // Inert keysym keeps preedit e.g. XF86AudioMute
+    let ret = engine.press_keysym(0x1008_ff12, ModifierState::empty(), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.preedit_str(), "ㄸ");
+    engine.clear_commit();
+
+    // Keysym without key commits preedit before client types it e.g. euro sign
+    let ret = engine.press_keysym(0x20ac, ModifierState::LEVEL3, &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+    assert!(ret.contains(InputResult::HAS_COMMIT));
+    assert_eq!(engine.preedit_str(), "");
+    assert_eq!(engine.commit_str(), "ㄸ");
+}
@@ -0,0 +1,133 @@
+use kime_engine_backend_hangul::Addon;
+use kime_engine_core::{
+    Config, EngineConfig, EnumSet, Hotkey, HotkeyBehavior, HotkeyResult, InputCategory,
+    InputEngine, InputResult, Key, KeyCode::*, LayoutName,
+};
+use pretty_assertions::assert_eq;
+
+fn config(f: impl FnOnce(&mut EngineConfig)) -> Config {
+    let mut engine = EngineConfig::default();
+    engine.hangul.layouts = vec!["dubeolsik".into(), "sebeolsik-3-90".into()];
+    engine.global_hotkeys.insert(
+        Key::normal(F1).into(),
+        Hotkey::new(
+            HotkeyBehavior::CycleLayout,
+            HotkeyResult::ConsumeIfProcessed,
+        ),
+    );
+    f(&mut engine);
+    Config::new(engine)
+}
+
+fn hangul_engine(config: &Config) -> InputEngine {
+    let mut engine = InputEngine::new(config);
+    engine.set_input_category(InputCategory::HANGUL);
+    engine
+}
+
+#[test]
+fn cycle() {
+    let config = config(|_| {});
+    let mut engine = hangul_engine(&config);
+
+    assert_eq!(engine.layout(), Some("dubeolsik"));
+    engine.press_key(Key::normal(R), &config);
+    assert_eq!(engine.preedit_str(), "ㄱ");
+
+    let ret = engine.press_key(Key::normal(F1), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert_eq!(engine.commit_str(), "ㄱ");
+    assert_eq!(engine.layout(), Some("sebeolsik-3-90"));
+    engine.clear_commit();
+
+    engine.press_key(Key::normal(J), &config);
+    assert_eq!(engine.preedit_str(), "ㅇ");
+
+    engine.press_key(Key::normal(F1), &config);
+    assert_eq!(engine.layout(), Some("dubeolsik"));
+}
+
+#[test]
+fn select_by_name() {
+    let config = config(|engine| {
+        engine.global_hotkeys.insert(
+            Key::normal(F2).into(),
+            Hotkey::new(
+                HotkeyBehavior::Layout(LayoutName::new("sebeolsik-3-90")),
+                HotkeyResult::ConsumeIfProcessed,
+            ),
+        );
+        engine.global_hotkeys.insert(
+            Key::normal(F3).into(),
+            Hotkey::new(
+                HotkeyBehavior::Layout(LayoutName::new("unknown")),
+                HotkeyResult::ConsumeIfProcessed,
+            ),
+        );
+    });
+    let mut engine = hangul_engine(&config);
+
+    let ret = engine.press_key(Key::normal(F2), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.layout(), Some("sebeolsik-3-90"));
+
+    let ret = engine.press_key(Key::normal(F3), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.layout(), Some("sebeolsik-3-90"));
+}
+
+#[test]
+fn layout_addons() {
+    let config = config(|engine| {
+        engine.hangul.addons = vec![
+            ("all".into(), EnumSet::empty()),
+            (
+                "sebeolsik-3-90".into(),
+                EnumSet::only(Addon::ComposeChoseongSsang),
+            ),
+        ]
+        .into_iter()
+        .collect();
+    });
+    let mut engine = hangul_engine(&config);
+
+    engine.press_key(Key::normal(R), &config);
+    engine.press_key(Key::normal(R), &config);
+    assert_eq!(engine.preedit_str(), "ㄱ");
+    assert_eq!(engine.commit_str(), "ㄱ");
+    engine.clear_commit();
+
+    engine.press_key(Key::normal(F1), &config);
+    engine.clear_commit();
+    engine.press_key(Key::normal(K), &config);
+    engine.press_key(Key::normal(K), &config);
+    assert_eq!(engine.preedit_str(), "ㄲ");
+}
+
+#[test]
+fn no_layout() {
+    let config = config(|_| {});
+    let mut engine = InputEngine::new(&config);
+
+    assert_eq!(engine.category(), InputCategory::LATIN);
+    assert_eq!(engine.layout(), None);
+
+    let ret = engine.press_key(Key::normal(F1), &config);
+    assert!(!ret.contains(InputResult::LANGUAGE_CHANGED));
+}
+
+#[test]
+fn unknown_layouts() {
+    let config = config(|engine| {
+        engine.hangul.layout = "sebeolsik-3-90".into();
+        engine.hangul.layouts = vec!["unknown".into(), "dubeolsik".into()];
+    });
+    let mut engine = hangul_engine(&config);
+
+    assert_eq!(engine.layout(), Some("sebeolsik-3-90"));
+    engine.press_key(Key::normal(F1), &config);
+    assert_eq!(engine.layout(), Some("dubeolsik"));
+    engine.press_key(Key::normal(F1), &config);
+    assert_eq!(engine.layout(), Some("sebeolsik-3-90"));
+}
@@ -0,0 +1,183 @@
+use kime_engine_core::{
+    Config, EngineConfig, Hotkey, HotkeyBehavior, HotkeyResult, InputCategory, InputEngine, Key,
+    KeyCode::{self, *},
+    ModifierState,
+};
+use pretty_assertions::assert_eq;
+use std::{
+    thread::sleep,
+    time::{Duration, Instant},
+};
+
+struct Tester {
+    config: Config,
+    engine: InputEngine,
+    start: Instant,
+}
+
+impl Tester {
+    fn new() -> Self {
+        Self::with_config(|_| {})
+    }
+
+    fn with_config(f: impl FnOnce(&mut EngineConfig)) -> Self {
+        let mut config = EngineConfig::default();
+        config.hangul.layout = "sebeolsik-3-90".into();
+        config.hangul.moachigi.enable = true;
+        config.hangul.moachigi.window = 50;
+        f(&mut config);
+        let config = Config::new(config);
+        let mut engine = InputEngine::new(&config);
+        engine.set_input_category(InputCategory::HANGUL);
+
+        Self {
+            config,
+            engine,
+            start: Instant::now(),
+        }
+    }
+
+    fn press(&mut self, code: KeyCode, ms: u64) {
+        let time = self.start + Duration::from_millis(ms);
+        self.engine
+            .press_key_at(Key::normal(code), &self.config, time);
+    }
+
+    fn release(&mut self, code: KeyCode, ms: u64) {
+        let time = self.start + Duration::from_millis(ms);
+        self.engine
+            .release_key_at(Key::normal(code), &self.config, time);
+    }
+
+    fn press_timestamp(&mut self, code: KeyCode, timestamp: u32) {
+        let hardware_code = code.to_hardware_code().unwrap();
+        self.engine.press_key_code_at(
+            hardware_code,
+            ModifierState::empty(),
+            &self.config,
+            timestamp,
+        );
+    }
+
+    fn release_timestamp(&mut self, code: KeyCode, timestamp: u32) {
+        let hardware_code = code.to_hardware_code().unwrap();
+        self.engine.release_key_code_at(
+            hardware_code,
+            ModifierState::empty(),
+            &self.config,
+            timestamp,
+        );
+    }
+
+    fn check(&mut self, preedit: &str, commit: &str) {
+        assert_eq!(self.engine.preedit_str(), preedit);
+        assert_eq!(self.engine.commit_str(), commit);
+        self.engine.clear_commit();
+    }
+}
+
+#[test]
+fn reverse_order() {
+    let mut t = Tester::new();
+
+    t.press(S, 0);
+    t.check("ㄴ", "");
+    t.press(F, 5);
+    t.press(J, 10);
+    t.check("안", "");
+    t.release(S, 60);
+    t.release(F, 60);
+    t.release(J, 60);
+    t.check("안", "");
+}
+
+#[test]
+fn window() {
+    let mut t = Tester::new();
+
+    t.press(F, 0);
+    t.release(F, 10);
+    t.press(J, 20);
+    t.release(J, 30);
+    t.check("아", "");
+
+    // out of window
+    t.press(S, 100);
+    t.release(S, 110);
+    t.check("안", "");
+}
+
+#[test]
+fn held() {
+    let mut t = Tester::new();
+
+    t.press(F, 0);
+    t.press(J, 100);
+    t.check("아", "");
+    t.release(J, 110);
+    t.release(F, 120);
+
+    t.press(S, 200);
+    t.press(K, 205);
+    t.press(F, 210);
+    t.check("아간", "");
+    t.release(S, 300);
+    t.release(K, 300);
+    t.release(F, 300);
+    t.check("간", "아");
+}
+
+#[test]
+fn same_key() {
+    let mut t = Tester::new();
+
+    t.press(J, 0);
+    t.release(J, 5);
+    t.press(J, 10);
+    t.check("ㅇㅇ", "");
+}
+
+#[test]
+fn clear_preedit() {
+    let mut t = Tester::new();
+
+    t.press(J, 0);
+    t.press(F, 5);
+    t.press(S, 100);
+    t.check("안", "");
+    t.press(Space, 110);
+    t.check("", "안");
+}
+
+#[test]
+fn event_timestamp() {
+    let mut t = Tester::new();
+
+    t.press_timestamp(F, 1000);
+    t.release_timestamp(F, 1010);
+    // Event is delivered late but it's pressed in window
+    sleep(Duration::from_millis(100));
+    t.press_timestamp(J, 1020);
+    t.release_timestamp(J, 1030);
+    t.check("아", "");
+}
+
+#[test]
+fn hotkey_release() {
+    let mut t = Tester::with_config(|config| {
+        config.global_hotkeys.insert(
+            "Hold-F".parse().unwrap(),
+            Hotkey::new(HotkeyBehavior::toggle_hangul_latin(), HotkeyResult::Consume),
+        );
+    });
+
+    // F is replayed by release
+    t.press(F, 0);
+    t.release(F, 10);
+    t.check("ㅏ", "");
+
+    // F is released so J doesn't join its chord
+    t.press(J, 100);
+    t.release(J, 110);
+    t.check("ㅏㅇ", "");
+}
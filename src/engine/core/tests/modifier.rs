@@ -0,0 +1,55 @@
+use kime_engine_core::{
+    Config, EngineConfig, InputCategory, InputEngine, InputResult, Key, KeyCode::*, ModifierState,
+};
+use pretty_assertions::assert_eq;
+
+const LOCKS: ModifierState = ModifierState::LOCKS;
+
+#[test]
+fn hotkey_with_locks() {
+    let config = Config::default();
+    let mut engine = InputEngine::new(&config);
+
+    let ret = engine.press_key(Key::new(AltR, LOCKS), &config);
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+}
+
+#[test]
+fn hangul_ignore_capslock() {
+    let config = Config::default();
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    engine.press_key(Key::new(R, ModifierState::CAPSLOCK), &config);
+    engine.press_key(Key::new(K, ModifierState::CAPSLOCK), &config);
+    assert_eq!(engine.preedit_str(), "가");
+}
+
+#[test]
+fn latin_capslock() {
+    let mut engine_config = EngineConfig::default();
+    engine_config.latin.preferred_direct = false;
+    let config = Config::new(engine_config);
+    let mut engine = InputEngine::new(&config);
+
+    for (key, commit) in [
+        (Key::new(A, ModifierState::CAPSLOCK), "A"),
+        (
+            Key::new(A, ModifierState::CAPSLOCK | ModifierState::SHIFT),
+            "a",
+        ),
+        (Key::new(One, ModifierState::CAPSLOCK), "1"),
+        (
+            Key::new(One, ModifierState::CAPSLOCK | ModifierState::SHIFT),
+            "!",
+        ),
+    ]
+    .iter()
+    {
+        let ret = engine.press_key(*key, &config);
+        assert!(ret.contains(InputResult::CONSUMED));
+        assert_eq!(engine.commit_str(), *commit);
+        engine.clear_commit();
+    }
+}
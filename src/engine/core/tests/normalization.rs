@@ -0,0 +1,107 @@
+#[macro_use]
+mod shared;
+
+define_layout_test!("dubeolsik");
+
+use kime_engine_core::CommitNormalization;
+
+#[track_caller]
+fn test_normalized(
+    keys: &[(Key, &str, &str)],
+    normalization: CommitNormalization,
+    word_commit: bool,
+) {
+    let mut config = default_config();
+    config.hangul.commit_normalization = normalization;
+    config.hangul.word_commit = word_commit;
+    test_input_impl(config, InputCategory::HANGUL, keys);
+}
+
+#[test]
+fn nfd() {
+    test_normalized(
+        &[
+            (Key::normal(G), "ㅎ", ""),
+            (Key::normal(K), "하", ""),
+            (Key::normal(S), "한", ""),
+            (Key::normal(K), "나", "\u{1112}\u{1161}"),
+            (Key::normal(Space), "", "\u{1102}\u{1161}PASS"),
+            (Key::normal(R), "ㄱ", ""),
+            (Key::normal(Space), "", "ㄱPASS"),
+        ],
+        CommitNormalization::Nfd,
+        false,
+    );
+}
+
+#[test]
+fn nfd_word_commit() {
+    test_normalized(
+        &[
+            (Key::normal(D), "ㅇ", ""),
+            (Key::normal(K), "아", ""),
+            (Key::normal(S), "안", ""),
+            (Key::normal(S), "안ㄴ", ""),
+            (Key::normal(U), "안녀", ""),
+            (Key::normal(D), "안녕", ""),
+            (Key::normal(Backspace), "안녀", ""),
+            (Key::normal(D), "안녕", ""),
+            (
+                Key::normal(Esc),
+                "",
+                "\u{110B}\u{1161}\u{11AB}\u{1102}\u{1167}\u{11BC}PASS",
+            ),
+        ],
+        CommitNormalization::Nfd,
+        true,
+    );
+}
+
+#[test]
+fn conjoining() {
+    test_normalized(
+        &[
+            (Key::normal(R), "ㄱ", ""),
+            (Key::normal(Space), "", "\u{1100}\u{1160}PASS"),
+            (Key::normal(K), "ㅏ", ""),
+            (Key::normal(Space), "", "\u{115F}\u{1161}PASS"),
+            (Key::normal(R), "ㄱ", ""),
+            (Key::normal(K), "가", ""),
+            (Key::normal(Space), "", "\u{1100}\u{1161}PASS"),
+        ],
+        CommitNormalization::Conjoining,
+        false,
+    );
+}
+
+#[test]
+fn nfc() {
+    test_normalized(
+        &[
+            (Key::normal(R), "ㄱ", ""),
+            (Key::normal(K), "가", ""),
+            (Key::normal(Space), "", "가PASS"),
+        ],
+        CommitNormalization::Nfc,
+        false,
+    );
+}
+
+#[test]
+fn conjoining_old_hangul() {
+    let mut config = default_config();
+    config.hangul.layout = "dubeolsik-old".into();
+    config.hangul.commit_normalization = CommitNormalization::Conjoining;
+    test_input_impl(
+        config,
+        InputCategory::HANGUL,
+        &[
+            (Key::normal(A), "ㅁ", ""),
+            (Key::normal(K), "마", ""),
+            (Key::normal(A), "맘", ""),
+            (Key::normal(A), "\u{1106}\u{1161}\u{D7E0}", ""),
+            // Conjoining jamo is committed once without filler
+            (Key::normal(Space), "", "\u{1106}\u{1161}\u{D7E0}PASS"),
+        ],
+    );
+}
@@ -0,0 +1,111 @@
+use kime_engine_core::{
+    Config, EngineConfig, InputCategory, InputEngine, InputMode, Key, KeyCode::*, PreeditAttribute,
+    PreeditSegment,
+};
+use pretty_assertions::assert_eq;
+
+fn segment(start: usize, end: usize, attribute: PreeditAttribute) -> PreeditSegment {
+    PreeditSegment {
+        start,
+        end,
+        attribute,
+    }
+}
+
+#[test]
+fn hangul_composing() {
+    let config = Config::default();
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    engine.press_key(Key::normal(R), &config);
+    engine.press_key(Key::normal(K), &config);
+
+    let preedit = engine.preedit();
+    assert_eq!(preedit.text(), "가");
+    assert_eq!(preedit.cursor(), 3);
+    assert_eq!(
+        preedit.segments(),
+        &[segment(0, 3, PreeditAttribute::Composing)]
+    );
+}
+
+#[test]
+fn word_commit_select_syllable() {
+    let mut engine_config = EngineConfig::default();
+    engine_config.hangul.word_commit = true;
+    let config = Config::new(engine_config);
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    for code in [R, K, S, K].iter().copied() {
+        engine.press_key(Key::normal(code), &config);
+    }
+
+    let preedit = engine.preedit();
+    assert_eq!(preedit.text(), "가나");
+    assert_eq!(preedit.cursor(), 6);
+    assert_eq!(
+        preedit.segments(),
+        &[
+            segment(0, 3, PreeditAttribute::Composing),
+            segment(3, 6, PreeditAttribute::Selected),
+        ]
+    );
+}
+
+#[test]
+fn emoji_hint_after_cursor() {
+    let config = Config::default();
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_mode(InputMode::Emoji);
+
+    for code in [D, O, G].iter().copied() {
+        engine.press_key(Key::normal(code), &config);
+    }
+
+    let preedit = engine.preedit();
+    assert_eq!(preedit.cursor(), 3);
+    assert_eq!(
+        preedit.segments()[0],
+        segment(0, 3, PreeditAttribute::Composing)
+    );
+    assert_eq!(
+        preedit.segments()[1].attribute,
+        PreeditAttribute::CandidateHint
+    );
+    assert_eq!(preedit.segments()[1].end, preedit.text().len());
+}
+
+#[test]
+fn emoji_not_found() {
+    let config = Config::default();
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_mode(InputMode::Emoji);
+
+    for code in [Q, Z, X, Q, Z, X].iter().copied() {
+        engine.press_key(Key::normal(code), &config);
+    }
+
+    let preedit = engine.preedit();
+    assert_eq!(preedit.text(), "qzxqzx");
+    assert_eq!(
+        preedit.segments(),
+        &[segment(0, 6, PreeditAttribute::Error)]
+    );
+}
+
+#[test]
+fn preedit_str_update_last_preedit() {
+    let config = Config::default();
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    engine.press_key(Key::normal(R), &config);
+    assert_eq!(engine.preedit_str(), "ㄱ");
+    assert_eq!(engine.last_preedit().segments().len(), 1);
+
+    engine.press_key(Key::normal(Space), &config);
+    assert_eq!(engine.preedit_str(), "");
+    assert_eq!(engine.last_preedit().segments(), &[]);
+}
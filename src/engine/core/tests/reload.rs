@@ -0,0 +1,57 @@
+use kime_engine_core::{
+    Config, EngineConfig, InputCategory, InputEngine, InputResult, Key, KeyCode::*,
+};
+use pretty_assertions::assert_eq;
+
+fn dubeolsik() -> Config {
+    Config::new(EngineConfig::default())
+}
+
+fn sebeolsik() -> Config {
+    let mut config = EngineConfig::default();
+    config.hangul.layout = "sebeolsik-3-90".into();
+    Config::new(config)
+}
+
+#[test]
+fn layout_changed_commit_preedit() {
+    let old = dubeolsik();
+    let new = sebeolsik();
+    let mut engine = InputEngine::new(&old);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    engine.press_key(Key::normal(R), &old);
+    engine.press_key(Key::normal(K), &old);
+    assert_eq!(engine.preedit_str(), "가");
+
+    let ret = engine.update_config(&old, &new);
+    assert!(ret.contains(InputResult::HAS_COMMIT));
+    assert!(!ret.contains(InputResult::HAS_PREEDIT));
+    assert_eq!(engine.commit_str(), "가");
+    engine.clear_commit();
+
+    // sebeolsik: K(ㄱ) F(ㅏ)
+    engine.press_key(Key::normal(K), &new);
+    engine.press_key(Key::normal(F), &new);
+    assert_eq!(engine.preedit_str(), "가");
+}
+
+#[test]
+fn layout_unchanged_keep_preedit() {
+    let old = dubeolsik();
+    let mut engine_config = EngineConfig::default();
+    engine_config.latin.preferred_direct = false;
+    let new = Config::new(engine_config);
+    let mut engine = InputEngine::new(&old);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    engine.press_key(Key::normal(R), &old);
+    engine.press_key(Key::normal(K), &old);
+
+    let ret = engine.update_config(&old, &new);
+    assert!(ret.contains(InputResult::HAS_PREEDIT));
+    assert!(!ret.contains(InputResult::HAS_COMMIT));
+
+    engine.press_key(Key::normal(S), &new);
+    assert_eq!(engine.preedit_str(), "간");
+}
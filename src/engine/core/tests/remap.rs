@@ -0,0 +1,88 @@
+use kime_engine_core::{
+    Config, EngineConfig, Hotkey, HotkeyBehavior, HotkeyResult, InputCategory, InputEngine,
+    InputResult, Key, KeyCode::*, KeyRemap, ModifierState,
+};
+use pretty_assertions::assert_eq;
+
+fn config(f: impl FnOnce(&mut EngineConfig)) -> Config {
+    let mut engine = EngineConfig::default();
+    f(&mut engine);
+    Config::new(engine)
+}
+
+#[test]
+fn one_to_one() {
+    let config = config(|engine| {
+        engine
+            .remap
+            .global
+            .insert(Key::normal(CapsLock), KeyRemap::Key(Key::normal(Hangul)));
+    });
+    let mut engine = InputEngine::new(&config);
+
+    let ret = engine.press_key(Key::normal(CapsLock), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+}
+
+#[test]
+fn keep_modifiers() {
+    let config = config(|engine| {
+        engine
+            .remap
+            .global
+            .insert(Key::normal(R), KeyRemap::Key(Key::normal(E)));
+    });
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    engine.press_key(Key::shift(R), &config);
+    assert_eq!(engine.preedit_str(), "ㄸ");
+}
+
+#[test]
+fn modifier() {
+    let config = config(|engine| {
+        engine.remap.global.insert(
+            Key::normal(CapsLock),
+            KeyRemap::Modifier(ModifierState::CONTROL),
+        );
+        engine.global_hotkeys.insert(
+            Key::ctrl(Space).into(),
+            Hotkey::new(HotkeyBehavior::toggle_hangul_latin(), HotkeyResult::Consume),
+        );
+    });
+    let mut engine = InputEngine::new(&config);
+
+    let ret = engine.press_key(Key::normal(CapsLock), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+
+    let ret = engine.press_key(Key::normal(Space), &config);
+    assert!(ret.contains(InputResult::LANGUAGE_CHANGED));
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+
+    let ret = engine.release_key(Key::normal(CapsLock), &config);
+    assert_eq!(ret, InputResult::empty());
+
+    engine.press_key(Key::normal(Space), &config);
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+}
+
+#[test]
+fn category() {
+    let config = config(|engine| {
+        engine.remap.category.insert(
+            InputCategory::HANGUL,
+            std::iter::once((Key::normal(Q), KeyRemap::Key(Key::normal(W)))).collect(),
+        );
+    });
+    let mut engine = InputEngine::new(&config);
+
+    let ret = engine.press_key(Key::normal(Q), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+
+    engine.set_input_category(InputCategory::HANGUL);
+    engine.press_key(Key::normal(Q), &config);
+    assert_eq!(engine.preedit_str(), "ㅈ");
+}
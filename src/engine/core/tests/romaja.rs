@@ -0,0 +1,135 @@
+#[macro_use]
+mod shared;
+
+define_layout_test!("romaja");
+
+#[test]
+fn annyeonghaseyo() {
+    test_input(&[
+        (Key::normal(A), "아", ""),
+        (Key::normal(N), "안", ""),
+        (Key::normal(N), "안ㄴ", ""),
+        (Key::normal(Y), "안ㄴy", ""),
+        (Key::normal(E), "안녜", ""),
+        (Key::normal(O), "안녀", ""),
+        (Key::normal(N), "안년", ""),
+        (Key::normal(G), "안녕", ""),
+        (Key::normal(H), "안녕ㅎ", ""),
+        (Key::normal(A), "안녕하", ""),
+        (Key::normal(S), "안녕핫", ""),
+        (Key::normal(E), "안녕하세", ""),
+        (Key::normal(Y), "안녕하세y", ""),
+        (Key::normal(O), "안녕하세요", ""),
+        (Key::normal(Space), "", "안녕하세요PASS"),
+    ]);
+}
+
+#[test]
+fn eo_eu() {
+    test_input(&[
+        (Key::normal(S), "ㅅ", ""),
+        (Key::normal(E), "세", ""),
+        (Key::normal(O), "서", ""),
+        (Key::normal(U), "서우", ""),
+        (Key::normal(L), "서울", ""),
+        (Key::normal(Space), "", "서울PASS"),
+        (Key::normal(G), "ㄱ", ""),
+        (Key::normal(E), "게", ""),
+        (Key::normal(U), "그", ""),
+    ]);
+}
+
+#[test]
+fn candidate() {
+    test_input(&[
+        (Key::normal(J), "ㅈ", ""),
+        (Key::normal(E), "제", ""),
+        (Key::normal(O), "저", ""),
+        (Key::normal(N), "전", ""),
+        (Key::normal(G), "정", ""),
+        (Key::normal(E), "전게", ""),
+        (Key::normal(U), "전그", ""),
+        (Key::normal(M), "전금", ""),
+        // Alternatives are shown by candidate window
+        (Key::normal(Tab), "", "전금PASS"),
+    ]);
+}
+
+#[test]
+fn hanguk() {
+    test_input(&[
+        (Key::normal(H), "ㅎ", ""),
+        (Key::normal(A), "하", ""),
+        (Key::normal(N), "한", ""),
+        (Key::normal(G), "항", ""),
+        (Key::normal(U), "한구", ""),
+        (Key::normal(K), "한국", ""),
+        (Key::normal(Space), "", "한국PASS"),
+    ]);
+}
+
+#[test]
+fn hangugeo() {
+    test_input(&[
+        (Key::normal(H), "ㅎ", ""),
+        (Key::normal(A), "하", ""),
+        (Key::normal(N), "한", ""),
+        (Key::normal(G), "항", ""),
+        (Key::normal(U), "한구", ""),
+        (Key::normal(G), "한국", ""),
+        (Key::normal(E), "한구게", ""),
+        (Key::normal(O), "한구거", ""),
+        (Key::normal(Space), "", "한구거PASS"),
+        (Key::normal(H), "ㅎ", ""),
+        (Key::normal(A), "하", ""),
+        (Key::normal(N), "한", ""),
+        (Key::normal(G), "항", ""),
+        (Key::normal(U), "한구", ""),
+        (Key::normal(G), "한국", ""),
+        (Key::normal(Minus), "한국", ""),
+        (Key::normal(E), "한국에", ""),
+        (Key::normal(O), "한국어", ""),
+        (Key::normal(Space), "", "한국어PASS"),
+    ]);
+}
+
+#[test]
+fn boundary() {
+    test_input(&[
+        (Key::normal(Minus), "", "PASS"),
+        (Key::normal(J), "ㅈ", ""),
+        (Key::normal(E), "제", ""),
+        (Key::normal(O), "저", ""),
+        (Key::normal(N), "전", ""),
+        (Key::normal(G), "정", ""),
+        (Key::normal(Minus), "정", ""),
+        (Key::normal(E), "정에", ""),
+        (Key::normal(U), "정으", ""),
+        (Key::normal(M), "정음", ""),
+        (Key::normal(Tab), "", "정음PASS"),
+    ]);
+}
+
+#[test]
+fn backspace() {
+    test_input(&[
+        (Key::normal(H), "ㅎ", ""),
+        (Key::normal(A), "하", ""),
+        (Key::normal(N), "한", ""),
+        (Key::normal(Backspace), "하", ""),
+        (Key::normal(Backspace), "ㅎ", ""),
+        (Key::normal(Backspace), "", ""),
+        (Key::normal(Backspace), "", "PASS"),
+    ]);
+}
+
+#[test]
+fn invalid() {
+    test_input(&[
+        (Key::normal(F), "f", ""),
+        (Key::normal(O), "fo", ""),
+        (Key::normal(Space), "", "foPASS"),
+        (Key::shift(G), "ㄱ", ""),
+        (Key::shift(A), "가", ""),
+    ]);
+}
@@ -0,0 +1,27 @@
+#[macro_use]
+mod shared;
+
+define_layout_test!("sebeolsik-old");
+
+#[test]
+fn altgr_jamo() {
+    test_input(&[
+        (Key::normal(J), "ㅇ", ""),
+        (Key::normal(F), "아", ""),
+        (Key::level3(A), "\u{110B}\u{1161}\u{11F0}", ""),
+        (Key::level3(N), "ㅿ", "\u{110B}\u{1161}\u{11F0}"),
+        (Key::level3(F), "ᅀᆞ", ""),
+        (Key::normal(D), "ᅀᆡ", ""),
+    ]);
+}
+
+#[test]
+fn choseong_cluster() {
+    test_input(&[
+        (Key::normal(N), "ㅅ", ""),
+        (Key::normal(K), "ㅺ", ""),
+        (Key::normal(F), "ᄭᅡ", ""),
+        (Key::normal(Backspace), "ㅺ", ""),
+        (Key::normal(Backspace), "ㅅ", ""),
+    ]);
+}
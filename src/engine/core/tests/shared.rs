@@ -6,7 +6,26 @@ pub fn test_input_impl(engine: EngineConfig, category: InputCategory, keys: &[(K
     let config = Config::new(engine);
     let mut engine = InputEngine::new(&config);
     engine.set_input_category(category);
+    test_engine_input(&mut engine, &config, keys);
+}
+
+/// Engine of frontend which can delete surrounding text
+#[allow(dead_code)]
+#[track_caller]
+pub fn test_surrounding_input_impl(
+    engine: EngineConfig,
+    category: InputCategory,
+    keys: &[(Key, &str, &str)],
+) {
+    let config = Config::new(engine);
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(category);
+    engine.set_surrounding_text("", 0);
+    test_engine_input(&mut engine, &config, keys);
+}
 
+#[track_caller]
+fn test_engine_input(engine: &mut InputEngine, config: &Config, keys: &[(Key, &str, &str)]) {
     macro_rules! test_preedit {
         ($text:expr) => {{
             assert_eq!(engine.preedit_str(), $text);
@@ -25,7 +44,7 @@ pub fn test_input_impl(engine: EngineConfig, category: InputCategory, keys: &[(K
     for (key, preedit, commit) in keys.iter().copied() {
         eprintln!("Key: {:?}", key);
 
-        let ret = engine.press_key(key, &config);
+        let ret = engine.press_key(key, config);
 
         eprintln!("Ret: {:?}", ret);
 
@@ -50,7 +69,9 @@ macro_rules! define_layout_test {
     ($layout:expr, $latin_layout:expr, $category:expr) => {
         use kime_engine_backend_hangul::Addon;
         use kime_engine_backend_latin::LatinLayout;
-        use kime_engine_core::{EngineConfig, EnumSet, Hotkey, InputCategory, Key, KeyCode::*};
+        use kime_engine_core::{
+            AutoConvertAction, EngineConfig, EnumSet, Hotkey, InputCategory, Key, KeyCode::*,
+        };
         use shared::test_input_impl;
 
         #[allow(dead_code)]
@@ -87,11 +108,25 @@ macro_rules! define_layout_test {
         #[track_caller]
         fn test_input_with_hotkey(keys: &[(Key, &str, &str)], hotkeys: &[(Key, Hotkey)]) {
             let mut config = default_config();
-            config.global_hotkeys = hotkeys.iter().copied().collect();
+            config.global_hotkeys = hotkeys.iter().map(|(k, v)| ((*k).into(), *v)).collect();
             test_input_impl(config, $category, keys);
         }
+
+        #[allow(dead_code)]
+        #[track_caller]
+        fn test_input_with_auto_convert(
+            keys: &[(Key, &str, &str)],
+            action: AutoConvertAction,
+            hotkeys: &[(Key, Hotkey)],
+        ) {
+            let mut config = default_config();
+            config.auto_convert.enable = true;
+            config.auto_convert.action = action;
+            config.global_hotkeys = hotkeys.iter().map(|(k, v)| ((*k).into(), *v)).collect();
+            shared::test_surrounding_input_impl(config, $category, keys);
+        }
     };
     ($layout:expr) => {
-        define_layout_test!($layout, LatinLayout::Qwerty, InputCategory::Hangul);
+        define_layout_test!($layout, LatinLayout::Qwerty, InputCategory::HANGUL);
     };
 }
@@ -0,0 +1,138 @@
+use kime_engine_core::{
+    Config, EngineConfig, EngineSnapshot, InputCategory, InputEngine, InputMode, InputResult, Key,
+    KeyCode::*,
+};
+use pretty_assertions::assert_eq;
+
+#[test]
+fn restore_hangul_preedit() {
+    let config = Config::default();
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    engine.press_key(Key::normal(R), &config);
+    engine.press_key(Key::normal(K), &config);
+    engine.press_key(Key::normal(F), &config);
+    assert_eq!(engine.preedit_str(), "갈");
+
+    let snapshot = engine.snapshot();
+    engine.reset();
+    engine.set_input_category(InputCategory::LATIN);
+
+    let ret = engine.restore(&snapshot);
+    assert!(ret.contains(InputResult::HAS_PREEDIT));
+    assert_eq!(engine.category(), InputCategory::HANGUL);
+    assert_eq!(engine.preedit_str(), "갈");
+
+    // Composition continues from restored state
+    engine.press_key(Key::normal(R), &config);
+    assert_eq!(engine.preedit_str(), "갉");
+    engine.press_key(Key::normal(K), &config);
+    assert_eq!(engine.commit_str(), "갈");
+    assert_eq!(engine.preedit_str(), "가");
+}
+
+#[test]
+fn restore_word_buffer() {
+    let mut engine_config = EngineConfig::default();
+    engine_config.hangul.word_commit = true;
+    let config = Config::new(engine_config);
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    for code in [R, K, S, K].iter().copied() {
+        engine.press_key(Key::normal(code), &config);
+    }
+
+    let snapshot = engine.snapshot();
+    engine.reset();
+    assert_eq!(engine.preedit_str(), "");
+
+    engine.restore(&snapshot);
+    assert_eq!(engine.preedit_str(), "가나");
+
+    engine.press_key(Key::normal(Backspace), &config);
+    engine.press_key(Key::normal(Backspace), &config);
+    assert_eq!(engine.preedit_str(), "가");
+}
+
+#[test]
+fn restore_romaja() {
+    let mut engine_config = EngineConfig::default();
+    engine_config.hangul.layout = "romaja".into();
+    let config = Config::new(engine_config);
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+
+    for code in [H, A, N, G, U, K].iter().copied() {
+        engine.press_key(Key::normal(code), &config);
+    }
+    assert_eq!(engine.preedit_str(), "한국");
+
+    let snapshot = engine.snapshot();
+    engine.reset();
+    engine.press_key(Key::normal(A), &config);
+
+    engine.restore(&snapshot);
+    assert_eq!(engine.preedit_str(), "한국");
+
+    // Romaja is typed after restored preedit
+    engine.press_key(Key::normal(E), &config);
+    engine.press_key(Key::normal(O), &config);
+    assert_eq!(engine.preedit_str(), "한국어");
+    assert_eq!(engine.preedit().text(), "한국어");
+
+    engine.press_key(Key::normal(Backspace), &config);
+    engine.press_key(Key::normal(Backspace), &config);
+    engine.press_key(Key::normal(Backspace), &config);
+    assert_eq!(engine.preedit_str(), "한구");
+
+    engine.press_key(Key::normal(Space), &config);
+    assert_eq!(engine.commit_str(), "한구");
+}
+
+#[test]
+fn serialize_snapshot() {
+    let config = Config::default();
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_category(InputCategory::HANGUL);
+    engine.press_key(Key::normal(D), &config);
+
+    let snapshot = engine.snapshot();
+    let loaded = EngineSnapshot::from_yaml(&snapshot.to_yaml()).unwrap();
+    assert_eq!(snapshot, loaded);
+
+    let mut other = InputEngine::new(&config);
+    other.restore(&loaded);
+    assert_eq!(other.preedit_str(), "ㅇ");
+}
+
+#[test]
+fn restore_mode() {
+    let config = Config::default();
+    let mut engine = InputEngine::new(&config);
+    engine.set_input_mode(InputMode::Math);
+
+    let snapshot = engine.snapshot();
+    assert_eq!(snapshot.mode, Some(InputMode::Math));
+
+    let mut other = InputEngine::new(&config);
+    other.restore(&snapshot);
+    assert_eq!(other.snapshot(), snapshot);
+}
+
+#[test]
+fn restore_drop_preedit() {
+    let config = Config::default();
+    let mut engine = InputEngine::new(&config);
+    let snapshot = engine.snapshot();
+    assert_eq!(snapshot.backend, None);
+
+    engine.set_input_category(InputCategory::HANGUL);
+    engine.press_key(Key::normal(R), &config);
+
+    let ret = engine.restore(&snapshot);
+    assert!(!ret.contains(InputResult::HAS_PREEDIT));
+    assert!(!ret.contains(InputResult::HAS_COMMIT));
+    assert_eq!(engine.category(), InputCategory::LATIN);
+}
@@ -0,0 +1,109 @@
+use kime_engine_core::{
+    Config, EngineConfig, InputCategory, InputEngine, InputResult, Key, KeyCode::*,
+};
+use pretty_assertions::assert_eq;
+
+fn hangul_engine(config: &Config) -> InputEngine {
+    let mut engine = InputEngine::new(config);
+    engine.set_input_category(InputCategory::HANGUL);
+    engine
+}
+
+#[test]
+fn backspace_reopen_syllable() {
+    let config = Config::default();
+    let mut engine = hangul_engine(&config);
+
+    engine.set_surrounding_text("abc각def", "abc각".len());
+    let ret = engine.press_key(Key::normal(Backspace), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert!(ret.contains(InputResult::DELETE_SURROUNDING));
+    assert_eq!(engine.delete_surrounding_len(), 1);
+    assert_eq!(engine.preedit_str(), "가");
+    engine.clear_commit();
+
+    let ret = engine.press_key(Key::normal(Backspace), &config);
+    assert!(!ret.contains(InputResult::DELETE_SURROUNDING));
+    assert_eq!(engine.preedit_str(), "ㄱ");
+
+    engine.press_key(Key::normal(Backspace), &config);
+    assert_eq!(engine.preedit_str(), "");
+
+    // `c` can't be reopened
+    let ret = engine.press_key(Key::normal(Backspace), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+    assert!(!ret.contains(InputResult::DELETE_SURROUNDING));
+}
+
+#[test]
+fn reopen_and_continue() {
+    let config = Config::default();
+    let mut engine = hangul_engine(&config);
+
+    engine.set_surrounding_text("갃", "갃".len());
+    engine.press_key(Key::normal(Backspace), &config);
+    assert_eq!(engine.preedit_str(), "각");
+    engine.clear_commit();
+
+    engine.press_key(Key::normal(K), &config);
+    assert_eq!(engine.commit_str(), "가");
+    assert_eq!(engine.preedit_str(), "가");
+}
+
+#[test]
+fn no_surrounding_pass_backspace() {
+    let config = Config::default();
+    let mut engine = hangul_engine(&config);
+
+    let ret = engine.press_key(Key::normal(Backspace), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+    assert!(!ret.contains(InputResult::DELETE_SURROUNDING));
+}
+
+#[test]
+fn surrounding_invalid_after_commit() {
+    let config = Config::default();
+    let mut engine = hangul_engine(&config);
+
+    engine.set_surrounding_text("각", "각".len());
+    engine.press_key(Key::normal(R), &config);
+    engine.press_key(Key::normal(Space), &config);
+    assert_eq!(engine.commit_str(), "ㄱ");
+    engine.clear_commit();
+
+    let ret = engine.press_key(Key::normal(Backspace), &config);
+    assert!(!ret.contains(InputResult::DELETE_SURROUNDING));
+}
+
+#[test]
+fn latin_not_reopen() {
+    let config = Config::default();
+    let mut engine = InputEngine::new(&config);
+
+    engine.set_surrounding_text("각", "각".len());
+    let ret = engine.press_key(Key::normal(Backspace), &config);
+    assert!(!ret.contains(InputResult::CONSUMED));
+    assert!(!ret.contains(InputResult::DELETE_SURROUNDING));
+}
+
+#[test]
+fn word_commit_backspace() {
+    let mut engine_config = EngineConfig::default();
+    engine_config.hangul.word_commit = true;
+    let config = Config::new(engine_config);
+    let mut engine = hangul_engine(&config);
+
+    for code in [R, K, S, K].iter().copied() {
+        engine.press_key(Key::normal(code), &config);
+    }
+    assert_eq!(engine.preedit_str(), "가나");
+
+    engine.press_key(Key::normal(Backspace), &config);
+    engine.press_key(Key::normal(Backspace), &config);
+    assert_eq!(engine.preedit_str(), "가");
+
+    let ret = engine.press_key(Key::normal(Backspace), &config);
+    assert!(ret.contains(InputResult::CONSUMED));
+    assert_eq!(engine.preedit_str(), "ㄱ");
+    assert_eq!(engine.commit_str(), "");
+}
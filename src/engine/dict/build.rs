@@ -1,3 +1,6 @@
+#[path = "src/choseong.rs"]
+#[allow(dead_code)]
+mod choseong;
 #[path = "src/math_symbol_key.rs"]
 mod math_symbol_key;
 
@@ -63,12 +66,22 @@ struct KeySymPair<'a> {
 }
 
 type Dict = BTreeMap<&'static str, Vec<HanjaEntry>>;
+type FreqDict = BTreeMap<char, u32>;
 
-fn load_hanja_dict() -> Dict {
-    let hanja_data = include_str!("data/hanja.txt");
+/// Max count of words of each abbreviation in choseong index
+const MAX_CHOSEONG_WORDS: usize = 32;
+
+fn hanja_freq(freq_dict: &FreqDict, hanja: &str) -> u32 {
+    hanja
+        .chars()
+        .map(|c| freq_dict.get(&c).map_or(0, |n| *n))
+        .sum()
+}
+
+fn load_hanja_freq() -> FreqDict {
     let hanja_freq = include_str!("data/freq-hanja.txt");
 
-    let mut freq_dict: BTreeMap<char, u32> = BTreeMap::new();
+    let mut freq_dict = FreqDict::new();
 
     for line in hanja_freq.lines() {
         match line.split(':').next_tuple() {
@@ -83,6 +96,12 @@ fn load_hanja_dict() -> Dict {
         }
     }
 
+    freq_dict
+}
+
+fn load_hanja_dict(freq_dict: &FreqDict) -> Dict {
+    let hanja_data = include_str!("data/hanja.txt");
+
     let mut dict = Dict::new();
 
     for line in hanja_data.lines() {
@@ -106,14 +125,55 @@ fn load_hanja_dict() -> Dict {
     }
 
     for (_, entries) in dict.iter_mut() {
-        entries.sort_by_key(|e| {
-            std::cmp::Reverse(
-                e.hanja
-                    .chars()
-                    .map(|c| freq_dict.get(&c).map_or(0, |n| *n))
-                    .sum::<u32>(),
-            )
+        entries.sort_by_key(|e| std::cmp::Reverse(hanja_freq(freq_dict, e.hanja)))
+    }
+
+    dict
+}
+
+/// Words of hanja dictionary by abbreviation of choseong, more frequent word comes first
+fn choseong_index(dict: &Dict, freq_dict: &FreqDict) -> BTreeMap<String, Vec<&'static str>> {
+    let mut index: BTreeMap<String, Vec<(u32, &'static str)>> = BTreeMap::new();
+
+    for (word, entries) in dict.iter() {
+        if word.chars().nth(1).is_none() {
+            continue;
+        }
+
+        if let Some(abbreviation) = choseong::abbreviate(word) {
+            // entries are already sorted by frequency
+            let freq = hanja_freq(freq_dict, entries[0].hanja);
+            index.entry(abbreviation).or_default().push((freq, word));
+        }
+    }
+
+    index
+        .into_iter()
+        .map(|(abbreviation, mut words)| {
+            words.sort_by_key(|(freq, _)| std::cmp::Reverse(*freq));
+            let words = words
+                .into_iter()
+                .map(|(_, word)| word)
+                .take(MAX_CHOSEONG_WORDS)
+                .collect();
+            (abbreviation, words)
         })
+        .collect()
+}
+
+fn load_symbol_dict() -> Dict {
+    let mut dict = Dict::new();
+
+    for line in include_str!("data/symbol.txt").lines() {
+        if line.starts_with('#') {
+            continue;
+        }
+
+        if let Some((jamo, hanja, description)) = line.split(':').next_tuple() {
+            dict.entry(jamo)
+                .or_default()
+                .push(HanjaEntry { hanja, description });
+        }
     }
 
     dict
@@ -166,7 +226,10 @@ fn main() {
     )
     .unwrap();
 
-    for (k, values) in load_hanja_dict() {
+    let freq_dict = load_hanja_freq();
+    let hanja_dict = load_hanja_dict(&freq_dict);
+
+    for (k, values) in hanja_dict.iter() {
         write!(out, "(\"{}\", &[", k).unwrap();
         for value in values {
             write!(out, "(\"{}\", \"{}\"),", value.hanja, value.description).unwrap();
@@ -176,6 +239,30 @@ fn main() {
 
     writeln!(out, "];").unwrap();
 
+    writeln!(out, "pub static CHOSEONG_ENTRIES: &[(&str, &[&str])] = &[").unwrap();
+
+    for (k, words) in choseong_index(&hanja_dict, &freq_dict) {
+        writeln!(out, "(\"{}\", &{:?}),", k, words).unwrap();
+    }
+
+    writeln!(out, "];").unwrap();
+
+    writeln!(
+        out,
+        "pub static SYMBOL_ENTRIES: &[(&str, &[(&str, &str)])] = &[",
+    )
+    .unwrap();
+
+    for (k, values) in load_symbol_dict() {
+        write!(out, "(\"{}\", &[", k).unwrap();
+        for value in values {
+            write!(out, "({:?}, \"{}\"),", value.hanja, value.description).unwrap();
+        }
+        writeln!(out, "]),").unwrap();
+    }
+
+    writeln!(out, "];").unwrap();
+
     let symbol_map_data = include_str!("data/symbol_map.json");
     let symbol_map_data: Vec<KeySymPair> = serde_json::from_str(symbol_map_data).unwrap();
     let mut symbol_map: Vec<(SymbolKey, &str)> = Vec::new();
@@ -212,5 +299,15 @@ fn main() {
     }
     writeln!(out, "];").unwrap();
 
+    let mut en_words = include_str!("data/en_words.txt")
+        .lines()
+        .map(str::trim)
+        .filter(|word| !word.is_empty())
+        .collect::<Vec<_>>();
+    en_words.sort_unstable();
+    en_words.dedup();
+
+    writeln!(out, "pub static EN_WORDS: &[&str] = &{:?};", en_words).unwrap();
+
     out.flush().unwrap();
 }
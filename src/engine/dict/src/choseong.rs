@@ -0,0 +1,27 @@
+/// Compatibility jamo of choseong in order of hangul syllables
+pub const CHOSEONG_JAMO: [char; 19] = [
+    'ㄱ', 'ㄲ', 'ㄴ', 'ㄷ', 'ㄸ', 'ㄹ', 'ㅁ', 'ㅂ', 'ㅃ', 'ㅅ', 'ㅆ', 'ㅇ', 'ㅈ', 'ㅉ', 'ㅊ', 'ㅋ',
+    'ㅌ', 'ㅍ', 'ㅎ',
+];
+
+/// Choseong of hangul syllable e.g. `ㄷ` of `대`
+pub fn choseong_of(syllable: char) -> Option<char> {
+    match syllable {
+        '가'..='힣' => Some(CHOSEONG_JAMO[(syllable as usize - '가' as usize) / (21 * 28)]),
+        _ => None,
+    }
+}
+
+pub fn is_choseong(ch: char) -> bool {
+    CHOSEONG_JAMO.contains(&ch)
+}
+
+/// Choseong of each syllable of `word`, `None` when there is other char
+pub fn abbreviate(word: &str) -> Option<String> {
+    word.chars().map(choseong_of).collect()
+}
+
+/// Text is abbreviation of word e.g. `ㄷㅎㅁㄱ`, single jamo is not treated as abbreviation
+pub fn is_abbreviation(text: &str) -> bool {
+    text.chars().nth(1).is_some() && text.chars().all(is_choseong)
+}
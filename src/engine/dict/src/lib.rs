@@ -1,4 +1,6 @@
+pub mod choseong;
 pub mod math_symbol_key;
+pub mod user;
 mod dict {
     include!(concat!(env!("OUT_DIR"), "/dict.rs"));
 }
@@ -6,6 +8,106 @@ mod dict {
 pub use dict::UnicodeAnnotation;
 use math_symbol_key::*;
 
+pub fn lookup(hangul: &str) -> Option<&'static [(&'static str, &'static str)]> {
+    crate::dict::HANJA_ENTRIES
+        .binary_search_by_key(&hangul, |(k, _)| *k)
+        .ok()
+        .map(|idx| crate::dict::HANJA_ENTRIES[idx].1)
+}
+
+/// Symbols for single jamo e.g. `ㅁ` for shapes
+pub fn lookup_symbol(jamo: &str) -> Option<&'static [(&'static str, &'static str)]> {
+    crate::dict::SYMBOL_ENTRIES
+        .binary_search_by_key(&jamo, |(k, _)| *k)
+        .ok()
+        .map(|idx| crate::dict::SYMBOL_ENTRIES[idx].1)
+}
+
+/// Words of hanja dictionary which are abbreviated into `abbreviation` e.g. `ㄷㅎㅁㄱ`,
+/// more frequent word comes first
+pub fn lookup_choseong(abbreviation: &str) -> Option<&'static [&'static str]> {
+    crate::dict::CHOSEONG_ENTRIES
+        .binary_search_by_key(&abbreviation, |(k, _)| *k)
+        .ok()
+        .map(|idx| crate::dict::CHOSEONG_ENTRIES[idx].1)
+}
+
+/// Word of text with its entries, entries are `None` when word isn't in dictionary
+pub type Segment<'a, T> = (&'a str, Option<T>);
+
+/// Max count of chars of hanja word which [`segment_hanja`] tries
+const MAX_HANJA_WORD_LEN: usize = 8;
+
+/// Split `text` into longest words of hanja dictionary from left
+///
+/// Text which isn't in dictionary is merged into one segment with `None`
+///
+/// Single jamo is looked up in symbol table
+pub fn segment_hanja(text: &str) -> Vec<Segment<'_, &'static [(&'static str, &'static str)]>> {
+    segment_by(text, |word| lookup(word).or_else(|| lookup_symbol(word)))
+}
+
+fn segment_by<T>(text: &str, lookup: impl Fn(&str) -> Option<T>) -> Vec<Segment<'_, T>> {
+    let mut segments = Vec::new();
+    // Start of text which isn't in dictionary
+    let mut rest = 0;
+    let mut start = 0;
+
+    while start < text.len() {
+        let ends: Vec<usize> = text[start..]
+            .char_indices()
+            .skip(1)
+            .map(|(idx, _)| start + idx)
+            .chain(std::iter::once(text.len()))
+            .take(MAX_HANJA_WORD_LEN)
+            .collect();
+        let found = ends
+            .into_iter()
+            .rev()
+            .find_map(|end| Some((end, lookup(&text[start..end])?)));
+
+        match found {
+            Some((end, entries)) => {
+                if rest < start {
+                    segments.push((&text[rest..start], None));
+                }
+                segments.push((&text[start..end], Some(entries)));
+                start = end;
+                rest = end;
+            }
+            None => {
+                start += text[start..].chars().next().map_or(1, char::len_utf8);
+            }
+        }
+    }
+
+    if rest < text.len() {
+        segments.push((&text[rest..], None));
+    }
+
+    segments
+}
+
+/// Check `word` is common english word, `word` must be lowercase
+pub fn is_english_word(word: &str) -> bool {
+    crate::dict::EN_WORDS.binary_search(&word).is_ok()
+}
+
+pub fn lookup_math_symbol(keyword: &str, style: Style) -> Option<&'static str> {
+    let key = SymbolKey(keyword, style);
+    crate::dict::MATH_SYMBOL_ENTRIES
+        .binary_search_by_key(&key, |(k, _)| *k)
+        .ok()
+        .map(|idx| crate::dict::MATH_SYMBOL_ENTRIES[idx].1)
+}
+
+pub fn search_unicode_annotations(keyword: &str) -> impl Iterator<Item = UnicodeAnnotation> + '_ {
+    crate::dict::UNICODE_ANNOTATIONS
+        .iter()
+        .copied()
+        .filter(move |annotation| annotation.tts.contains(keyword))
+}
+
 #[cfg(test)]
 mod tests {
     #[test]
@@ -13,6 +115,16 @@ mod tests {
         assert_eq!(crate::lookup("가").unwrap()[0].0, "可");
     }
 
+    #[test]
+    fn segment() {
+        let segments = crate::segment_hanja("a대한민국 학교");
+        assert_eq!(segments[0], ("a", None));
+        assert_eq!(segments[1].0, "대한민국");
+        assert!(segments[1].1.is_some());
+        assert_eq!(segments[2], (" ", None));
+        assert_eq!(segments[3].0, "학교");
+    }
+
     #[test]
     fn hanja_no_empty() {
         for (k, v) in crate::dict::HANJA_ENTRIES {
@@ -20,6 +132,31 @@ mod tests {
         }
     }
 
+    #[test]
+    fn symbols() {
+        assert_eq!(crate::lookup_symbol("ㅁ").unwrap()[0].0, "＃");
+        assert!(crate::lookup_symbol("ㅂ").unwrap().contains(&("┼", "괘선")));
+        assert_eq!(crate::lookup_symbol("가"), None);
+        assert_eq!(crate::segment_hanja("ㅎ")[0].1.unwrap()[0].0, "Α");
+    }
+
+    #[test]
+    fn choseong() {
+        use crate::choseong::*;
+
+        assert_eq!(choseong_of('대'), Some('ㄷ'));
+        assert_eq!(choseong_of('힣'), Some('ㅎ'));
+        assert_eq!(abbreviate("대한민국").as_deref(), Some("ㄷㅎㅁㄱ"));
+        assert_eq!(abbreviate("대한 민국"), None);
+        assert!(is_abbreviation("ㄷㅎ"));
+        assert!(!is_abbreviation("ㄷ"));
+        assert!(!is_abbreviation("ㄷㅏ"));
+
+        assert_eq!(crate::lookup_choseong("ㄷㅎㅁㄱ"), Some(&["대한민국"][..]));
+        assert_eq!(crate::lookup_choseong("ㄷㅎ").unwrap()[0], "대학");
+        assert_eq!(crate::lookup_choseong("ㄷ"), None);
+    }
+
     #[test]
     fn math_symbols() {
         use crate::lookup_math_symbol;
@@ -44,6 +181,12 @@ mod tests {
         assert_eq!(lookup_math_symbol("R", Style::FRAK), Some("ℜ"));
     }
 
+    #[test]
+    fn english_word() {
+        assert!(crate::is_english_word("hello"));
+        assert!(!crate::is_english_word("dkssud"));
+    }
+
     #[test]
     fn unicode() {
         assert_eq!(
@@ -55,25 +198,3 @@ mod tests {
         );
     }
 }
-
-pub fn lookup(hangul: &str) -> Option<&'static [(&'static str, &'static str)]> {
-    crate::dict::HANJA_ENTRIES
-        .binary_search_by_key(&hangul, |(k, _)| *k)
-        .ok()
-        .map(|idx| crate::dict::HANJA_ENTRIES[idx].1)
-}
-
-pub fn lookup_math_symbol(keyword: &str, style: Style) -> Option<&'static str> {
-    let key = SymbolKey(keyword, style);
-    crate::dict::MATH_SYMBOL_ENTRIES
-        .binary_search_by_key(&key, |(k, _)| *k)
-        .ok()
-        .map(|idx| crate::dict::MATH_SYMBOL_ENTRIES[idx].1)
-}
-
-pub fn search_unicode_annotations(keyword: &str) -> impl Iterator<Item = UnicodeAnnotation> + '_ {
-    crate::dict::UNICODE_ANNOTATIONS
-        .iter()
-        .copied()
-        .filter(move |annotation| annotation.tts.contains(keyword))
-}
@@ -0,0 +1,432 @@
+//! Hanja dictionary of user
+//!
+//! Entries which are added by user and counts of selected candidates are saved in `$XDG_DATA_HOME/kime`,
+//! symbols for single jamo can be added in `symbol.txt` of same directory
+
+use std::{
+    collections::BTreeMap,
+    fmt::Write as _,
+    io::{self, Write as _},
+    path::{Path, PathBuf},
+    time::SystemTime,
+};
+
+/// File of entries which are added by user, its format is same as builtin dictionary `hangul:hanja:description`
+pub const USER_HANJA_FILE: &str = "hanja.txt";
+/// File of selection counts `hangul:hanja:count`
+pub const USER_HANJA_FREQ_FILE: &str = "hanja-freq.txt";
+/// File of symbols for single jamo `jamo:symbol:description`, it's only read
+pub const USER_SYMBOL_FILE: &str = "symbol.txt";
+
+/// Modified time and size of file, `None` when file doesn't exist
+pub type FileStamp = Option<(SystemTime, u64)>;
+
+/// Word of text with candidates of it
+pub type Segment<'a, 'd> = crate::Segment<'a, Vec<(&'d str, &'d str)>>;
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct UserDict {
+    /// Entries of hangul, it's ordered as added
+    entries: BTreeMap<String, Vec<(String, String)>>,
+    /// Selection count of `(hangul, hanja)`
+    freq: BTreeMap<(String, String), u32>,
+    /// Symbols of jamo from [`USER_SYMBOL_FILE`]
+    symbols: BTreeMap<String, Vec<(String, String)>>,
+}
+
+/// Parse `hangul:hanja:description` lines, lines which start with `#` are ignored
+fn parse_entries(text: &str) -> impl Iterator<Item = (&str, &str, &str)> {
+    text.lines()
+        .filter(|line| !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut parts = line.splitn(3, ':');
+            Some((parts.next()?, parts.next()?, parts.next().unwrap_or("")))
+        })
+        .filter(|(hangul, hanja, _)| !hangul.is_empty() && !hanja.is_empty())
+}
+
+/// Directory of user dictionary
+#[cfg(unix)]
+pub fn user_dict_dir() -> Option<PathBuf> {
+    xdg::BaseDirectories::with_prefix("kime")
+        .ok()
+        .map(|dirs| dirs.get_data_home())
+}
+
+/// There is no user dictionary
+#[cfg(not(unix))]
+pub fn user_dict_dir() -> Option<PathBuf> {
+    None
+}
+
+fn no_dict_dir() -> io::Error {
+    io::Error::new(io::ErrorKind::NotFound, "Can't find data directory")
+}
+
+/// Stamps of dictionary files in `dir`, dictionary is changed when they are changed
+pub fn file_stamps(dir: &Path) -> [FileStamp; 3] {
+    let stamp = |file: &str| {
+        let metadata = std::fs::metadata(dir.join(file)).ok()?;
+        Some((metadata.modified().ok()?, metadata.len()))
+    };
+
+    [
+        stamp(USER_HANJA_FILE),
+        stamp(USER_HANJA_FREQ_FILE),
+        stamp(USER_SYMBOL_FILE),
+    ]
+}
+
+impl UserDict {
+    /// Load dictionary from [`user_dict_dir`], missing file is treated as empty
+    pub fn load() -> Self {
+        user_dict_dir().map_or_else(Self::default, |dir| Self::load_from(&dir))
+    }
+
+    /// Save selection counts to [`user_dict_dir`]
+    pub fn save_freq(&self) -> io::Result<()> {
+        self.save_freq_to(&user_dict_dir().ok_or_else(no_dict_dir)?)
+    }
+
+    /// Add entries of `text` and append them to [`user_dict_dir`]
+    ///
+    /// Return count of added entries
+    pub fn import_and_save(&mut self, text: &str) -> io::Result<usize> {
+        self.import_and_save_to(&user_dict_dir().ok_or_else(no_dict_dir)?, text)
+    }
+
+    pub fn load_from(dir: &Path) -> Self {
+        let mut dict = Self::default();
+
+        if let Ok(text) = std::fs::read_to_string(dir.join(USER_HANJA_FILE)) {
+            dict.import(&text);
+        }
+
+        if let Ok(text) = std::fs::read_to_string(dir.join(USER_SYMBOL_FILE)) {
+            for (jamo, symbol, description) in parse_entries(&text) {
+                dict.symbols
+                    .entry(jamo.into())
+                    .or_default()
+                    .push((symbol.into(), description.into()));
+            }
+        }
+
+        if let Ok(text) = std::fs::read_to_string(dir.join(USER_HANJA_FREQ_FILE)) {
+            for line in text.lines() {
+                let mut parts = line.splitn(3, ':');
+
+                if let (Some(hangul), Some(hanja), Some(Ok(count))) =
+                    (parts.next(), parts.next(), parts.next().map(str::parse))
+                {
+                    dict.freq.insert((hangul.into(), hanja.into()), count);
+                }
+            }
+        }
+
+        dict
+    }
+
+    /// Replace [`USER_HANJA_FREQ_FILE`] of `dir` atomically, file of entries isn't touched
+    pub fn save_freq_to(&self, dir: &Path) -> io::Result<()> {
+        let mut freq = String::new();
+        for ((hangul, hanja), count) in self.freq.iter() {
+            writeln!(freq, "{}:{}:{}", hangul, hanja, count).ok();
+        }
+
+        std::fs::create_dir_all(dir)?;
+        let tmp = dir.join(format!(
+            "{}.{}.tmp",
+            USER_HANJA_FREQ_FILE,
+            std::process::id()
+        ));
+        std::fs::write(&tmp, freq)?;
+        std::fs::rename(&tmp, dir.join(USER_HANJA_FREQ_FILE)).inspect_err(|_| {
+            std::fs::remove_file(&tmp).ok();
+        })
+    }
+
+    /// Add entries of `text` and append added entries to [`USER_HANJA_FILE`] of `dir`,
+    /// existing lines of file e.g. comments are kept
+    ///
+    /// Return count of added entries
+    pub fn import_and_save_to(&mut self, dir: &Path, text: &str) -> io::Result<usize> {
+        let path = dir.join(USER_HANJA_FILE);
+        let mut added = String::new();
+        let mut count = 0;
+
+        // Last line of file may not be ended
+        if std::fs::read(&path).is_ok_and(|file| file.last().is_some_and(|b| *b != b'\n')) {
+            added.push('\n');
+        }
+
+        for (hangul, hanja, description) in parse_entries(text) {
+            if self.add(hangul, hanja, description) {
+                writeln!(added, "{}:{}:{}", hangul, hanja, description).ok();
+                count += 1;
+            }
+        }
+
+        if count != 0 {
+            std::fs::create_dir_all(dir)?;
+            std::fs::OpenOptions::new()
+                .create(true)
+                .append(true)
+                .open(&path)?
+                .write_all(added.as_bytes())?;
+        }
+
+        Ok(count)
+    }
+
+    /// Add entry, description of existing entry is replaced
+    ///
+    /// Return `false` when there is same entry already
+    pub fn add(&mut self, hangul: &str, hanja: &str, description: &str) -> bool {
+        let entries = self.entries.entry(hangul.into()).or_default();
+
+        match entries.iter_mut().find(|(h, _)| h == hanja) {
+            Some((_, desc)) if desc == description => false,
+            Some((_, desc)) => {
+                *desc = description.into();
+                true
+            }
+            None => {
+                entries.push((hanja.into(), description.into()));
+                true
+            }
+        }
+    }
+
+    /// Add entries of `hangul:hanja:description` lines, lines which start with `#` are ignored
+    ///
+    /// Return count of added entries
+    pub fn import(&mut self, text: &str) -> usize {
+        parse_entries(text)
+            .filter(|(hangul, hanja, description)| self.add(hangul, hanja, description))
+            .count()
+    }
+
+    /// Entries as `hangul:hanja:description` lines
+    pub fn export(&self) -> String {
+        let mut out = String::new();
+        for (hangul, hanja, description) in self.entries() {
+            writeln!(out, "{}:{}:{}", hangul, hanja, description).ok();
+        }
+        out
+    }
+
+    /// Entries which are added by user as `(hangul, hanja, description)`
+    pub fn entries(&self) -> impl Iterator<Item = (&str, &str, &str)> {
+        self.entries.iter().flat_map(|(hangul, entries)| {
+            entries.iter().map(move |(hanja, description)| {
+                (hangul.as_str(), hanja.as_str(), description.as_str())
+            })
+        })
+    }
+
+    /// Record `hanja` is selected for `hangul`
+    pub fn record(&mut self, hangul: &str, hanja: &str) {
+        *self.freq.entry((hangul.into(), hanja.into())).or_default() += 1;
+    }
+
+    /// Entries and symbols of user and builtin dictionary, more selected candidate comes first
+    ///
+    /// Abbreviation of choseong is looked up as words and their hanja
+    pub fn lookup(&self, hangul: &str) -> Option<Vec<(&str, &str)>> {
+        let mut candidates: Vec<(&str, &str)> = Vec::new();
+
+        if crate::choseong::is_abbreviation(hangul) {
+            self.abbreviation_candidates(hangul, &mut candidates);
+        }
+
+        for entries in [self.entries.get(hangul), self.symbols.get(hangul)]
+            .iter()
+            .flatten()
+        {
+            candidates.extend(entries.iter().map(|(h, d)| (h.as_str(), d.as_str())));
+        }
+
+        for entries in [crate::lookup(hangul), crate::lookup_symbol(hangul)]
+            .iter()
+            .flatten()
+        {
+            for (hanja, description) in entries.iter().copied() {
+                if !candidates.iter().any(|(h, _)| *h == hanja) {
+                    candidates.push((hanja, description));
+                }
+            }
+        }
+
+        if candidates.is_empty() {
+            return None;
+        }
+
+        candidates.sort_by_key(|(hanja, _)| {
+            std::cmp::Reverse(
+                self.freq
+                    .get(&(hangul.to_string(), hanja.to_string()))
+                    .copied()
+                    .unwrap_or_default(),
+            )
+        });
+
+        Some(candidates)
+    }
+
+    /// Words of user and builtin dictionary which are abbreviated into `abbreviation`,
+    /// each word is followed by its hanja
+    fn abbreviation_candidates<'a>(
+        &'a self,
+        abbreviation: &str,
+        candidates: &mut Vec<(&'a str, &'a str)>,
+    ) {
+        let user_words = self
+            .entries
+            .iter()
+            .filter(|(word, _)| crate::choseong::abbreviate(word).as_deref() == Some(abbreviation))
+            .map(|(word, entries)| {
+                let entries = entries.iter().map(|(h, d)| (h.as_str(), d.as_str()));
+                (word.as_str(), entries.collect::<Vec<_>>())
+            });
+        let words = crate::lookup_choseong(abbreviation)
+            .unwrap_or_default()
+            .iter()
+            .map(|word| (*word, crate::lookup(word).unwrap_or_default().to_vec()));
+
+        for (word, entries) in user_words.chain(words) {
+            if candidates.iter().any(|(c, _)| *c == word) {
+                continue;
+            }
+
+            candidates.push((word, entries.first().map_or("", |(hanja, _)| hanja)));
+            for (hanja, description) in entries {
+                if !candidates.iter().any(|(c, _)| *c == hanja) {
+                    candidates.push((hanja, description));
+                }
+            }
+        }
+    }
+
+    /// Same as [`crate::segment_hanja`] but entries of user are also used
+    ///
+    /// Abbreviation of choseong isn't split
+    pub fn segment<'a>(&self, text: &'a str) -> Vec<Segment<'a, '_>> {
+        if crate::choseong::is_abbreviation(text) {
+            return vec![(text, self.lookup(text))];
+        }
+
+        crate::segment_by(text, |word| self.lookup(word))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::UserDict;
+
+    #[test]
+    fn user_entries() {
+        let mut dict = UserDict::default();
+        assert_eq!(
+            dict.import("# comment\n가:伽:절 가\n가:可:옳을 가\n카:\n"),
+            2
+        );
+        assert!(!dict.add("가", "伽", "절 가"));
+
+        let candidates = dict.lookup("가").unwrap();
+        assert_eq!(candidates[0], ("伽", "절 가"));
+        assert_eq!(candidates[1], ("可", "옳을 가"));
+        assert_eq!(candidates.iter().filter(|(h, _)| *h == "可").count(), 1);
+
+        assert_eq!(dict.export(), "가:伽:절 가\n가:可:옳을 가\n");
+    }
+
+    #[test]
+    fn learned_order() {
+        let mut dict = UserDict::default();
+        let last = *crate::lookup("가").unwrap().last().unwrap();
+
+        dict.record("가", last.0);
+        assert_eq!(dict.lookup("가").unwrap()[0], last);
+    }
+
+    #[test]
+    fn segment_user_entries() {
+        let mut dict = UserDict::default();
+        dict.add("한자", "韓字", "");
+
+        let segments = dict.segment("대한민국, 한자!");
+        assert_eq!(segments.len(), 4);
+        assert_eq!(segments[0].0, "대한민국");
+        assert_eq!(segments[0].1.as_ref().unwrap()[0].0, "大韓民國");
+        assert_eq!(segments[1], (", ", None));
+        assert_eq!(segments[2].0, "한자");
+        assert_eq!(segments[2].1.as_ref().unwrap()[0], ("韓字", ""));
+        assert_eq!(segments[3], ("!", None));
+
+        assert!(dict
+            .segment("hello")
+            .iter()
+            .all(|(_, entries)| entries.is_none()));
+    }
+
+    #[test]
+    fn abbreviation() {
+        let mut dict = UserDict::default();
+        dict.add("대한제국", "大韓帝國", "대한제국");
+
+        let candidates = dict.lookup("ㄷㅎㅈㄱ").unwrap();
+        assert_eq!(
+            candidates,
+            [("대한제국", "大韓帝國"), ("大韓帝國", "대한제국")]
+        );
+
+        let candidates = dict.lookup("ㄷㅎㅁㄱ").unwrap();
+        assert_eq!(
+            candidates,
+            [("대한민국", "大韓民國"), ("大韓民國", "대한민국")]
+        );
+
+        dict.record("ㄷㅎㅁㄱ", "大韓民國");
+        assert_eq!(dict.lookup("ㄷㅎㅁㄱ").unwrap()[0].0, "大韓民國");
+
+        let segments = dict.segment("ㄷㅎㅁㄱ");
+        assert_eq!(segments.len(), 1);
+        assert_eq!(dict.segment("ㅁ")[0].1.as_ref().unwrap()[0].0, "＃");
+    }
+
+    #[test]
+    fn save_load() {
+        let dir = std::env::temp_dir().join(format!("kime-user-dict-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join(super::USER_HANJA_FILE);
+        std::fs::write(&path, "# comment").unwrap();
+
+        let mut dict = UserDict::default();
+        assert_eq!(
+            dict.import_and_save_to(&dir, "대한:大韓:\n한자:韓字:\n")
+                .unwrap(),
+            2
+        );
+        assert_eq!(dict.import_and_save_to(&dir, "대한:大韓:").unwrap(), 0);
+        let stamps = super::file_stamps(&dir);
+        dict.record("대한", "大韓");
+        dict.save_freq_to(&dir).unwrap();
+        assert_ne!(super::file_stamps(&dir), stamps);
+
+        // Entries are appended to file of user
+        assert_eq!(
+            std::fs::read_to_string(&path).unwrap(),
+            "# comment\n대한:大韓:\n한자:韓字:\n"
+        );
+        assert_eq!(UserDict::load_from(&dir), dict);
+        assert_eq!(dict.segment("대한")[0].0, "대한");
+
+        std::fs::write(dir.join(super::USER_SYMBOL_FILE), "ㅁ:♥:하트\n").unwrap();
+        let dict = UserDict::load_from(&dir);
+        let symbols = dict.lookup("ㅁ").unwrap();
+        assert_eq!(symbols[0], ("♥", "하트"));
+        assert_eq!(symbols[1], ("＃", "도형"));
+
+        std::fs::remove_dir_all(dir).ok();
+    }
+}
@@ -2,6 +2,7 @@
 #include "str_buf.h"
 
 #include <stdio.h>
+#include <string.h>
 
 static GType KIME_TYPE_IM_CONTEXT = 0;
 // for many buggy gtk apps
@@ -23,6 +24,7 @@ static const guint NOT_ENGLISH_MASK =
 typedef struct _KeyRet {
   gboolean bypassed;
   gboolean has_preedit;
+  gboolean has_bypass_keys;
 } KeyRet;
 
 typedef struct KimeSignals {
@@ -96,10 +98,62 @@ void commit(KimeImContext *ctx) {
   ctx->buf.len = 0;
 }
 
-KeyRet process_input_result(KimeImContext *ctx, KimeInputResult ret) {
+// put keys which were held as a part of hotkey trigger after the key, client
+// never got them
+void bypass_keys(KimeImContext *ctx, EventType *key) {
+  uintptr_t count = kime_engine_bypass_key_count(ctx->engine);
+
+  for (uintptr_t i = 0; i < count; i++) {
+    KimeBypassKey bypass = kime_engine_bypass_key(ctx->engine, i);
+    guint state = HANDLED_MASK | BYPASS_MASK;
+
+    if (bypass.state & KimeModifierState_SHIFT) {
+      state |= GDK_SHIFT_MASK;
+    }
+
+    if (bypass.state & KimeModifierState_CONTROL) {
+      state |= GDK_CONTROL_MASK;
+    }
+
+    if (bypass.state & KimeModifierState_ALT) {
+      state |= GDK_ALT_MASK;
+    }
+
+    if (bypass.state & KimeModifierState_SUPER) {
+      state |= GDK_SUPER_MASK;
+    }
+
+#if GTK_CHECK_VERSION(3, 98, 4)
+    for (int press = 1; press >= 0; press--) {
+      gtk_im_context_filter_key(
+          GTK_IM_CONTEXT(ctx), press, gdk_event_get_surface(key),
+          gdk_event_get_device(key), gdk_event_get_time(key),
+          bypass.hardware_code, state, 0);
+    }
+#else
+    GdkEventKey *ev = (GdkEventKey *)gdk_event_copy((GdkEvent *)key);
+    ev->hardware_keycode = bypass.hardware_code;
+    ev->state = state;
+    gdk_keymap_translate_keyboard_state(
+        gdk_keymap_get_for_display(gdk_window_get_display(ev->window)),
+        bypass.hardware_code, state & ~(HANDLED_MASK | BYPASS_MASK), ev->group,
+        &ev->keyval, NULL, NULL, NULL);
+    ev->type = GDK_KEY_PRESS;
+    gdk_event_put((GdkEvent *)ev);
+    ev->type = GDK_KEY_RELEASE;
+    gdk_event_put((GdkEvent *)ev);
+    gdk_event_free((GdkEvent *)ev);
+#endif
+  }
+}
+
+// key is NULL when result isn't from key event
+KeyRet process_input_result(KimeImContext *ctx, KimeInputResult ret,
+                            EventType *key) {
   KeyRet key_ret;
   key_ret.bypassed = (ret & KimeInputResult_CONSUMED) == 0;
   key_ret.has_preedit = (ret & KimeInputResult_HAS_PREEDIT) != 0;
+  key_ret.has_bypass_keys = (ret & KimeInputResult_BYPASS_KEYS) != 0;
 
   if (ret & KimeInputResult_NOT_READY) {
     ctx->engine_ready = FALSE;
@@ -116,9 +170,22 @@ KeyRet process_input_result(KimeImContext *ctx, KimeInputResult ret) {
     kime_engine_update_layout_state(ctx->engine);
   }
 
+  // replaced text is deleted before commit
+  if (ret & KimeInputResult_DELETE_SURROUNDING) {
+    gint len = (gint)kime_engine_delete_surrounding_len(ctx->engine);
+    gtk_im_context_delete_surrounding(GTK_IM_CONTEXT(ctx), -len, len);
+  }
+
   if (ret & KimeInputResult_HAS_COMMIT) {
     str_buf_set_str(&ctx->buf, kime_engine_commit_str(ctx->engine));
     commit(ctx);
+  }
+
+  if ((ret & KimeInputResult_BYPASS_KEYS) && key) {
+    bypass_keys(ctx, key);
+  }
+
+  if (ret & (KimeInputResult_HAS_COMMIT | KimeInputResult_BYPASS_KEYS)) {
     kime_engine_clear_commit(ctx->engine);
   }
 
@@ -134,8 +201,9 @@ void focus_in(GtkIMContext *im) {
 
   if (!ctx->engine_ready) {
     if (kime_engine_check_ready(ctx->engine)) {
-      process_input_result(ctx, kime_engine_end_ready(ctx->engine));
+      // result can be NOT_READY again when hanja shows next segment
       ctx->engine_ready = TRUE;
+      process_input_result(ctx, kime_engine_end_ready(ctx->engine), NULL);
     }
   }
 }
@@ -194,20 +262,96 @@ gboolean commit_event(KimeImContext *ctx, GdkModifierType state, guint keyval) {
   return FALSE;
 }
 
-KeyRet on_key_input(KimeImContext *ctx, guint16 code, KimeModifierState state) {
+// surrounding text is invalid after commit so it's retrieved for each key
+void update_surrounding(KimeImContext *ctx) {
+  gchar *text = NULL;
+  gint cursor = 0;
+#if GTK_CHECK_VERSION(4, 2, 0)
+  gint anchor = 0;
+  gboolean has_surrounding = gtk_im_context_get_surrounding_with_selection(
+      GTK_IM_CONTEXT(ctx), &text, &cursor, &anchor);
+#else
+  gboolean has_surrounding =
+      gtk_im_context_get_surrounding(GTK_IM_CONTEXT(ctx), &text, &cursor);
+#endif
+
+  if (has_surrounding) {
+    KimeRustStr s = {(const uint8_t *)text, strlen(text)};
+    kime_engine_set_surrounding_text(ctx->engine, s, (uintptr_t)cursor);
+    g_free(text);
+  } else {
+    kime_engine_clear_surrounding_text(ctx->engine);
+  }
+}
+
+KeyRet on_key_input(KimeImContext *ctx, EventType *key, guint16 code,
+                    KimeModifierState state) {
+  update_surrounding(ctx);
+
   KimeInputResult ret =
-      kime_engine_press_key(ctx->engine, ctx->config, code, state);
+      kime_engine_press_key(ctx->engine, ctx->config, code, state,
+                            gdk_event_get_time((GdkEvent *)key));
 
 #if DEBUG
   debug("(%d, %d, %d)", code, state, ret);
 #endif
 
-  return process_input_result(ctx, ret);
+  return process_input_result(ctx, ret, key);
+}
+
+KimeModifierState to_kime_state(GdkModifierType state) {
+  KimeModifierState kime_state = 0;
+
+  if (state & GDK_SHIFT_MASK) {
+    kime_state |= KimeModifierState_SHIFT;
+  }
+
+  if (state & GDK_ALT_MASK) {
+    kime_state |= KimeModifierState_ALT;
+  }
+
+  if (state & GDK_CONTROL_MASK) {
+    kime_state |= KimeModifierState_CONTROL;
+  }
+
+  if (state & GDK_SUPER_MASK) {
+    kime_state |= KimeModifierState_SUPER;
+  }
+
+  return kime_state;
+}
+
+// release can end pending input e.g. moachigi, it's only consumed when
+// its press was consumed as a part of hotkey trigger
+gboolean on_key_release(KimeImContext *ctx, EventType *key, guint16 code,
+                        GdkModifierType state) {
+  // put by bypass_keys
+  if (state & HANDLED_MASK) {
+    return FALSE;
+  }
+
+  KimeInputResult ret =
+      kime_engine_release_key(ctx->engine, ctx->config, code,
+                              to_kime_state(state),
+                              gdk_event_get_time((GdkEvent *)key));
+
+  if (ret) {
+    // commit comes before preedit which replaces it
+    gboolean consumed = !process_input_result(ctx, ret, key).bypassed;
+    update_preedit(ctx);
+    return consumed;
+  }
+
+  return FALSE;
 }
 
 gboolean filter_keypress(GtkIMContext *im, EventType *key) {
   KIME_IM_CONTEXT(im);
 #if GTK_CHECK_VERSION(3, 98, 4)
+  if (gdk_event_get_event_type(key) == GDK_KEY_RELEASE) {
+    return on_key_release(ctx, key, gdk_key_event_get_keycode(key),
+                          gdk_event_get_modifier_state(key));
+  }
   if (gdk_event_get_event_type(key) != GDK_KEY_PRESS) {
     return FALSE;
   }
@@ -215,6 +359,9 @@ gboolean filter_keypress(GtkIMContext *im, EventType *key) {
   guint keyval = gdk_key_event_get_keyval(key);
   GdkModifierType state = gdk_event_get_modifier_state(key);
 #else
+  if (key->type == GDK_KEY_RELEASE) {
+    return on_key_release(ctx, key, key->hardware_keycode, key->state);
+  }
   if (key->type != GDK_KEY_PRESS) {
     return FALSE;
   }
@@ -235,27 +382,10 @@ gboolean filter_keypress(GtkIMContext *im, EventType *key) {
     }
   }
 
-  KimeModifierState kime_state = 0;
-
-  if (state & GDK_SHIFT_MASK) {
-    kime_state |= KimeModifierState_SHIFT;
-  }
+  KeyRet key_ret = on_key_input(ctx, key, code, to_kime_state(state));
 
-  if (state & GDK_ALT_MASK) {
-    kime_state |= KimeModifierState_ALT;
-  }
-
-  if (state & GDK_CONTROL_MASK) {
-    kime_state |= KimeModifierState_CONTROL;
-  }
-
-  if (state & GDK_SUPER_MASK) {
-    kime_state |= KimeModifierState_SUPER;
-  }
-
-  KeyRet key_ret = on_key_input(ctx, code, kime_state);
-
-  if (ctx->preedit_visible || key_ret.has_preedit) {
+  // bypass keys are put before the key
+  if (ctx->preedit_visible || key_ret.has_preedit || key_ret.has_bypass_keys) {
     guint mask = HANDLED_MASK;
 
     if (key_ret.bypassed) {
@@ -367,6 +497,12 @@ void im_context_init(KimeImContext *ctx, KimeImContextClass *klass) {
   ctx->signals = klass->signals;
   ctx->engine = kime_engine_new(klass->config);
   ctx->config = klass->config;
+
+  const gchar *prgname = g_get_prgname();
+  if (prgname) {
+    KimeRustStr app_id = {(const uint8_t *)prgname, strlen(prgname)};
+    kime_engine_set_app_id(ctx->engine, ctx->config, app_id);
+  }
 }
 
 void im_context_finalize(GObject *obj) {
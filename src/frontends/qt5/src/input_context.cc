@@ -56,7 +56,8 @@ void KimeInputContext::invokeAction(QInputMethod::Action action,
 }
 
 bool KimeInputContext::filterEvent(const QEvent *event) {
-  if (event->type() != QEvent::KeyPress) {
+  if (event->type() != QEvent::KeyPress &&
+      event->type() != QEvent::KeyRelease) {
     return false;
   }
 
@@ -81,10 +82,35 @@ bool KimeInputContext::filterEvent(const QEvent *event) {
     state |= kime::ModifierState_SUPER;
   }
 
+  if (event->type() == QEvent::KeyRelease) {
+    // release can end pending input e.g. moachigi, it's only consumed when
+    // its press was consumed as a part of hotkey trigger
+    kime::InputResult ret = kime_engine_release_key(
+        this->engine, this->config, (uint16_t)keyevent->nativeScanCode(),
+        state, (uint32_t)keyevent->timestamp());
+    if (ret) {
+      return this->process_input_result(ret);
+    }
+    return false;
+  }
+
+  this->update_surrounding();
+
   kime::InputResult ret = kime_engine_press_key(
-      this->engine, this->config, (uint16_t)keyevent->nativeScanCode(), state);
+      this->engine, this->config, (uint16_t)keyevent->nativeScanCode(), state,
+      (uint32_t)keyevent->timestamp());
 
-  return this->process_input_result(ret);
+  bool consumed = this->process_input_result(ret);
+
+  if (consumed) {
+    this->held_keys.insert(
+        keyevent->nativeScanCode(),
+        HeldKey{keyevent->key(), keyevent->modifiers(),
+                keyevent->nativeVirtualKey(), keyevent->nativeModifiers(),
+                keyevent->text()});
+  }
+
+  return consumed;
 }
 
 void KimeInputContext::preedit_str(kime::RustStr s) {
@@ -102,18 +128,59 @@ void KimeInputContext::preedit_str(kime::RustStr s) {
   QCoreApplication::sendEvent(this->focus_object, &e);
 }
 
-void KimeInputContext::commit_str(kime::RustStr s) {
+void KimeInputContext::commit_str(kime::RustStr s, int delete_len) {
   if (!this->focus_object) {
     return;
   }
 
   QInputMethodEvent e;
-  if (s.len) {
-    e.setCommitString(QString::fromUtf8((const char *)(s.ptr), s.len));
+  if (s.len || delete_len) {
+    e.setCommitString(QString::fromUtf8((const char *)(s.ptr), s.len),
+                      -delete_len, delete_len);
   }
   QCoreApplication::sendEvent(this->focus_object, &e);
 }
 
+void KimeInputContext::update_surrounding() {
+  this->surrounding.clear();
+
+  if (!this->focus_object) {
+    kime::kime_engine_clear_surrounding_text(this->engine);
+    return;
+  }
+
+  QInputMethodQueryEvent query(Qt::ImSurroundingText | Qt::ImCursorPosition);
+  QCoreApplication::sendEvent(this->focus_object, &query);
+
+  QVariant text = query.value(Qt::ImSurroundingText);
+  QVariant cursor = query.value(Qt::ImCursorPosition);
+
+  if (!text.isValid() || !cursor.isValid()) {
+    kime::kime_engine_clear_surrounding_text(this->engine);
+    return;
+  }
+
+  this->surrounding = text.toString().left(cursor.toInt());
+  QByteArray utf8 = this->surrounding.toUtf8();
+  kime::RustStr s{(const uint8_t *)utf8.constData(), (uintptr_t)utf8.size()};
+  kime::kime_engine_set_surrounding_text(this->engine, s, s.len);
+}
+
+// engine counts chars but Qt counts UTF-16 code units
+int KimeInputContext::surrounding_units(size_t len) const {
+  int units = 0;
+  int end = this->surrounding.length();
+
+  for (size_t i = 0; i < len && units < end; ++i) {
+    units += units + 1 < end &&
+                     this->surrounding.at(end - units - 1).isLowSurrogate()
+                 ? 2
+                 : 1;
+  }
+
+  return units;
+}
+
 bool KimeInputContext::process_input_result(kime::InputResult ret) {
   if (ret & kime::InputResult_LANGUAGE_CHANGED) {
     kime::kime_engine_update_layout_state(this->engine);
@@ -132,11 +199,27 @@ bool KimeInputContext::process_input_result(kime::InputResult ret) {
     }
   }
 
-  if (ret & (kime::InputResult_HAS_COMMIT)) {
+  // replaced text is deleted with commit
+  int delete_len = 0;
+  if (ret & kime::InputResult_DELETE_SURROUNDING) {
+    delete_len = this->surrounding_units(
+        kime::kime_engine_delete_surrounding_len(this->engine));
+  }
+
+  if (ret & kime::InputResult_HAS_COMMIT) {
 #ifdef DEBUG
     KIME_DEBUG << "Commit\n";
 #endif
-    commit_str(kime::kime_engine_commit_str(this->engine));
+    commit_str(kime::kime_engine_commit_str(this->engine), delete_len);
+  } else if (delete_len) {
+    commit_str(kime::RustStr{nullptr, 0}, delete_len);
+  }
+
+  if (ret & kime::InputResult_BYPASS_KEYS) {
+    this->bypass_keys();
+  }
+
+  if (ret & (kime::InputResult_HAS_COMMIT | kime::InputResult_BYPASS_KEYS)) {
     kime::kime_engine_clear_commit(this->engine);
   }
 
@@ -151,3 +234,24 @@ bool KimeInputContext::process_input_result(kime::InputResult ret) {
 
   return !!(ret & kime::InputResult_CONSUMED);
 }
+
+// send keys which were held as a part of hotkey trigger, client never got them
+void KimeInputContext::bypass_keys() {
+  uintptr_t count = kime::kime_engine_bypass_key_count(this->engine);
+
+  for (uintptr_t i = 0; i < count; i++) {
+    kime::BypassKey bypass = kime::kime_engine_bypass_key(this->engine, i);
+    auto held = this->held_keys.constFind(bypass.hardware_code);
+
+    if (!this->focus_object || held == this->held_keys.constEnd()) {
+      continue;
+    }
+
+    for (QEvent::Type type : {QEvent::KeyPress, QEvent::KeyRelease}) {
+      QKeyEvent e(type, held->key, held->modifiers, bypass.hardware_code,
+                  held->native_virtual_key, held->native_modifiers,
+                  held->text);
+      QCoreApplication::sendEvent(this->focus_object, &e);
+    }
+  }
+}
@@ -2,6 +2,7 @@
 
 #include "kime-qt5.hpp"
 
+#include <QtCore/QHash>
 #include <QtGui/QInputMethodEvent>
 #include <qpa/qplatforminputcontext.h>
 
@@ -24,13 +25,28 @@ public:
   void setFocusObject(QObject *object) override;
 
 private:
-  void commit_str(kime::RustStr s);
+  void commit_str(kime::RustStr s, int delete_len = 0);
   void preedit_str(kime::RustStr s);
   bool process_input_result(kime::InputResult ret);
+  void bypass_keys();
+  void update_surrounding();
+  int surrounding_units(size_t len) const;
 
   bool visible = false;
   bool engine_ready = true;
   QList<QInputMethodEvent::Attribute> attributes;
+  // text before cursor which is set as surrounding text of engine
+  QString surrounding;
+  // consumed key press of each scan code, it's sent to focus object again
+  // when engine bypasses the key
+  struct HeldKey {
+    int key;
+    Qt::KeyboardModifiers modifiers;
+    quint32 native_virtual_key;
+    quint32 native_modifiers;
+    QString text;
+  };
+  QHash<quint32, HeldKey> held_keys;
   const kime::Config *config = nullptr;
   kime::InputEngine *engine = nullptr;
   QObject *focus_object = nullptr;
@@ -19,5 +19,10 @@ KimePlatformInputContextPlugin::~KimePlatformInputContextPlugin() {
 QPlatformInputContext *
 KimePlatformInputContextPlugin::create(const QString &key,
                                        const QStringList &param_list) {
+  QByteArray app_id = QCoreApplication::applicationName().toUtf8();
+  kime::RustStr app_id_str{(const uint8_t *)app_id.constData(),
+                           (uintptr_t)app_id.size()};
+  kime::kime_engine_set_app_id(this->engine, this->config, app_id_str);
+
   return new KimeInputContext(this->engine, this->config);
 }
@@ -1,8 +1,14 @@
-use std::time::{Duration, Instant};
+use std::{
+    sync::atomic::{AtomicBool, Ordering},
+    time::{Duration, Instant},
+};
 
 use wayland_client::{
     event_enum,
-    protocol::{wl_keyboard::KeyState, wl_seat::WlSeat},
+    protocol::{
+        wl_keyboard::{KeyState, KeymapFormat},
+        wl_seat::WlSeat,
+    },
     DispatchData, Display, Filter, GlobalManager, Main,
 };
 
@@ -11,19 +17,28 @@ use wayland_protocols::misc::zwp_input_method_v2::client::{
     zwp_input_method_manager_v2::ZwpInputMethodManagerV2,
     zwp_input_method_v2::{Event as ImEvent, ZwpInputMethodV2},
 };
+use wayland_protocols::wlr::unstable::foreign_toplevel::v1::client::{
+    zwlr_foreign_toplevel_handle_v1::{Event as ToplevelEvent, State as ToplevelState},
+    zwlr_foreign_toplevel_manager_v1::{
+        Event as ToplevelManagerEvent, ZwlrForeignToplevelManagerV1,
+    },
+};
 use zwp_virtual_keyboard::virtual_keyboard_unstable_v1::{
     zwp_virtual_keyboard_manager_v1::ZwpVirtualKeyboardManagerV1,
     zwp_virtual_keyboard_v1::ZwpVirtualKeyboardV1,
 };
 
 use kime_engine_cffi::{
-    Config, InputEngine, InputResult, InputResult_CONSUMED, InputResult_HAS_COMMIT,
+    BypassKey, Config, ConfigWatcher, InputEngine, InputResult, InputResult_BYPASS_KEYS,
+    InputResult_CONSUMED, InputResult_DELETE_SURROUNDING, InputResult_HAS_COMMIT,
     InputResult_HAS_PREEDIT, InputResult_LANGUAGE_CHANGED, InputResult_NOT_READY, ModifierState,
-    ModifierState_ALT, ModifierState_CONTROL, ModifierState_SHIFT, ModifierState_SUPER,
+    ModifierState_ALT, ModifierState_CAPSLOCK, ModifierState_CONTROL, ModifierState_LEVEL3,
+    ModifierState_NUMLOCK, ModifierState_SHIFT, ModifierState_SUPER, PreeditAttribute,
 };
 
 use mio::{unix::SourceFd, Events as MioEvents, Interest, Poll, Token};
 use mio_timerfd::{ClockId, TimerFd};
+use xkbcommon::xkb;
 
 event_enum! {
     Events |
@@ -34,6 +49,10 @@ event_enum! {
 struct InputMethodState {
     activate: bool,
     deactivate: bool,
+    /// Surrounding text and byte offset of cursor
+    surrounding: Option<(String, usize)>,
+    /// Selected text of surrounding text
+    selection: String,
 }
 
 impl Default for InputMethodState {
@@ -41,6 +60,8 @@ impl Default for InputMethodState {
         Self {
             activate: false,
             deactivate: false,
+            surrounding: None,
+            selection: String::new(),
         }
     }
 }
@@ -94,6 +115,10 @@ struct KimeContext {
     config: Config,
     engine: InputEngine,
     mod_state: ModifierState,
+    /// Depressed, latched, locked modifiers and group which client has
+    mods: (u32, u32, u32, u32),
+    /// Time of key event which is being processed
+    key_time: u32,
     current_state: InputMethodState,
     pending_state: InputMethodState,
     vk: Main<ZwpVirtualKeyboardV1>,
@@ -101,8 +126,14 @@ struct KimeContext {
     grab: Main<ZwpInputMethodKeyboardGrabV2>,
     engine_ready: bool,
     keymap_init: bool,
+    /// Resolve keysym of key, `None` until keymap is received
+    xkb_state: Option<xkb::State>,
     grab_activate: bool,
     serial: u32,
+    /// App id of activated toplevel, only known when compositor support foreign toplevel
+    focused_app: Option<String>,
+    /// App id which engine is using now
+    engine_app: Option<String>,
     // Have to consern Multi seats?
 
     // Key repeat contexts
@@ -133,11 +164,16 @@ impl KimeContext {
             engine: InputEngine::new(&config),
             config,
             mod_state: 0,
+            mods: (0, 0, 0, 0),
+            key_time: 0,
             current_state: InputMethodState::default(),
             pending_state: InputMethodState::default(),
             serial: 0,
+            focused_app: None,
+            engine_app: None,
             engine_ready: true,
             keymap_init: false,
+            xkb_state: None,
             grab_activate: false,
             vk,
             im,
@@ -151,6 +187,39 @@ impl KimeContext {
         data.get::<Self>().unwrap()
     }
 
+    pub fn reload_config(&mut self) {
+        log::info!("Reload config");
+        let config = Config::load();
+        let ret = self.engine.update_config(&self.config, &config);
+        self.config = config;
+
+        if self.grab_activate {
+            self.process_input_result(ret);
+        }
+    }
+
+    pub fn set_focused_app(&mut self, app_id: String) {
+        self.focused_app = Some(app_id);
+
+        if self.grab_activate {
+            self.update_app_id();
+        }
+    }
+
+    /// Let engine know focused app when it has changed
+    fn update_app_id(&mut self) {
+        if self.focused_app == self.engine_app {
+            return;
+        }
+
+        if let Some(ref app_id) = self.focused_app {
+            log::debug!("Focused app: {}", app_id);
+            let ret = self.engine.set_app_id(&self.config, app_id);
+            self.engine_app = self.focused_app.clone();
+            self.process_input_result(ret);
+        }
+    }
+
     fn process_input_result(&mut self, ret: InputResult) -> bool {
         if ret & InputResult_NOT_READY != 0 {
             self.engine_ready = false;
@@ -167,8 +236,22 @@ impl KimeContext {
             self.clear_preedit();
         }
 
+        if ret & InputResult_DELETE_SURROUNDING != 0 {
+            self.delete_surrounding(self.engine.delete_surrounding_len());
+        }
+
         if ret & InputResult_HAS_COMMIT != 0 {
             self.commit_string(self.engine.commit_str().into());
+        }
+
+        if ret & InputResult_BYPASS_KEYS != 0 {
+            let keys = self.engine.bypass_keys().collect::<Vec<_>>();
+            self.bypass_keys(&keys);
+        }
+
+        if ret & (InputResult_HAS_COMMIT | InputResult_DELETE_SURROUNDING | InputResult_BYPASS_KEYS)
+            != 0
+        {
             self.engine.clear_commit();
         }
 
@@ -177,6 +260,40 @@ impl KimeContext {
         ret & InputResult_CONSUMED == 0
     }
 
+    /// Press and release keys which were held as a part of hotkey trigger, client never got them
+    fn bypass_keys(&mut self, keys: &[BypassKey]) {
+        let (depressed, latched, locked, group) = self.mods;
+
+        for key in keys {
+            // Evdev code is offset by 8 from hardware code
+            let code = match (key.hardware_code as u32).checked_sub(8) {
+                Some(code) => code,
+                None => continue,
+            };
+            let mut mods = 0;
+            if key.state & ModifierState_SHIFT != 0 {
+                mods |= 0x1;
+            }
+            if key.state & ModifierState_CONTROL != 0 {
+                mods |= 0x4;
+            }
+            if key.state & ModifierState_ALT != 0 {
+                mods |= 0x8;
+            }
+            if key.state & ModifierState_SUPER != 0 {
+                mods |= 0x40;
+            }
+            if key.state & ModifierState_LEVEL3 != 0 {
+                mods |= 0x80;
+            }
+            self.vk.modifiers(mods, latched, locked, group);
+            self.vk.key(self.key_time, code, KeyState::Pressed as _);
+            self.vk.key(self.key_time, code, KeyState::Released as _);
+        }
+
+        self.vk.modifiers(depressed, latched, locked, group);
+    }
+
     fn commit(&mut self) {
         self.im.commit(self.serial);
         self.serial += 1;
@@ -188,13 +305,50 @@ impl KimeContext {
         }
     }
 
+    /// Delete `len` chars before cursor
+    ///
+    /// Deletion is skipped when cursor from compositor isn't valid
+    fn delete_surrounding(&mut self, len: usize) {
+        if let Some((ref mut text, ref mut cursor)) = self.current_state.surrounding {
+            let bytes = match text.get(..*cursor) {
+                Some(before) => before
+                    .chars()
+                    .rev()
+                    .take(len)
+                    .map(char::len_utf8)
+                    .sum::<usize>(),
+                None => {
+                    log::warn!("Invalid surrounding cursor: {}", cursor);
+                    return;
+                }
+            };
+            let start = match cursor.checked_sub(bytes) {
+                Some(start) => start,
+                None => return,
+            };
+            text.replace_range(start..*cursor, "");
+            *cursor = start;
+            self.im.delete_surrounding_text(bytes as _, 0);
+        }
+    }
+
     fn clear_preedit(&mut self) {
         self.im.set_preedit_string(String::new(), -1, -1);
     }
 
     fn preedit(&mut self, s: String) {
-        let len = s.len();
-        self.im.set_preedit_string(s, 0, len as _);
+        let (begin, end) = match self
+            .engine
+            .preedit_segments()
+            .find(|segment| segment.attribute == PreeditAttribute::Selected)
+        {
+            Some(segment) => (segment.start, segment.end),
+            None => {
+                let cursor = self.engine.preedit_cursor();
+                (cursor, cursor)
+            }
+        };
+        self.im.set_preedit_string(s, begin as _, end as _);
     }
 
     pub fn handle_im_ev(&mut self, ev: ImEvent) {
@@ -209,17 +363,35 @@ impl KimeContext {
                 log::error!("Receive Unavailable event is another server already running?");
                 panic!("Unavailable")
             }
+            ImEvent::SurroundingText {
+                text,
+                cursor,
+                anchor,
+            } => {
+                let (start, end) = if anchor < cursor {
+                    (anchor, cursor)
+                } else {
+                    (cursor, anchor)
+                };
+                self.pending_state.selection = text
+                    .get(start as usize..end as usize)
+                    .unwrap_or_default()
+                    .into();
+                self.pending_state.surrounding = Some((text, cursor as usize));
+            }
             ImEvent::Done => {
                 if !self.current_state.activate && self.pending_state.activate {
                     self.engine.update_layout_state();
                     if !self.engine_ready {
                         if self.engine.check_ready() {
+                            // Result can be NOT_READY again when hanja shows next segment
+                            self.engine_ready = true;
                             let ret = self.engine.end_ready();
                             self.process_input_result(ret);
-                            self.engine_ready = true;
                         }
                     }
                     self.grab_activate = true;
+                    self.update_app_id();
                 } else if !self.current_state.deactivate && self.pending_state.deactivate {
                     // Focus lost, reset states
                     if self.engine_ready {
@@ -233,12 +405,32 @@ impl KimeContext {
                         *press_state = PressState::NotPressing
                     }
                 }
+                match self.pending_state.surrounding {
+                    Some((ref text, cursor)) => {
+                        self.engine.set_surrounding_text(text, cursor);
+                        self.engine
+                            .set_selection_text(&self.pending_state.selection);
+                    }
+                    None => self.engine.clear_surrounding_text(),
+                }
                 self.current_state = std::mem::take(&mut self.pending_state);
             }
             _ => {}
         }
     }
 
+    /// Keysym of evdev key when hangul layout is interpreted by keysym
+    fn keysym(&self, key: u32) -> Option<u32> {
+        if !self.config.keysym_layout() {
+            return None;
+        }
+
+        match self.xkb_state.as_ref()?.key_get_one_sym(key + 8) {
+            0 => None,
+            keysym => Some(keysym),
+        }
+    }
+
     pub fn handle_key_ev(&mut self, ev: KeyEvent) {
         match ev {
             KeyEvent::Keymap { fd, format, size } => {
@@ -246,6 +438,9 @@ impl KimeContext {
                     self.vk.keymap(format as _, fd, size);
                     self.keymap_init = true;
                 }
+                if format == KeymapFormat::XkbV1 {
+                    self.xkb_state = load_xkb_state(fd, size);
+                }
                 unsafe {
                     libc::close(fd);
                 }
@@ -254,11 +449,21 @@ impl KimeContext {
                 state, key, time, ..
             } => {
                 // NOTE: Never read `serial` of KeyEvent. You should rely on serial of KimeContext
+                self.key_time = time;
                 if state == KeyState::Pressed {
                     if self.grab_activate {
-                        let ret =
-                            self.engine
-                                .press_key(&self.config, (key + 8) as u16, self.mod_state);
+                        let ret = match self.keysym(key) {
+                            Some(keysym) => {
+                                self.engine
+                                    .press_keysym(&self.config, keysym, self.mod_state, time)
+                            }
+                            None => self.engine.press_key(
+                                &self.config,
+                                (key + 8) as u16,
+                                self.mod_state,
+                                time,
+                            ),
+                        };
 
                         let bypassed = self.process_input_result(ret);
 
@@ -300,7 +505,32 @@ impl KimeContext {
                         }
                     }
 
-                    self.vk.key(time, key, state as _);
+                    // Release of key consumed as a part of hotkey trigger is also consumed
+                    let mut consumed = false;
+
+                    if self.grab_activate {
+                        let ret = match self.keysym(key) {
+                            Some(keysym) => self.engine.release_keysym(
+                                &self.config,
+                                keysym,
+                                self.mod_state,
+                                time,
+                            ),
+                            None => self.engine.release_key(
+                                &self.config,
+                                (key + 8) as u16,
+                                self.mod_state,
+                                time,
+                            ),
+                        };
+                        if ret != 0 {
+                            consumed = !self.process_input_result(ret);
+                        }
+                    }
+
+                    if !consumed {
+                        self.vk.key(time, key, state as _);
+                    }
                 }
             }
             KeyEvent::Modifiers {
@@ -323,6 +553,21 @@ impl KimeContext {
                 if mods_depressed & 0x40 != 0 {
                     self.mod_state |= ModifierState_SUPER;
                 }
+                // Mod5 is AltGr on standard keymaps
+                if (mods_depressed | mods_latched) & 0x80 != 0 {
+                    self.mod_state |= ModifierState_LEVEL3;
+                }
+                if mods_locked & 0x2 != 0 {
+                    self.mod_state |= ModifierState_CAPSLOCK;
+                }
+                // Mod2 is NumLock on standard keymaps
+                if mods_locked & 0x10 != 0 {
+                    self.mod_state |= ModifierState_NUMLOCK;
+                }
+                if let Some(xkb_state) = self.xkb_state.as_mut() {
+                    xkb_state.update_mask(mods_depressed, mods_latched, mods_locked, 0, 0, group);
+                }
+                self.mods = (mods_depressed, mods_latched, mods_locked, group);
                 self.vk
                     .modifiers(mods_depressed, mods_latched, mods_locked, group);
             }
@@ -385,6 +630,56 @@ impl KimeContext {
     }
 }
 
+/// Set by SIGHUP handler
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn sighup_handler(_: libc::c_int) {
+    RELOAD_REQUESTED.store(true, Ordering::SeqCst);
+}
+
+/// Compile keymap which is sent by compositor, `fd` is still owned by caller
+fn load_xkb_state(fd: i32, size: u32) -> Option<xkb::State> {
+    let size = size as usize;
+    let keymap = unsafe {
+        let ptr = libc::mmap(
+            std::ptr::null_mut(),
+            size,
+            libc::PROT_READ,
+            libc::MAP_PRIVATE,
+            fd,
+            0,
+        );
+
+        if ptr == libc::MAP_FAILED {
+            log::error!("Can't map keymap: {}", std::io::Error::last_os_error());
+            return None;
+        }
+
+        let data = std::slice::from_raw_parts(ptr as *const u8, size);
+        // Keymap string is null terminated
+        let len = data.iter().position(|&b| b == 0).unwrap_or(size);
+        let keymap = String::from_utf8_lossy(&data[..len]).into_owned();
+        libc::munmap(ptr, size);
+        keymap
+    };
+
+    let context = xkb::Context::new(xkb::CONTEXT_NO_FLAGS);
+    let keymap = xkb::Keymap::new_from_string(
+        &context,
+        keymap,
+        xkb::KEYMAP_FORMAT_TEXT_V1,
+        xkb::KEYMAP_COMPILE_NO_FLAGS,
+    );
+
+    match keymap {
+        Some(keymap) => Some(xkb::State::new(&keymap)),
+        None => {
+            log::error!("Can't compile keymap");
+            None
+        }
+    }
+}
+
 fn main() {
     kime_version::cli_boilerplate!((),);
 
@@ -408,6 +703,35 @@ fn main() {
         .instantiate_exact::<ZwpVirtualKeyboardManagerV1>(1)
         .expect("Load VirtualKeyboardManager");
 
+    // Optional, used for finding app id of focused window
+    match globals.instantiate_range::<ZwlrForeignToplevelManagerV1>(1, 3) {
+        Ok(toplevel_manager) => toplevel_manager.quick_assign(|_, event, _| {
+            if let ToplevelManagerEvent::Toplevel { toplevel } = event {
+                let mut app_id = None;
+                let mut activated = false;
+                toplevel.quick_assign(move |toplevel, event, mut data| match event {
+                    ToplevelEvent::AppId { app_id: id } => app_id = Some(id),
+                    ToplevelEvent::State { state } => {
+                        activated = state
+                            .chunks_exact(4)
+                            .map(|c| u32::from_ne_bytes([c[0], c[1], c[2], c[3]]))
+                            .any(|s| s == ToplevelState::Activated as u32);
+                    }
+                    ToplevelEvent::Done => {
+                        if let (true, Some(app_id)) = (activated, app_id.as_ref()) {
+                            if let Some(ctx) = data.get::<KimeContext>() {
+                                ctx.set_focused_app(app_id.clone());
+                            }
+                        }
+                    }
+                    ToplevelEvent::Closed => toplevel.destroy(),
+                    _ => {}
+                });
+            }
+        }),
+        Err(_) => log::info!("Foreign toplevel is not supported, per app category is disabled"),
+    }
+
     let filter = Filter::new(|ev, _filter, mut data| {
         let ctx = KimeContext::new_data(&mut data);
 
@@ -448,6 +772,25 @@ fn main() {
         .register(&mut timer, POLL_TIMER, Interest::READABLE)
         .expect("Register timer to the epoll()");
 
+    let mut watcher = ConfigWatcher::new();
+
+    const POLL_CONFIG: Token = Token(2);
+    if let Some(ref watcher) = watcher {
+        registry
+            .register(
+                &mut SourceFd(&watcher.fd()),
+                POLL_CONFIG,
+                Interest::READABLE,
+            )
+            .expect("Register config watcher to the epoll()");
+    } else {
+        log::warn!("Can't watch config directory");
+    }
+
+    unsafe {
+        libc::signal(libc::SIGHUP, sighup_handler as libc::sighandler_t);
+    }
+
     // Initialize kime context
     let mut kime_ctx = KimeContext::new(vk, im, grab, timer);
     event_queue
@@ -470,12 +813,14 @@ fn main() {
             //
             // Reference:
             //   https://www.gnu.org/software/libc/manual/html_node/Interrupted-Primitives.html
-            if e.kind() == ErrorKind::Interrupted {
-                continue;
+            if e.kind() != ErrorKind::Interrupted {
+                break Err(e);
             }
-            break Err(e);
+            events.clear();
         }
 
+        let mut reload = RELOAD_REQUESTED.swap(false, Ordering::SeqCst);
+
         for event in &events {
             match event.token() {
                 POLL_WAYLAND => {}
@@ -484,10 +829,19 @@ fn main() {
                         break 'main Err(e);
                     }
                 }
+                POLL_CONFIG => {
+                    if let Some(ref mut watcher) = watcher {
+                        reload |= watcher.changed();
+                    }
+                }
                 _ => unreachable!(),
             }
         }
 
+        if reload {
+            kime_ctx.reload_config();
+        }
+
         // Perform read() only when it's ready, returns None when there're already pending events
         if let Some(guard) = event_queue.prepare_read() {
             if let Err(e) = guard.read_events() {
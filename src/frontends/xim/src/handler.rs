@@ -1,63 +1,101 @@
-use std::{num::NonZeroU32, sync::Arc};
+use std::{num::NonZeroU32, rc::Rc, sync::Arc};
 
 use crate::pe_window::PeWindow;
 use ahash::AHashMap;
 use x11rb::{
     connection::Connection,
-    protocol::xproto::{ConfigureNotifyEvent, KeyPressEvent, KEY_PRESS_EVENT},
+    properties::WmClass,
+    protocol::xproto::{
+        ConfigureNotifyEvent, ConnectionExt, KeyPressEvent, KEY_PRESS_EVENT, KEY_RELEASE_EVENT,
+    },
 };
 use xim::{
     x11rb::{HasConnection, X11rbServer},
-    InputStyle, Server, ServerHandler,
+    ForwardEventFlag, InputStyle, Request, Server, ServerCore, ServerHandler, XEvent,
 };
 
 use kime_engine_cffi::{
-    Config, InputEngine, InputResult_CONSUMED, InputResult_HAS_COMMIT, InputResult_HAS_PREEDIT,
-    InputResult_LANGUAGE_CHANGED, InputResult_NOT_READY, ModifierState_ALT, ModifierState_CONTROL,
-    ModifierState_SHIFT, ModifierState_SUPER,
+    BypassKey, Config, InputEngine, InputResult_BYPASS_KEYS, InputResult_CONSUMED,
+    InputResult_HAS_COMMIT, InputResult_HAS_PREEDIT, InputResult_LANGUAGE_CHANGED,
+    InputResult_NOT_READY, ModifierState_ALT, ModifierState_CAPSLOCK, ModifierState_CONTROL,
+    ModifierState_LEVEL3, ModifierState_NUMLOCK, ModifierState_SHIFT, ModifierState_SUPER,
 };
 
 pub struct KimeData {
     engine: InputEngine,
+    config: Rc<Config>,
     pe: Option<NonZeroU32>,
     show_preedit_window: bool,
     engine_ready: bool,
+    app_id_checked: bool,
 }
 
 impl KimeData {
-    pub fn new(config: &Config, show_preedit_window: bool) -> Self {
+    pub fn new(config: &Rc<Config>, show_preedit_window: bool) -> Self {
         Self {
             engine: InputEngine::new(config),
+            config: config.clone(),
             pe: None,
             show_preedit_window,
             engine_ready: true,
+            app_id_checked: false,
         }
     }
 }
 
+/// WM_CLASS of toplevel window which contains `window`
+fn wm_class(conn: &impl Connection, mut window: u32) -> Option<String> {
+    loop {
+        if let Ok(class) = WmClass::get(conn, window).ok()?.reply() {
+            if !class.class().is_empty() {
+                return Some(String::from_utf8_lossy(class.class()).into_owned());
+            }
+        }
+
+        let tree = conn.query_tree(window).ok()?.reply().ok()?;
+
+        if tree.parent == tree.root || tree.parent == x11rb::NONE {
+            return None;
+        }
+
+        window = tree.parent;
+    }
+}
+
 pub struct KimeHandler {
     preedit_windows: AHashMap<NonZeroU32, PeWindow>,
     font: (Arc<rusttype::Font<'static>>, f32),
-    config: Config,
+    config: Rc<Config>,
     screen_num: usize,
 }
 
+fn load_font(config: &Config) -> (Arc<rusttype::Font<'static>>, f32) {
+    let (font_data, index, font_size) = config.xim_font();
+    let font = Arc::new(
+        rusttype::Font::try_from_vec_and_index(font_data.to_vec(), index)
+            .unwrap()
+            .to_owned(),
+    );
+
+    (font, font_size)
+}
+
 impl KimeHandler {
     pub fn new(screen_num: usize, config: Config) -> Self {
-        let (font_data, index, font_size) = config.xim_font();
-        let font = Arc::new(
-            rusttype::Font::try_from_vec_and_index(font_data.to_vec(), index)
-                .unwrap()
-                .to_owned(),
-        );
-
         Self {
             preedit_windows: AHashMap::new(),
-            config,
+            font: load_font(&config),
+            config: Rc::new(config),
             screen_num,
-            font: (font, font_size),
         }
     }
+
+    /// Replace config, each input context will apply it when it used next time
+    pub fn reload_config(&mut self, config: Config) {
+        log::info!("Reload config");
+        self.font = load_font(&config);
+        self.config = Rc::new(config);
+    }
 }
 
 impl KimeHandler {
@@ -140,6 +178,23 @@ impl KimeHandler {
         Ok(())
     }
 
+    fn update_config<C: HasConnection>(
+        &mut self,
+        server: &mut X11rbServer<C>,
+        user_ic: &mut xim::UserInputContext<KimeData>,
+    ) -> Result<(), xim::ServerError> {
+        if !Rc::ptr_eq(&user_ic.user_data.config, &self.config) {
+            let ret = user_ic
+                .user_data
+                .engine
+                .update_config(&user_ic.user_data.config, &self.config);
+            user_ic.user_data.config = self.config.clone();
+            self.process_input_result(server, user_ic, ret)?;
+        }
+
+        Ok(())
+    }
+
     fn reset<C: HasConnection>(
         &mut self,
         server: &mut X11rbServer<C>,
@@ -173,6 +228,9 @@ impl KimeHandler {
 
         if ret & InputResult_HAS_COMMIT != 0 {
             self.commit(server, user_ic)?;
+        }
+
+        if ret & (InputResult_HAS_COMMIT | InputResult_BYPASS_KEYS) != 0 {
             user_ic.user_data.engine.clear_commit();
         }
 
@@ -185,6 +243,87 @@ impl KimeHandler {
         Ok(ret & InputResult_CONSUMED != 0)
     }
 
+    /// Send keys which were held as a part of hotkey trigger, client never got them
+    fn bypass_keys<C: HasConnection>(
+        &mut self,
+        server: &mut X11rbServer<C>,
+        user_ic: &xim::UserInputContext<KimeData>,
+        xev: &KeyPressEvent,
+        keys: &[BypassKey],
+    ) -> Result<(), xim::ServerError> {
+        for key in keys {
+            // Keep locks of current event
+            let mut state = xev.state & (0x2 | 0x10);
+
+            if key.state & ModifierState_SHIFT != 0 {
+                state |= 0x1;
+            }
+
+            if key.state & ModifierState_CONTROL != 0 {
+                state |= 0x4;
+            }
+
+            if key.state & ModifierState_ALT != 0 {
+                state |= 0x8;
+            }
+
+            if key.state & ModifierState_SUPER != 0 {
+                state |= 0x40;
+            }
+
+            if key.state & ModifierState_LEVEL3 != 0 {
+                state |= 0x80;
+            }
+
+            for response_type in &[KEY_PRESS_EVENT, KEY_RELEASE_EVENT] {
+                server.send_req(
+                    user_ic.ic.client_win(),
+                    Request::ForwardEvent {
+                        input_method_id: user_ic.ic.input_method_id().get(),
+                        input_context_id: user_ic.ic.input_context_id().get(),
+                        flag: ForwardEventFlag::empty(),
+                        serial_number: 0,
+                        xev: XEvent {
+                            response_type: *response_type,
+                            detail: key.hardware_code as u8,
+                            sequence: xev.sequence,
+                            time: xev.time,
+                            root: xev.root,
+                            event: xev.event,
+                            child: xev.child,
+                            root_x: xev.root_x,
+                            root_y: xev.root_y,
+                            event_x: xev.event_x,
+                            event_y: xev.event_y,
+                            state,
+                            same_screen: xev.same_screen,
+                        },
+                    },
+                )?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Process result of key event, bypass keys are sent to client after commit
+    fn process_key_result<C: HasConnection>(
+        &mut self,
+        server: &mut X11rbServer<C>,
+        user_ic: &mut xim::UserInputContext<KimeData>,
+        xev: &KeyPressEvent,
+        ret: kime_engine_cffi::InputResult,
+    ) -> Result<bool, xim::ServerError> {
+        let keys = if ret & InputResult_BYPASS_KEYS != 0 {
+            user_ic.user_data.engine.bypass_keys().collect()
+        } else {
+            Vec::new()
+        };
+        let consumed = self.process_input_result(server, user_ic, ret)?;
+        self.bypass_keys(server, user_ic, xev, &keys)?;
+        Ok(consumed)
+    }
+
     fn clear_preedit<C: HasConnection>(
         &mut self,
         server: &mut X11rbServer<C>,
@@ -318,13 +457,10 @@ impl<C: HasConnection> ServerHandler<X11rbServer<C>> for KimeHandler {
         user_ic: &mut xim::UserInputContext<Self::InputContextData>,
         xev: &KeyPressEvent,
     ) -> Result<bool, xim::ServerError> {
-        // skip release
-        if xev.response_type != KEY_PRESS_EVENT {
-            return Ok(false);
-        }
-
         log::trace!("{:?}", xev);
 
+        self.update_config(server, user_ic)?;
+
         let mut state = 0;
 
         if xev.state & 0x1 != 0 {
@@ -343,12 +479,41 @@ impl<C: HasConnection> ServerHandler<X11rbServer<C>> for KimeHandler {
             state |= ModifierState_SUPER;
         }
 
-        let ret = user_ic
-            .user_data
-            .engine
-            .press_key(&self.config, xev.detail as u16, state);
+        if xev.state & 0x2 != 0 {
+            state |= ModifierState_CAPSLOCK;
+        }
+
+        // Mod2 is NumLock on standard keymaps
+        if xev.state & 0x10 != 0 {
+            state |= ModifierState_NUMLOCK;
+        }
+
+        // Mod5 is AltGr on standard keymaps
+        if xev.state & 0x80 != 0 {
+            state |= ModifierState_LEVEL3;
+        }
+
+        if xev.response_type != KEY_PRESS_EVENT {
+            // Release of key consumed as a part of hotkey trigger is also consumed
+            let ret = user_ic.user_data.engine.release_key(
+                &self.config,
+                xev.detail as u16,
+                state,
+                xev.time,
+            );
+            if ret != 0 {
+                return self.process_key_result(server, user_ic, xev, ret);
+            }
+            return Ok(false);
+        }
+
+        let ret =
+            user_ic
+                .user_data
+                .engine
+                .press_key(&self.config, xev.detail as u16, state, xev.time);
 
-        self.process_input_result(server, user_ic, ret)
+        self.process_key_result(server, user_ic, xev, ret)
     }
 
     fn handle_destory_ic(
@@ -373,13 +538,26 @@ impl<C: HasConnection> ServerHandler<X11rbServer<C>> for KimeHandler {
         server: &mut X11rbServer<C>,
         user_ic: &mut xim::UserInputContext<Self::InputContextData>,
     ) -> Result<(), xim::ServerError> {
+        self.update_config(server, user_ic)?;
+
+        if !user_ic.user_data.app_id_checked && user_ic.ic.client_win() != 0 {
+            user_ic.user_data.app_id_checked = true;
+
+            if let Some(app_id) = wm_class(server.conn(), user_ic.ic.client_win()) {
+                log::debug!("WM_CLASS: {}", app_id);
+                let config = user_ic.user_data.config.clone();
+                let ret = user_ic.user_data.engine.set_app_id(&config, &app_id);
+                self.process_input_result(server, user_ic, ret)?;
+            }
+        }
+
         user_ic.user_data.engine.update_layout_state();
 
         if !user_ic.user_data.engine_ready {
             if user_ic.user_data.engine.check_ready() {
+                // Result can be NOT_READY again when hanja shows next segment
                 let ret = user_ic.user_data.engine.end_ready();
                 self.process_input_result(server, user_ic, ret)?;
-                user_ic.user_data.engine_ready = true;
             }
         }
 
@@ -1,3 +1,7 @@
+use std::{
+    os::unix::io::AsRawFd,
+    sync::atomic::{AtomicBool, Ordering},
+};
 use x11rb::{
     connection::Connection,
     protocol::{ErrorKind, Event},
@@ -7,6 +11,13 @@ use xim::{x11rb::HasConnection, XimConnections};
 mod handler;
 mod pe_window;
 
+/// Set by SIGHUP handler
+static RELOAD_REQUESTED: AtomicBool = AtomicBool::new(false);
+
+extern "C" fn sighup_handler(_: libc::c_int) {
+    RELOAD_REQUESTED.store(true, Ordering::SeqCst);
+}
+
 fn main() {
     kime_version::cli_boilerplate!((),);
 
@@ -23,9 +34,55 @@ fn main() {
         .expect("Init XIM server");
     let mut connections = XimConnections::new();
     let mut handler = self::handler::KimeHandler::new(screen_num, config);
+    let mut watcher = kime_engine_cffi::ConfigWatcher::new();
+
+    if watcher.is_none() {
+        log::warn!("Can't watch config directory");
+    }
+
+    unsafe {
+        libc::signal(libc::SIGHUP, sighup_handler as libc::sighandler_t);
+    }
 
     loop {
-        let e = server.conn().wait_for_event().expect("Wait event");
+        let e = match server.conn().poll_for_event().expect("Poll event") {
+            Some(e) => e,
+            None => {
+                server.conn().flush().expect("Flush connection");
+
+                let mut fds = [
+                    libc::pollfd {
+                        fd: server.conn().stream().as_raw_fd(),
+                        events: libc::POLLIN,
+                        revents: 0,
+                    },
+                    libc::pollfd {
+                        fd: watcher.as_ref().map_or(-1, |w| w.fd()),
+                        events: libc::POLLIN,
+                        revents: 0,
+                    },
+                ];
+
+                // EINTR is fine here SIGHUP is checked below
+                unsafe {
+                    libc::poll(fds.as_mut_ptr(), fds.len() as _, -1);
+                }
+
+                let mut reload = RELOAD_REQUESTED.swap(false, Ordering::SeqCst);
+
+                if fds[1].revents & libc::POLLIN != 0 {
+                    if let Some(ref mut watcher) = watcher {
+                        reload |= watcher.changed();
+                    }
+                }
+
+                if reload {
+                    handler.reload_config(kime_engine_cffi::Config::load());
+                }
+
+                continue;
+            }
+        };
         match server.filter_event(&e, &mut connections, &mut handler) {
             // event has filtered
             Ok(true) => {}
@@ -1,9 +1,23 @@
 use std::{
     collections::BTreeMap,
-    io::{self, BufRead, Stdout, Write},
+    io::{self, BufReader, Write},
+    net::Shutdown,
+    os::unix::net::{UnixListener, UnixStream},
+    path::Path,
 };
 
 use egui::Widget;
+use egui_glow::{glow, EguiGlow};
+use glutin::{
+    dpi::LogicalSize,
+    event::{Event, WindowEvent},
+    event_loop::{ControlFlow, EventLoop, EventLoopProxy},
+    window::WindowBuilder,
+    ContextBuilder, PossiblyCurrent, WindowedContext,
+};
+use kime_engine_candidate::protocol::{read_message, socket_path, Message};
+use kime_engine_cffi::ConfigWatcher;
+use nix::unistd::setsid;
 
 const PAGE_SIZE: usize = 10;
 
@@ -13,19 +27,86 @@ struct KeyState {
     right: bool,
 }
 
-struct CandidateApp {
-    stdout: Stdout,
+/// Selected candidate is written here
+enum Output {
+    Stdout,
+    Client(UnixStream),
+}
+
+impl Output {
+    /// `None` means candidate window is closed without selection
+    fn finish(self, selected: Option<&str>) {
+        match self {
+            Output::Stdout => {
+                let mut stdout = io::stdout();
+                if let Some(selected) = selected {
+                    stdout.write_all(selected.as_bytes()).ok();
+                }
+                stdout.flush().ok();
+            }
+            Output::Client(mut stream) => {
+                if let Some(selected) = selected {
+                    stream.write_all(selected.as_bytes()).ok();
+                }
+                stream.shutdown(Shutdown::Both).ok();
+            }
+        }
+    }
+}
+
+enum UserEvent {
+    /// Show candidates, `id` identifies request
+    Show {
+        id: u64,
+        candidate_list: Vec<(String, String)>,
+        output: Output,
+    },
+    /// Replace candidates of request `id`
+    Update {
+        id: u64,
+        candidate_list: Vec<(String, String)>,
+    },
+    /// Hide candidates of request `id` without selection, client is closed or hid them
+    Hide { id: u64 },
+}
+
+/// Candidates which are shown now
+struct Request {
+    id: u64,
+    output: Output,
+    candidate_list: Vec<(String, String)>,
     key_state: KeyState,
     page_index: usize,
     max_page_index: usize,
-    candidate_list: Vec<(String, String)>,
 }
 
-impl eframe::epi::App for CandidateApp {
-    fn update(&mut self, ctx: &egui::Context, frame: &eframe::epi::Frame) {
+enum Action {
+    Select(String),
+    Cancel,
+}
+
+impl Request {
+    fn new(id: u64, candidate_list: Vec<(String, String)>, output: Output) -> Self {
+        Self {
+            id,
+            output,
+            key_state: KeyState::default(),
+            page_index: 0,
+            max_page_index: (candidate_list.len().max(1) - 1) / PAGE_SIZE,
+            candidate_list,
+        }
+    }
+
+    /// Show candidates from first page
+    fn set_candidates(&mut self, candidate_list: Vec<(String, String)>) {
+        self.page_index = 0;
+        self.max_page_index = (candidate_list.len().max(1) - 1) / PAGE_SIZE;
+        self.candidate_list = candidate_list;
+    }
+
+    fn update(&mut self, ctx: &egui::Context) -> Option<Action> {
         if ctx.input().key_down(egui::Key::Escape) || ctx.input().key_down(egui::Key::Q) {
-            frame.quit();
-            return;
+            return Some(Action::Cancel);
         }
 
         macro_rules! num_hotkey {
@@ -47,11 +128,13 @@ impl eframe::epi::App for CandidateApp {
         num_hotkey!(egui::Key::Num9, 8);
         num_hotkey!(egui::Key::Num0, 9);
 
-        if ctx.input().key_down(egui::Key::ArrowLeft) || ctx.input().key_down(egui::Key::H) {
-            if !self.key_state.left {
-                self.page_index = self.page_index.saturating_sub(1);
-                self.key_state.left = true;
-            }
+        self.page_index = self.page_index.min(self.max_page_index);
+
+        if (ctx.input().key_down(egui::Key::ArrowLeft) || ctx.input().key_down(egui::Key::H))
+            && !self.key_state.left
+        {
+            self.page_index = self.page_index.saturating_sub(1);
+            self.key_state.left = true;
         }
 
         if ctx.input().key_released(egui::Key::ArrowLeft) || ctx.input().key_released(egui::Key::H)
@@ -59,11 +142,11 @@ impl eframe::epi::App for CandidateApp {
             self.key_state.left = false;
         }
 
-        if ctx.input().key_down(egui::Key::ArrowRight) || ctx.input().key_down(egui::Key::L) {
-            if !self.key_state.right {
-                self.page_index = self.page_index.saturating_add(1).min(self.max_page_index);
-                self.key_state.right = true;
-            }
+        if (ctx.input().key_down(egui::Key::ArrowRight) || ctx.input().key_down(egui::Key::L))
+            && !self.key_state.right
+        {
+            self.page_index = self.page_index.saturating_add(1).min(self.max_page_index);
+            self.key_state.right = true;
         }
 
         if ctx.input().key_released(egui::Key::ArrowRight) || ctx.input().key_released(egui::Key::L)
@@ -71,28 +154,24 @@ impl eframe::epi::App for CandidateApp {
             self.key_state.right = false;
         }
 
+        let mut action = None;
+
         egui::CentralPanel::default().show(ctx, |ui| {
             ui.vertical_centered(|ui| {
                 let from = self.page_index * PAGE_SIZE;
                 let to = (from + PAGE_SIZE).min(self.candidate_list.len());
 
                 for (key, value) in self.candidate_list[from..to].iter() {
-                    let quitted = ui
+                    let clicked = ui
                         .horizontal(|ui| {
                             ui.colored_label(egui::Color32::LIGHT_BLUE, key);
                             ui.separator();
-                            if ui.button(value).clicked() {
-                                true
-                            } else {
-                                false
-                            }
+                            ui.button(value).clicked()
                         })
                         .inner;
 
-                    if quitted {
-                        self.stdout.write_all(key.as_bytes()).unwrap();
-                        frame.quit();
-                        return;
+                    if clicked {
+                        action = Some(Action::Select(key.clone()));
                     }
                 }
             });
@@ -107,91 +186,275 @@ impl eframe::epi::App for CandidateApp {
                                 .color(egui::Color32::YELLOW),
                         )
                         .ui(ui);
-                    } else {
-                        if ui.button(format!("{}", i + 1)).clicked() {
-                            self.page_index = i;
-                        }
-                    };
+                    } else if ui.button(format!("{}", i + 1)).clicked() {
+                        self.page_index = i;
+                    }
                 }
             });
         });
+
+        action
     }
+}
+
+fn load_font() -> Vec<u8> {
+    assert!(kime_engine_cffi::check_api_version());
+
+    let config = kime_engine_cffi::Config::load();
+    let (font_bytes, _index) = config.candidate_font();
+    font_bytes.to_vec()
+}
+
+fn set_font(ctx: &egui::Context, font: Vec<u8>) {
+    let mut font_data = BTreeMap::<_, egui::FontData>::new();
+    let mut families = BTreeMap::new();
+
+    font_data.insert("Font".to_string(), egui::FontData::from_owned(font));
 
-    fn setup(
-        &mut self,
-        ctx: &egui::Context,
-        _frame: &eframe::epi::Frame,
-        _storage: Option<&dyn eframe::epi::Storage>,
-    ) {
-        assert!(kime_engine_cffi::check_api_version());
-
-        let config = kime_engine_cffi::Config::load();
-        let (font_bytes, _index) = config.candidate_font();
-        let mut font_data = BTreeMap::<_, egui::FontData>::new();
-        let mut families = BTreeMap::new();
-
-        font_data.insert(
-            "Font".to_string(),
-            egui::FontData::from_owned(font_bytes.to_vec()),
-        );
-
-        families.insert(egui::FontFamily::Proportional, vec!["Font".to_string()]);
-        families.insert(egui::FontFamily::Monospace, vec!["Font".to_string()]);
-
-        ctx.set_fonts(egui::FontDefinitions {
-            font_data,
-            families,
+    families.insert(egui::FontFamily::Proportional, vec!["Font".to_string()]);
+    families.insert(egui::FontFamily::Monospace, vec!["Font".to_string()]);
+
+    ctx.set_fonts(egui::FontDefinitions {
+        font_data,
+        families,
+    });
+}
+
+fn create_window(
+    event_loop: &EventLoop<UserEvent>,
+) -> (WindowedContext<PossiblyCurrent>, glow::Context) {
+    let window_builder = WindowBuilder::new()
+        .with_title("kime-candidate")
+        .with_visible(false)
+        .with_decorations(false)
+        .with_always_on_top(true)
+        .with_inner_size(LogicalSize {
+            width: 400.0,
+            height: 400.0,
         });
-    }
 
-    fn name(&self) -> &str {
-        "kime-candidate"
-    }
+    let gl_window = unsafe {
+        ContextBuilder::new()
+            .with_depth_buffer(0)
+            .with_srgb(true)
+            .with_stencil_buffer(0)
+            .with_vsync(true)
+            .build_windowed(window_builder, event_loop)
+            .expect("Can't create candidate window")
+            .make_current()
+            .expect("Can't use GL context of candidate window")
+    };
+
+    let gl = unsafe { glow::Context::from_loader_function(|s| gl_window.get_proc_address(s)) };
+
+    (gl_window, gl)
 }
 
-fn main() -> io::Result<()> {
-    let mut buf = String::with_capacity(4096);
-    let stdin = io::stdin();
-    let stdout = io::stdout();
+/// Show candidates of each request with one window, it's hidden while there is no request
+///
+/// Process exits after first request when `exit_on_finish` is `true`,
+/// font is loaded again for new request when `watcher` reports changed config
+fn run(
+    event_loop: EventLoop<UserEvent>,
+    mut watcher: Option<ConfigWatcher>,
+    exit_on_finish: bool,
+) -> ! {
+    let (gl_window, gl) = create_window(&event_loop);
+    let mut egui_glow = EguiGlow::new(gl_window.window(), &gl);
+    set_font(&egui_glow.egui_ctx, load_font());
+
+    let mut current: Option<Request> = None;
 
-    let mut candidate_list = Vec::new();
-    let mut stdin_lock = stdin.lock();
+    event_loop.run(move |event, _, control_flow| {
+        if *control_flow != ControlFlow::Exit {
+            *control_flow = ControlFlow::Wait;
+        }
+
+        let window = gl_window.window();
+        let mut finish = |current: &mut Option<Request>, selected: Option<&str>| {
+            if let Some(request) = current.take() {
+                request.output.finish(selected);
+                window.set_visible(false);
 
-    macro_rules! read {
-        ($ret:ident) => {
-            let len = stdin_lock.read_line(&mut buf)?;
-            if len == 0 {
-                break;
+                if exit_on_finish {
+                    *control_flow = ControlFlow::Exit;
+                }
             }
-            let $ret = buf.trim_end_matches('\n').to_string();
-            buf.clear();
         };
+
+        match event {
+            Event::UserEvent(UserEvent::Show {
+                id,
+                candidate_list,
+                output,
+            }) => {
+                // New request replaces current one
+                finish(&mut current, None);
+                current = Some(Request::new(id, candidate_list, output));
+
+                if watcher.as_mut().is_some_and(ConfigWatcher::changed) {
+                    set_font(&egui_glow.egui_ctx, load_font());
+                }
+
+                window.set_visible(true);
+                window.focus_window();
+                window.request_redraw();
+            }
+            Event::UserEvent(UserEvent::Update { id, candidate_list }) => {
+                if let Some(request) = current.as_mut().filter(|request| request.id == id) {
+                    request.set_candidates(candidate_list);
+                    window.request_redraw();
+                }
+            }
+            Event::UserEvent(UserEvent::Hide { id })
+                if current.as_ref().is_some_and(|request| request.id == id) =>
+            {
+                finish(&mut current, None);
+            }
+            Event::RedrawRequested(_) => {
+                let request = match current.as_mut() {
+                    Some(request) => request,
+                    None => return,
+                };
+
+                let mut action = None;
+                let needs_repaint = egui_glow.run(window, |ctx| {
+                    action = request.update(ctx);
+                });
+
+                unsafe {
+                    use glow::HasContext as _;
+                    gl.clear_color(0.0, 0.0, 0.0, 1.0);
+                    gl.clear(glow::COLOR_BUFFER_BIT);
+                }
+
+                egui_glow.paint(window, &gl);
+                gl_window.swap_buffers().ok();
+
+                match action {
+                    Some(Action::Select(selected)) => finish(&mut current, Some(&selected)),
+                    Some(Action::Cancel) => finish(&mut current, None),
+                    None if needs_repaint => window.request_redraw(),
+                    None => {}
+                }
+            }
+            Event::WindowEvent { event, .. } => {
+                match event {
+                    // Window is kept for next request
+                    WindowEvent::CloseRequested => {
+                        finish(&mut current, None);
+                        return;
+                    }
+                    WindowEvent::Resized(physical_size) => gl_window.resize(physical_size),
+                    _ => {}
+                }
+
+                egui_glow.on_event(&event);
+                window.request_redraw();
+            }
+            Event::LoopDestroyed => {
+                egui_glow.destroy(&gl);
+            }
+            _ => {}
+        }
+    })
+}
+
+/// Pass messages of client `id` to window until it's closed
+fn serve_client(stream: UnixStream, id: u64, proxy: EventLoopProxy<UserEvent>) {
+    std::thread::spawn(move || {
+        let mut reader = BufReader::new(&stream);
+
+        loop {
+            let event = match read_message(&mut reader) {
+                Ok(Some(Message::Show(candidate_list))) if !candidate_list.is_empty() => {
+                    match stream.try_clone() {
+                        Ok(output) => UserEvent::Show {
+                            id,
+                            candidate_list,
+                            output: Output::Client(output),
+                        },
+                        Err(_) => break,
+                    }
+                }
+                Ok(Some(Message::Update(candidate_list))) => {
+                    UserEvent::Update { id, candidate_list }
+                }
+                _ => break,
+            };
+
+            // Window is closed
+            if proxy.send_event(event).is_err() {
+                return;
+            }
+        }
+
+        proxy.send_event(UserEvent::Hide { id }).ok();
+    });
+}
+
+/// Accept clients and pass their messages to window
+fn accept_clients(listener: UnixListener, proxy: EventLoopProxy<UserEvent>) {
+    for (id, stream) in (0..).zip(listener.incoming()) {
+        if let Ok(stream) = stream {
+            serve_client(stream, id, proxy.clone());
+        }
     }
+}
+
+/// Serve candidates of clients with one window, fonts are loaded again only when config is changed
+///
+/// Candidates of new client replace current one
+fn candidate_server(file_path: &Path) -> io::Result<()> {
+    let listener = match UnixListener::bind(file_path) {
+        Ok(listener) => listener,
+        Err(err) if err.kind() == io::ErrorKind::AddrInUse => {
+            if UnixStream::connect(file_path).is_ok() {
+                // Server is already running
+                return Ok(());
+            }
 
-    loop {
-        read!(key);
-        read!(value);
-        candidate_list.push((key, value));
+            // Socket of exited server
+            std::fs::remove_file(file_path)?;
+            UnixListener::bind(file_path)?
+        }
+        Err(err) => return Err(err),
+    };
+
+    setsid().ok();
+
+    let event_loop = EventLoop::with_user_event();
+    let proxy = event_loop.create_proxy();
+    std::thread::spawn(move || accept_clients(listener, proxy));
+
+    run(event_loop, ConfigWatcher::new(), false)
+}
+
+fn main() -> io::Result<()> {
+    if std::env::args().any(|arg| arg == "--server") {
+        return candidate_server(&socket_path());
     }
 
-    eframe::run_native(
-        Box::new(CandidateApp {
-            stdout,
-            page_index: 0,
-            key_state: KeyState::default(),
-            max_page_index: if candidate_list.len() % PAGE_SIZE == 0 {
-                (candidate_list.len() / PAGE_SIZE) - 1
-            } else {
-                candidate_list.len() / PAGE_SIZE
-            },
+    let stdin = io::stdin();
+    let candidate_list = match read_message(stdin.lock())? {
+        Some(Message::Show(candidate_list)) => candidate_list,
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "Candidates aren't shown",
+            ))
+        }
+    };
+
+    let event_loop = EventLoop::with_user_event();
+    event_loop
+        .create_proxy()
+        .send_event(UserEvent::Show {
+            id: 0,
             candidate_list,
-        }),
-        eframe::NativeOptions {
-            always_on_top: true,
-            decorated: false,
-            icon_data: None,
-            initial_window_size: Some(egui::vec2(400.0, 400.0)),
-            ..Default::default()
-        },
-    );
+            output: Output::Stdout,
+        })
+        .ok();
+
+    run(event_loop, None, true)
 }
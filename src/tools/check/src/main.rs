@@ -1,7 +1,6 @@
 use ansi_term::Color;
 use kime_engine_cffi::{
-    Config, InputCategory, InputEngine, InputResult_CONSUMED, InputResult_HAS_COMMIT,
-    InputResult_HAS_PREEDIT,
+    Config, InputEngine, InputResult_CONSUMED, InputResult_HAS_COMMIT, InputResult_HAS_PREEDIT,
 };
 use pad::PadStr;
 use std::env;
@@ -50,6 +49,8 @@ enum Check {
     Icons,
     #[strum(message = "Config file")]
     Config,
+    #[strum(message = "Key remap")]
+    Remap,
     #[strum(message = "Engine works")]
     EngineWorks,
     #[strum(message = "XMODIFIERS has @im=kime")]
@@ -137,6 +138,27 @@ impl Check {
 
                 CondResult::Ok
             }
+            Check::Remap => {
+                let config = match kime_engine_core::load_engine_config_from_config_dir() {
+                    Some(config) => config,
+                    None => return CondResult::Fail("Can't load config".into()),
+                };
+
+                let mut empty = true;
+
+                for category in config.categories() {
+                    for (from, to) in config.remap(category) {
+                        println!("{}: {} -> {}", category, from, to);
+                        empty = false;
+                    }
+                }
+
+                if empty {
+                    CondResult::Ignore("No key is remapped".into())
+                } else {
+                    CondResult::Ok
+                }
+            }
             Check::XModifier => match env::var("XDG_SESSION_TYPE").unwrap().as_str() {
                 "x11" => check_var(
                     "XMODIFIERS",
@@ -168,10 +190,12 @@ fn check_input(
     config: &Config,
     tests: &[(u16, &str, &str)],
 ) -> CondResult {
-    engine.set_input_category(InputCategory::Hangul);
+    if !engine.set_input_category("Hangul") {
+        return CondResult::Fail("Hangul category is not enabled".into());
+    }
 
     for (key, preedit, commit) in tests.iter().copied() {
-        let ret = engine.press_key(config, key, 0);
+        let ret = engine.press_key(config, key, 0, 0);
 
         let preedit_ret;
         let commit_ret;
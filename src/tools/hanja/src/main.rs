@@ -0,0 +1,70 @@
+use kime_engine_dict::user::UserDict;
+use std::{
+    io::{self, Read, Write},
+    path::PathBuf,
+};
+
+fn read_input(path: Option<PathBuf>) -> io::Result<String> {
+    match path {
+        Some(path) => std::fs::read_to_string(path),
+        None => {
+            let mut text = String::new();
+            io::stdin().read_to_string(&mut text)?;
+            Ok(text)
+        }
+    }
+}
+
+fn write_output(path: Option<PathBuf>, text: &str) -> io::Result<()> {
+    match path {
+        Some(path) => std::fs::write(path, text),
+        None => io::stdout().write_all(text.as_bytes()),
+    }
+}
+
+fn main() -> Result<(), ()> {
+    let mut args = kime_version::cli_boilerplate!(
+        Ok(()),
+        "list [HANGUL]: show entries of user hanja dictionary",
+        "import [FILE]: add `hangul:hanja:description` entries of FILE or stdin",
+        "export [FILE]: write entries to FILE or stdout",
+    );
+
+    let command = args.subcommand().ok().flatten();
+    let arg: Option<String> = args.opt_free_from_str().ok().flatten();
+    let mut dict = UserDict::load();
+
+    match command.as_deref() {
+        Some("list") => {
+            for (hangul, hanja, description) in dict.entries() {
+                if arg.as_deref().map_or(true, |arg| arg == hangul) {
+                    println!("{}\t{}\t{}", hangul, hanja, description);
+                }
+            }
+
+            Ok(())
+        }
+        Some("import") => {
+            let text = read_input(arg.map(PathBuf::from)).map_err(|err| {
+                log::error!("Can't read entries: {}", err);
+            })?;
+            let count = dict.import_and_save(&text).map_err(|err| {
+                log::error!("Can't save user hanja dictionary: {}", err);
+            })?;
+
+            println!("Imported {} entries", count);
+            Ok(())
+        }
+        Some("export") => write_output(arg.map(PathBuf::from), &dict.export()).map_err(|err| {
+            log::error!("Can't write entries: {}", err);
+        }),
+        Some(command) => {
+            log::error!("Unknown command: {}", command);
+            Err(())
+        }
+        None => {
+            log::error!("Command is needed, see --help");
+            Err(())
+        }
+    }
+}
@@ -9,13 +9,9 @@ use std::{
     time::Duration,
 };
 
-#[derive(Clone, Copy, Debug)]
-enum InputCategory {
-    Latin,
-    Hangul,
-}
-
 struct KimeTray {
+    category: String,
+    layout: Option<String>,
     icon_name: &'static str,
     color: IconColor,
 }
@@ -30,7 +26,10 @@ impl ksni::Tray for KimeTray {
     }
 
     fn title(&self) -> String {
-        self.id()
+        match self.layout.as_deref() {
+            Some(layout) => format!("kime ({}: {})", self.category, layout),
+            None => format!("kime ({})", self.category),
+        }
     }
 
     fn attention_icon_name(&self) -> String {
@@ -47,40 +46,42 @@ impl ksni::Tray for KimeTray {
     }
 }
 
-const fn icon_name(category: InputCategory, color: IconColor) -> &'static str {
+/// Categories which don't have own icon use latin icon
+fn icon_name(category: &str, color: IconColor) -> &'static str {
     match (category, color) {
-        (InputCategory::Latin, IconColor::Black) => "kime-latin-black",
-        (InputCategory::Latin, IconColor::White) => "kime-latin-white",
-        (InputCategory::Hangul, IconColor::Black) => "kime-hangul-black",
-        (InputCategory::Hangul, IconColor::White) => "kime-hangul-white",
+        ("Hangul", IconColor::Black) => "kime-hangul-black",
+        ("Hangul", IconColor::White) => "kime-hangul-white",
+        (_, IconColor::Black) => "kime-latin-black",
+        (_, IconColor::White) => "kime-latin-white",
     }
 }
 
 impl KimeTray {
     pub fn new(color: IconColor) -> Self {
+        // Set init category Latin
+        // TODO: should consider `default_category` config?
+        let category = "Latin";
         Self {
-            // Set init category Latin
-            // TODO: should consider `default_category` config?
-            icon_name: icon_name(InputCategory::Latin, color),
+            category: category.into(),
+            layout: None,
+            icon_name: icon_name(category, color),
             color,
         }
     }
-    pub fn update_with_bytes(&mut self, bytes: &[u8; 1]) {
-        let category = match bytes[0] {
-            1 => InputCategory::Hangul,
-            _ => InputCategory::Latin,
-        };
 
-        self.update(category);
-    }
-
-    pub fn update(&mut self, category: InputCategory) {
-        log::debug!("Update: {:?}", category);
+    /// `message` is category name which is optionally followed by layout name after newline
+    pub fn update(&mut self, message: &str) {
+        log::debug!("Update: {:?}", message);
+        let mut lines = message.splitn(2, '\n');
+        let category = lines.next().unwrap_or_default();
         self.icon_name = icon_name(category, self.color);
+        self.category = category.into();
+        self.layout = lines.next().map(Into::into);
     }
 }
 
-const EXIT_MESSAGE: &[u8; 1] = b"Z";
+/// Category names can't contain NUL
+const EXIT_MESSAGE: &[u8] = b"\0";
 
 fn try_terminate_previous_server(file_path: &Path) -> Result<()> {
     let mut client = UnixStream::connect(file_path)?;
@@ -102,27 +103,34 @@ fn indicator_server(file_path: &Path, color: IconColor) -> Result<()> {
 
     let listener = UnixListener::bind(file_path)?;
 
-    let mut current_bytes = [0; 1];
-    let mut read_buf = [0; 1];
+    let mut current = String::from("Latin");
+    let mut read_buf = Vec::with_capacity(16);
 
     loop {
         let mut client = listener.accept()?.0;
         client.set_read_timeout(Some(Duration::from_secs(2))).ok();
         client.set_write_timeout(Some(Duration::from_secs(2))).ok();
-        client.write_all(&current_bytes).ok();
+        client.write_all(current.as_bytes()).ok();
         client.shutdown(Shutdown::Write).ok();
-        match client.read_exact(&mut read_buf) {
+        read_buf.clear();
+        match client.read_to_end(&mut read_buf) {
+            // Client only read current state
+            Ok(0) => {}
             Ok(_) => {
-                if &read_buf == EXIT_MESSAGE {
+                if read_buf == EXIT_MESSAGE {
                     log::info!("Receive exit message");
                     return Ok(());
                 }
 
-                current_bytes = read_buf;
-
-                handle.update(|tray| {
-                    tray.update_with_bytes(&current_bytes);
-                });
+                match std::str::from_utf8(&read_buf) {
+                    Ok(message) => {
+                        current = message.into();
+                        handle.update(|tray| {
+                            tray.update(&current);
+                        });
+                    }
+                    Err(err) => log::warn!("Invalid category name: {}", err),
+                }
             }
             _ => {}
         }
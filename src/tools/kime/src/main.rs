@@ -32,10 +32,23 @@ fn kill_daemon(pid: &Path) -> io::Result<()> {
     }
 }
 
+fn reload_frontends() -> io::Result<()> {
+    for module in [Module::Xim, Module::Wayland] {
+        // pkill return 1 when there is no matched process
+        Command::new("pkill")
+            .args(["-HUP", "-x", process_name(module)])
+            .spawn()?
+            .wait()?;
+    }
+
+    Ok(())
+}
+
 fn main() -> Result<(), ()> {
     let mut args = kime_version::cli_boilerplate!(
         Ok(()),
         "-k or --kill: kill daemon then exit",
+        "-r or --reload: reload config of running frontends then exit",
         "-D or --no-daemon: don't start as daemon",
     );
 
@@ -48,6 +61,12 @@ fn main() -> Result<(), ()> {
         });
     }
 
+    if args.contains(["-r", "--reload"]) {
+        return reload_frontends().map_err(|err| {
+            log::error!("Can't reload frontends: {}", err);
+        });
+    }
+
     if !args.contains(["-D", "--no-daemon"]) {
         let stderr = run_dir.join("kime.err");
         let stderr_file = match File::create(stderr) {